@@ -1,18 +1,19 @@
-use advent_of_code_2022::{get_data, get_day, get_days};
+use advent_of_code_2022::{find_input, get_data, solutions};
 use criterion::{criterion_group, criterion_main, Criterion};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    for i in get_days() {
-        let (part1, part2, path) = get_day(i);
+    for solution in solutions() {
+        let i = solution.day();
+        let path = find_input(i).unwrap();
         c.bench_function(&format!("day {i} A"), |b| {
             b.iter(|| {
-                part1(get_data(&path));
+                solution.part_one(get_data(&path).unwrap()).unwrap();
             })
         });
 
         c.bench_function(&format!("day {i} B"), |b| {
             b.iter(|| {
-                part2(get_data(&path));
+                solution.part_two(get_data(&path).unwrap()).unwrap();
             })
         });
     }
@@ -0,0 +1,129 @@
+use serde::{Serialize, Serializer};
+use std::{convert::Infallible, fmt::Display, str::FromStr};
+
+/// The solution to one star of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Integer(i64),
+    /// Integers too large to fit in an `i64`.
+    BigInteger(i128),
+    Text(String),
+    /// Rows of characters, such as the CRT output of day 10.
+    Grid(Vec<String>),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Integer(x) => write!(f, "{x}"),
+            Answer::BigInteger(x) => write!(f, "{x}"),
+            Answer::Text(s) => write!(f, "{s}"),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+/// Integers that fit in an `i64` become JSON numbers, larger ones become
+/// strings so that nothing is lost to floating point.
+impl Serialize for Answer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Answer::Integer(x) => serializer.serialize_i64(*x),
+            Answer::BigInteger(x) => serializer.collect_str(x),
+            Answer::Text(s) => serializer.serialize_str(s),
+            Answer::Grid(rows) => rows.serialize(serializer),
+        }
+    }
+}
+
+impl FromStr for Answer {
+    type Err = Infallible;
+
+    /// Picks the narrowest variant that can hold `s`. Parsing the rendered
+    /// form of an answer gives the answer back, except for text that reads
+    /// as a number, such as a SNAFU answer of `"20"`, which comes back as that
+    /// number. [`Answer::from`] keeps any string as text.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if let Ok(x) = s.parse::<i64>() {
+            Answer::Integer(x)
+        } else if let Ok(x) = s.parse::<i128>() {
+            Answer::BigInteger(x)
+        } else {
+            Answer::from(s)
+        })
+    }
+}
+
+impl From<i128> for Answer {
+    fn from(value: i128) -> Self {
+        match i64::try_from(value) {
+            Ok(x) => Answer::Integer(x),
+            Err(_) => Answer::BigInteger(value),
+        }
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Self {
+                    Answer::from(value as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i32, u32, i64, u64, isize, usize);
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        if value.contains('\n') {
+            Answer::Grid(value.lines().map(|line| line.to_string()).collect())
+        } else {
+            Answer::Text(value.to_string())
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::from(value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Answer;
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(Answer::from(42_usize), Answer::Integer(42));
+        assert_eq!(Answer::from(-3_isize), Answer::Integer(-3));
+        assert_eq!(Answer::from(u64::MAX), Answer::BigInteger(u64::MAX as i128));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let answers = [
+            Answer::Integer(-12),
+            Answer::BigInteger(i64::MAX as i128 + 1),
+            Answer::Text("2=-1=0".to_string()),
+            Answer::Grid(vec!["#..#".to_string(), ".##.".to_string()]),
+        ];
+        for answer in answers {
+            assert_eq!(answer.to_string().parse::<Answer>(), Ok(answer));
+        }
+    }
+
+    #[test]
+    fn test_text_that_looks_like_a_number() {
+        let answer = Answer::Text("20".to_string());
+        assert_eq!(
+            answer.to_string().parse::<Answer>(),
+            Ok(Answer::Integer(20))
+        );
+        assert_eq!(Answer::from(answer.to_string()), answer);
+    }
+}
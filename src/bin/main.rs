@@ -1,26 +1,933 @@
-use std::env;
-use std::fs;
-
-use std::io::BufReader;
+use std::{
+    io::{self, BufRead, Cursor, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use advent_of_code_2022::*;
+use clap::{error::ErrorKind, ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use serde::Serialize;
+use tracing::info_span;
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Solutions to Advent of Code 2022.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// How results are printed.
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+
+    /// How many threads to solve days on. Defaults to one per CPU, except
+    /// with `run --stats`, which always uses one.
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Give up on a part after this many seconds and report it as TIMEOUT.
+    /// Only the slow searches check for this, the rest always finish.
+    #[arg(long, global = true, value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Log what the solvers are doing to stderr: `-v` for debugging output,
+    /// `-vv` for every step. `RUST_LOG` takes the same directives.
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Log every step of one day, such as `day22`, however verbose the rest.
+    /// May be given more than once.
+    #[arg(long, global = true, value_name = "DAY", value_parser = parse_trace_day)]
+    trace: Vec<usize>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    /// One JSON object per line, one line per part.
+    Jsonl,
+    /// A header row, then one row per part.
+    Csv,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve a single day, or every day with `all`.
+    Run {
+        /// A day from 1 to 25, or `all`.
+        #[arg(value_parser = parse_days)]
+        days: Days,
+
+        /// Only solve this part of the puzzle.
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: Option<u8>,
+
+        /// Read the puzzle input from this file, or `-` for stdin. Otherwise
+        /// `dayNN.txt` is looked for in $AOC_DATA_DIR, ./data and the
+        /// per-user data directory.
+        #[arg(long, value_name = "PATH")]
+        input: Option<PathBuf>,
+
+        /// Report the time and peak heap use of each part. Parts run one at a
+        /// time, so that each peak is the part's own.
+        #[arg(long)]
+        stats: bool,
+
+        /// List the N elves carrying the most calories, and what each of them
+        /// carries, instead of solving. Only for day 1.
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with_all = ["part", "stats"],
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        top: Option<usize>,
+    },
+    /// Check answers against a manifest of known answers.
+    Verify {
+        /// A day from 1 to 25, or `all`.
+        #[arg(value_parser = parse_days, default_value = "all")]
+        days: Days,
+
+        /// Read the known answers from this file instead of looking for
+        /// answers.toml next to the inputs.
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+    },
+    /// Check a puzzle input without solving it, reporting every problem
+    /// found.
+    Validate {
+        /// A day from 1 to 25, or `all`.
+        #[arg(value_parser = parse_days)]
+        days: Days,
+
+        /// Read the puzzle input from this file, or `-` for stdin, as for
+        /// `run`.
+        #[arg(long, value_name = "PATH")]
+        input: Option<PathBuf>,
+    },
+    /// List the days that have solutions.
+    List,
+}
+
+#[derive(Clone)]
+enum Days {
+    All,
+    Day(usize),
+}
+
+fn parse_days(s: &str) -> Result<Days, String> {
+    if s == "all" {
+        return Ok(Days::All);
+    }
+    let day = s
+        .parse::<usize>()
+        .map_err(|_| format!("expected a day from 1 to 25 or `all`, found `{s}`"))?;
+    get_day(day)
+        .map(|_| Days::Day(day))
+        .ok_or_else(|| format!("there is no solution for day {day}"))
+}
+
+/// A day to trace, as `day22` or just `22`.
+fn parse_trace_day(s: &str) -> Result<usize, String> {
+    let day = s
+        .strip_prefix("day")
+        .unwrap_or(s)
+        .parse::<usize>()
+        .map_err(|_| format!("expected a day such as `day22`, found `{s}`"))?;
+    get_day(day)
+        .map(|_| day)
+        .ok_or_else(|| format!("there is no solution for day {day}"))
+}
+
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| format!("expected a positive number of seconds, found `{s}`"))
+}
+
+/// Where the puzzle input for a run comes from.
+enum Input {
+    /// Search the usual places for the day's input.
+    Default,
+    File(PathBuf),
+    /// Stdin read as it is needed, for a run that only reads it once.
+    Stdin,
+    /// Stdin can only be read once, so a run that reads it for several parts
+    /// reads it up front and shares it between them.
+    Buffered(Arc<[u8]>),
+}
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
-    let days = if args.len() == 1 && args[0] == "all" {
-        (1..=25).map(|x| x.to_string()).collect()
+impl Input {
+    /// Picks the days to run and where their input comes from. A file or
+    /// stdin only makes sense for a single day, whose input is opened once
+    /// for each of `reads`.
+    fn select(
+        days: Days,
+        input: Option<PathBuf>,
+        reads: usize,
+    ) -> Result<(Vec<&'static dyn Solution>, Input), AocError> {
+        let selected = match days {
+            Days::All => {
+                if input.is_some() {
+                    Cli::command()
+                        .error(
+                            ErrorKind::ArgumentConflict,
+                            "--input can only be used with a single day",
+                        )
+                        .exit();
+                }
+                solutions().collect()
+            }
+            Days::Day(day) => get_day(day).into_iter().collect(),
+        };
+        let input = match input {
+            None => Input::Default,
+            Some(path) if path.as_os_str() == "-" && reads <= 1 => Input::Stdin,
+            Some(path) if path.as_os_str() == "-" => {
+                let mut bytes = Vec::new();
+                io::stdin().read_to_end(&mut bytes)?;
+                Input::Buffered(bytes.into())
+            }
+            Some(path) => Input::File(path),
+        };
+        Ok((selected, input))
+    }
+
+    /// Opens the input for `solution` to be read as it is needed, afresh for
+    /// each part, so that days which stream their input never hold all of
+    /// it.
+    fn open(&self, solution: &dyn Solution) -> Result<Box<dyn BufRead>, AocError> {
+        match self {
+            Input::Default => get_data(&find_input(solution.day())?),
+            Input::File(path) => get_data(path),
+            Input::Stdin => get_data(Path::new("-")),
+            // Each part gets its own cursor over the one shared copy.
+            Input::Buffered(bytes) => Ok(Box::new(Normalized::new(Cursor::new(Arc::clone(bytes))))),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DayReport {
+    day: usize,
+    title: &'static str,
+    parts: Vec<PartReport>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Ok,
+    Error,
+    /// The part ran out of time and was cancelled.
+    Timeout,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::Error => "error",
+            Outcome::Timeout => "timeout",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PartReport {
+    part: u8,
+    status: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    answer: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<Stats>,
+    /// Always measured, for the line-based formats.
+    #[serde(skip)]
+    duration: Duration,
+}
+
+/// A flattened [`PartReport`] for the line-based formats.
+#[derive(Serialize)]
+struct PartRecord<'a> {
+    day: usize,
+    part: u8,
+    status: Outcome,
+    answer: Option<&'a Answer>,
+    seconds: f64,
+    error: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Stats {
+    seconds: f64,
+    peak_heap_bytes: usize,
+}
+
+impl From<Measurement> for Stats {
+    fn from(value: Measurement) -> Self {
+        Stats {
+            seconds: value.duration.as_secs_f64(),
+            peak_heap_bytes: value.peak_heap,
+        }
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let duration = Duration::from_secs_f64(seconds);
+    if duration < Duration::from_millis(1) {
+        format!("{:.1}µs", seconds * 1e6)
+    } else if duration < Duration::from_secs(1) {
+        format!("{:.1}ms", seconds * 1e3)
+    } else {
+        format!("{seconds:.2}s")
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes}B")
     } else {
-        args
+        format!("{value:.1}{}", UNITS[unit])
+    }
+}
+
+/// Solves `parts` of each of `selected` on the rayon thread pool. Each part
+/// reads its own input, and the reading counts towards its stats. The
+/// reports are in the same order as `selected`.
+fn solve(
+    selected: &[&'static dyn Solution],
+    parts: &[u8],
+    input: &Input,
+    stats: bool,
+    timeout: Option<Duration>,
+) -> Vec<DayReport> {
+    let tasks: Vec<_> = selected
+        .iter()
+        .flat_map(|solution| parts.iter().map(move |&part| (*solution, part)))
+        .collect();
+
+    let mut results = tasks
+        .into_par_iter()
+        .map(|(solution, part)| {
+            let token = timeout
+                .map(CancellationToken::with_timeout)
+                .unwrap_or_default();
+            // Days solve in parallel, so their log messages say which is which.
+            let _span = info_span!("solve", day = solution.day(), part).entered();
+            let (result, measurement) = token.run(|| {
+                measure(|| {
+                    input
+                        .open(solution)
+                        .and_then(|reader| solution.solve(part, reader))
+                })
+            });
+            let (status, answer, error) = match result {
+                Ok(answer) => (Outcome::Ok, Some(answer), None),
+                Err(AocError::Cancelled) => (
+                    Outcome::Timeout,
+                    None,
+                    Some(format!(
+                        "timed out after {}",
+                        format_duration(timeout.unwrap_or_default().as_secs_f64())
+                    )),
+                ),
+                Err(e) => (Outcome::Error, None, Some(e.to_string())),
+            };
+            PartReport {
+                part,
+                status,
+                answer,
+                error,
+                stats: stats.then(|| measurement.into()),
+                duration: measurement.duration,
+            }
+        })
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    selected
+        .iter()
+        .map(|solution| DayReport {
+            day: solution.day(),
+            title: solution.title(),
+            parts: results.by_ref().take(parts.len()).collect(),
+        })
+        .collect()
+}
+
+/// `summary` is the wall-clock time of the whole run, when a summary table
+/// should be printed.
+fn print_reports(reports: &[DayReport], format: Format, summary: Option<Duration>) {
+    match format {
+        Format::Text => {
+            for report in reports {
+                println!("Day {}: {}", report.day, report.title);
+                for part in &report.parts {
+                    let star = if part.part == 1 { "One" } else { "Two" };
+                    let stats = part
+                        .stats
+                        .as_ref()
+                        .map(|stats| {
+                            format!(
+                                " ({}, {} peak heap)",
+                                format_duration(stats.seconds),
+                                format_bytes(stats.peak_heap_bytes)
+                            )
+                        })
+                        .unwrap_or_default();
+                    match (part.status, &part.answer, &part.error) {
+                        (Outcome::Timeout, _, _) => println!("Star {star}: TIMEOUT{stats}"),
+                        (_, Some(answer), _) => println!("Star {star}: {answer}{stats}"),
+                        (_, _, Some(error)) => println!("Star {star} failed: {error}{stats}"),
+                        _ => {}
+                    }
+                }
+            }
+            if let Some(wall) = summary {
+                print_summary(reports, wall);
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(reports).unwrap()),
+        Format::Jsonl | Format::Csv => {
+            let records = reports.iter().flat_map(|report| {
+                report.parts.iter().map(|part| PartRecord {
+                    day: report.day,
+                    part: part.part,
+                    status: part.status,
+                    answer: part.answer.as_ref(),
+                    seconds: part.duration.as_secs_f64(),
+                    error: part.error.as_deref(),
+                })
+            });
+            if let Format::Jsonl = format {
+                print_jsonl(records);
+            } else {
+                print_csv(
+                    &["day", "part", "status", "answer", "seconds", "error"],
+                    records.map(|r| {
+                        vec![
+                            r.day.to_string(),
+                            r.part.to_string(),
+                            r.status.as_str().to_string(),
+                            r.answer.map(Answer::to_string).unwrap_or_default(),
+                            r.seconds.to_string(),
+                            r.error.unwrap_or_default().to_string(),
+                        ]
+                    }),
+                );
+            }
+        }
+    }
+}
+
+fn print_jsonl<T: Serialize>(records: impl IntoIterator<Item = T>) {
+    for record in records {
+        println!("{}", serde_json::to_string(&record).unwrap());
+    }
+}
+
+/// Grid answers span several lines, so fields are quoted where needed.
+fn print_csv(header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) {
+    let mut writer = csv::Writer::from_writer(io::stdout().lock());
+    let result = writer
+        .write_record(header)
+        .and_then(|_| {
+            rows.into_iter()
+                .try_for_each(|row| writer.write_record(row))
+        })
+        .and_then(|_| writer.flush().map_err(csv::Error::from));
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+    }
+}
+
+/// A table of the stats for every part, with the total time and the largest
+/// peak heap at the bottom. Parts run in parallel, so the wall-clock time can
+/// be less than the total.
+fn print_summary(reports: &[DayReport], wall: Duration) {
+    let rows: Vec<_> = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .parts
+                .iter()
+                .filter_map(move |part| part.stats.as_ref().map(|stats| (report.day, part, stats)))
+        })
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{:>3} {:>4} {:>10} {:>10}  Status",
+        "Day", "Part", "Time", "Peak heap"
+    );
+    for (day, part, stats) in &rows {
+        println!(
+            "{:>3} {:>4} {:>10} {:>10}  {}",
+            day,
+            part.part,
+            format_duration(stats.seconds),
+            format_bytes(stats.peak_heap_bytes),
+            match part.status {
+                Outcome::Timeout => "TIMEOUT",
+                status => status.as_str(),
+            }
+        );
+    }
+    let total_time = rows.iter().map(|(_, _, stats)| stats.seconds).sum();
+    let max_heap = rows
+        .iter()
+        .map(|(_, _, stats)| stats.peak_heap_bytes)
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:>8} {:>10} {:>10}",
+        "Total",
+        format_duration(total_time),
+        format_bytes(max_heap)
+    );
+    println!("{:>8} {:>10}", "Wall", format_duration(wall.as_secs_f64()));
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Verdict {
+    Pass,
+    Fail,
+    /// There is no known answer to check against.
+    Missing,
+}
+
+impl Verdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Pass => "pass",
+            Verdict::Fail => "fail",
+            Verdict::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Verification {
+    day: usize,
+    part: u8,
+    status: Verdict,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    answer: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn verify(reports: Vec<DayReport>, manifest: &Manifest) -> Vec<Verification> {
+    reports
+        .into_iter()
+        .flat_map(|report| {
+            report.parts.into_iter().map(move |part| {
+                let expected = manifest.expected(report.day, part.part).cloned();
+                let status = match (&part.answer, &expected) {
+                    (None, _) => Verdict::Fail,
+                    (Some(_), None) => Verdict::Missing,
+                    (Some(answer), Some(expected)) if answer == expected => Verdict::Pass,
+                    (Some(_), Some(_)) => Verdict::Fail,
+                };
+                Verification {
+                    day: report.day,
+                    part: part.part,
+                    status,
+                    answer: part.answer,
+                    expected,
+                    error: part.error,
+                }
+            })
+        })
+        .collect()
+}
+
+fn print_verifications(verifications: &[Verification], format: Format) {
+    match format {
+        Format::Text => {
+            for v in verifications {
+                let prefix = format!("Day {:>2} part {}", v.day, v.part);
+                match (&v.status, &v.answer, &v.expected, &v.error) {
+                    (Verdict::Pass, _, _, _) => println!("{prefix}: pass"),
+                    (Verdict::Missing, Some(answer), _, _) => {
+                        println!("{prefix}: missing, got {answer}")
+                    }
+                    (_, _, _, Some(error)) => println!("{prefix}: fail, {error}"),
+                    (_, Some(answer), Some(expected), _) => {
+                        println!("{prefix}: fail, expected {expected}, got {answer}")
+                    }
+                    _ => println!("{prefix}: fail"),
+                }
+            }
+            let count =
+                |status: Verdict| verifications.iter().filter(|v| v.status == status).count();
+            println!(
+                "{} passed, {} failed, {} missing",
+                count(Verdict::Pass),
+                count(Verdict::Fail),
+                count(Verdict::Missing)
+            );
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(verifications).unwrap()),
+        Format::Jsonl => print_jsonl(verifications),
+        Format::Csv => print_csv(
+            &["day", "part", "status", "answer", "expected", "error"],
+            verifications.iter().map(|v| {
+                vec![
+                    v.day.to_string(),
+                    v.part.to_string(),
+                    v.status.as_str().to_string(),
+                    v.answer.as_ref().map(Answer::to_string).unwrap_or_default(),
+                    v.expected
+                        .as_ref()
+                        .map(Answer::to_string)
+                        .unwrap_or_default(),
+                    v.error.clone().unwrap_or_default(),
+                ]
+            }),
+        ),
+    }
+}
+
+#[derive(Serialize)]
+struct Validation {
+    day: usize,
+    title: &'static str,
+    valid: bool,
+    /// Everything wrong with the input, including failing to read it.
+    problems: Vec<String>,
+}
+
+fn validate(selected: &[&'static dyn Solution], input: &Input) -> Vec<Validation> {
+    selected
+        .par_iter()
+        .map(|solution| {
+            let result = input
+                .open(*solution)
+                .and_then(|reader| AocError::catch_panic(|| solution.validate(reader)));
+            let problems = match &result {
+                Ok(()) => Vec::new(),
+                Err(e) => e.problems().iter().map(|p| p.to_string()).collect(),
+            };
+            Validation {
+                day: solution.day(),
+                title: solution.title(),
+                valid: problems.is_empty(),
+                problems,
+            }
+        })
+        .collect()
+}
+
+fn print_validations(validations: &[Validation], format: Format) {
+    match format {
+        Format::Text => {
+            for v in validations {
+                let prefix = format!("Day {:>2}: {}", v.day, v.title);
+                match v.problems.as_slice() {
+                    [] => println!("{prefix}: ok"),
+                    [problem] => println!("{prefix}: {problem}"),
+                    problems => {
+                        println!("{prefix}: {} problems", problems.len());
+                        for problem in problems {
+                            println!("  {problem}");
+                        }
+                    }
+                }
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(validations).unwrap()),
+        Format::Jsonl => print_jsonl(validations),
+        // One row per problem, or a single row for a valid input.
+        Format::Csv => print_csv(
+            &["day", "valid", "problem"],
+            validations.iter().flat_map(|v| {
+                let problems = if v.problems.is_empty() {
+                    vec![String::new()]
+                } else {
+                    v.problems.clone()
+                };
+                problems
+                    .into_iter()
+                    .map(|problem| vec![v.day.to_string(), v.valid.to_string(), problem])
+            }),
+        ),
+    }
+}
+
+fn list(format: Format) {
+    match format {
+        Format::Text => {
+            for solution in solutions() {
+                println!("{:>2}: {}", solution.day(), solution.title());
+            }
+        }
+        Format::Json | Format::Jsonl => {
+            #[derive(Serialize)]
+            struct Entry {
+                day: usize,
+                title: &'static str,
+            }
+            let entries = solutions().map(|solution| Entry {
+                day: solution.day(),
+                title: solution.title(),
+            });
+            if let Format::Json = format {
+                let entries: Vec<_> = entries.collect();
+                println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+            } else {
+                print_jsonl(entries);
+            }
+        }
+        Format::Csv => print_csv(
+            &["day", "title"],
+            solutions()
+                .map(|solution| vec![solution.day().to_string(), solution.title().to_string()]),
+        ),
+    }
+}
+
+/// One of the elves carrying the most calories on day 1.
+#[derive(Serialize)]
+struct TopElf {
+    rank: usize,
+    /// Which elf this is, counting from one.
+    elf: usize,
+    total: usize,
+    items: Vec<usize>,
+}
+
+/// The `k` elves carrying the most calories, streamed from the day 1 input.
+fn top_elves(input: &Input, k: usize) -> Result<Vec<TopElf>, AocError> {
+    let solution = get_day(1).expect("day 1 is solved");
+    let top = itertools::process_results(day01::elves(input.open(solution)?), |elves| {
+        day01::top(elves, k)
+    })?;
+    Ok(top
+        .into_iter()
+        .zip(1..)
+        .map(|(elf, rank)| TopElf {
+            rank,
+            elf: elf.number,
+            total: elf.total(),
+            items: elf.items,
+        })
+        .collect())
+}
+
+fn print_top_elves(elves: &[TopElf], format: Format) {
+    match format {
+        Format::Text => {
+            for elf in elves {
+                let items = elf.items.iter().map(usize::to_string).collect::<Vec<_>>();
+                println!(
+                    "{:>3}. Elf {}: {} ({})",
+                    elf.rank,
+                    elf.elf,
+                    elf.total,
+                    items.join(" + ")
+                );
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(elves).unwrap()),
+        Format::Jsonl => print_jsonl(elves),
+        // The items are space separated within their field.
+        Format::Csv => print_csv(
+            &["rank", "elf", "total", "items"],
+            elves.iter().map(|elf| {
+                let items = elf.items.iter().map(usize::to_string).collect::<Vec<_>>();
+                vec![
+                    elf.rank.to_string(),
+                    elf.elf.to_string(),
+                    elf.total.to_string(),
+                    items.join(" "),
+                ]
+            }),
+        ),
+    }
+}
+
+/// Sends log messages to stderr, so they never get mixed up with results.
+/// Each day logs under its own module, such as `advent_of_code_2022::day22`.
+fn init_logging(verbose: u8, trace: &[usize]) {
+    let level = match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
     };
-    for day in days {
-        println!("Day {day}:");
-
-        let (star_one, star_two, filename) = get_day(day.parse::<usize>().unwrap());
-        let f = fs::File::open(&filename).unwrap();
-        let input = BufReader::new(f);
-        println!("Star One: {}", star_one(Box::new(input)));
-        let f = fs::File::open(&filename).unwrap();
-        let input = BufReader::new(f);
-        println!("Star Two: {}", star_two(Box::new(input)));
+    // The spans saying which day a message came from are always wanted.
+    let mut filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy()
+        .add_directive(
+            format!("{}=info", module_path!())
+                .parse()
+                .expect("a module path is a valid directive"),
+        );
+    for day in trace {
+        filter = filter.add_directive(
+            format!("advent_of_code_2022::day{day:02}=trace")
+                .parse()
+                .expect("a module path is a valid directive"),
+        );
+    }
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        // Colour codes would end up in logs redirected to a file.
+        .with_ansi(io::stderr().is_terminal())
+        .init();
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logging(cli.verbose, &cli.trace);
+
+    // The heap is shared, so parts solved side by side would count each
+    // other's allocations in their peaks.
+    let stats = matches!(cli.command, Command::Run { stats: true, .. });
+    if stats && cli.threads.is_some_and(|threads| threads > 1) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--stats runs one part at a time, so --threads can only be 1",
+            )
+            .exit();
+    }
+    if let Some(threads) = if stats { Some(1) } else { cli.threads } {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.into())
+            .build_global()
+            .expect("the thread pool is only built once");
+    }
+
+    match cli.command {
+        Command::List => {
+            list(cli.format);
+            ExitCode::SUCCESS
+        }
+        Command::Verify { days, manifest } => {
+            let manifest = match Manifest::load(manifest.as_deref()) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    eprintln!("error: could not load the manifest: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let selected: Vec<_> = match days {
+                Days::All => solutions().collect(),
+                Days::Day(day) => get_day(day).into_iter().collect(),
+            };
+            let reports = solve(&selected, &[1, 2], &Input::Default, false, cli.timeout);
+            let verifications = verify(reports, &manifest);
+            print_verifications(&verifications, cli.format);
+
+            if verifications.iter().any(|v| v.status == Verdict::Fail) {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Command::Validate { days, input } => {
+            let (selected, input) = match Input::select(days, input, 1) {
+                Ok(selection) => selection,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let validations = validate(&selected, &input);
+            print_validations(&validations, cli.format);
+
+            if validations.iter().any(|v| !v.valid) {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Command::Run {
+            days,
+            part,
+            input,
+            stats,
+            top,
+        } => {
+            if top.is_some() && !matches!(days, Days::Day(1)) {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--top can only be used with day 1",
+                    )
+                    .exit();
+            }
+            let summary = matches!(days, Days::All);
+            let parts = match part {
+                Some(part) => vec![part],
+                None => vec![1, 2],
+            };
+            // Listing the top elves reads the input once, whatever the parts.
+            let reads = if top.is_some() { 1 } else { parts.len() };
+            let (selected, input) = match Input::select(days, input, reads) {
+                Ok(selection) => selection,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            if let Some(k) = top {
+                return match top_elves(&input, k) {
+                    Ok(elves) => {
+                        print_top_elves(&elves, cli.format);
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => {
+                        eprintln!("error: {e}");
+                        ExitCode::FAILURE
+                    }
+                };
+            }
+
+            let start = Instant::now();
+            let reports = solve(&selected, &parts, &input, stats, cli.timeout);
+            let wall = start.elapsed();
+            print_reports(&reports, cli.format, (summary && stats).then_some(wall));
+
+            if reports
+                .iter()
+                .flat_map(|report| &report.parts)
+                .any(|part| part.status != Outcome::Ok)
+            {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
     }
 }
@@ -0,0 +1,138 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::AocError;
+
+/// How many calls to [`checkpoint`] go by between looks at the clock.
+const CHECK_INTERVAL: u32 = 1024;
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+    static CALLS: Cell<u32> = const { Cell::new(0) };
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+/// Tells long-running solvers to give up, either when asked to or once a
+/// deadline passes. Solvers check it cooperatively with [`checkpoint`], so
+/// days that never call it always run to completion.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// A token that cancels itself `timeout` from now.
+    pub fn with_timeout(timeout: Duration) -> CancellationToken {
+        CancellationToken {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                deadline: Some(Instant::now() + timeout),
+            }),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        if self.inner.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self
+            .inner
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.cancel();
+            return true;
+        }
+        false
+    }
+
+    pub fn check(&self) -> Result<(), AocError> {
+        if self.is_cancelled() {
+            Err(AocError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs `f` with this as the token [`checkpoint`] looks at on the current
+    /// thread.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        let result = f();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+        result
+    }
+}
+
+/// The token in effect on this thread, for handing to worker threads.
+pub fn current_token() -> Option<CancellationToken> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Returns [`AocError::Cancelled`] if the current thread's token has been
+/// cancelled. Cheap enough to call in a search's inner loop, as the clock is
+/// only read every so often.
+pub fn checkpoint() -> Result<(), AocError> {
+    let calls = CALLS.with(|calls| {
+        let next = calls.get().wrapping_add(1);
+        calls.set(next);
+        next
+    });
+    if !calls.is_multiple_of(CHECK_INTERVAL) {
+        return Ok(());
+    }
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(token) => token.check(),
+        None => Ok(()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checkpoint, CancellationToken};
+    use crate::AocError;
+    use std::time::Duration;
+
+    #[test]
+    fn test_checkpoint() {
+        let token = CancellationToken::new();
+        let result = token.run(|| {
+            for i in 0.. {
+                if i == 10 {
+                    token.cancel();
+                }
+                checkpoint()?;
+            }
+            Ok(())
+        });
+        assert!(matches!(result, Err(AocError::Cancelled)));
+        // Outside of `run` nothing is cancelled.
+        assert!((0..10_000).all(|_| checkpoint().is_ok()));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let token = CancellationToken::with_timeout(Duration::ZERO);
+        assert!(token.is_cancelled());
+        assert!(!CancellationToken::new().is_cancelled());
+    }
+}
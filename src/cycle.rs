@@ -0,0 +1,162 @@
+use crate::{checkpoint, AocError};
+use std::{collections::HashMap, hash::Hash};
+
+/// A simulation that has settled into repeating itself: after `prefix`
+/// steps it comes back to the same state every `period` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub prefix: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// How much a metric grows each time round the cycle. `history[i]` is
+    /// its value after `i` steps, and must run at least one full cycle past
+    /// the prefix.
+    pub fn delta(&self, history: &[i64]) -> i64 {
+        history[self.prefix + self.period] - history[self.prefix]
+    }
+
+    /// The value of a metric after `n` steps, worked out from its `history`
+    /// rather than by running that far.
+    pub fn extrapolate(&self, history: &[i64], n: usize) -> i64 {
+        if n < history.len() {
+            return history[n];
+        }
+        let cycles = (n - self.prefix) / self.period;
+        let offset = (n - self.prefix) % self.period;
+        history[self.prefix + offset] + cycles as i64 * self.delta(history)
+    }
+}
+
+/// Watches the states of a simulation go by, one per step, until one comes
+/// round again.
+#[derive(Debug, Clone)]
+pub struct CycleDetector<K> {
+    /// The step each state was first seen at.
+    seen: HashMap<K, usize>,
+    steps: usize,
+}
+
+impl<K: Hash + Eq> Default for CycleDetector<K> {
+    fn default() -> Self {
+        CycleDetector {
+            seen: HashMap::new(),
+            steps: 0,
+        }
+    }
+}
+
+impl<K: Hash + Eq> CycleDetector<K> {
+    pub fn new() -> CycleDetector<K> {
+        CycleDetector::default()
+    }
+
+    /// Records the state after the next step, starting with the state
+    /// before any steps. Returns the cycle once a state repeats.
+    pub fn observe(&mut self, key: K) -> Option<Cycle> {
+        let step = self.steps;
+        self.steps += 1;
+        match self.seen.get(&key) {
+            Some(&first) => Some(Cycle {
+                prefix: first,
+                period: step - first,
+            }),
+            None => {
+                self.seen.insert(key, step);
+                None
+            }
+        }
+    }
+}
+
+/// The value of `metric` after `n` calls to `step`, skipping ahead once the
+/// simulation starts repeating. `key` must capture everything that decides
+/// how the simulation carries on, or the cycle found may not be real.
+pub fn metric_after<S, K: Hash + Eq>(
+    state: &mut S,
+    n: usize,
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> i64,
+    mut step: impl FnMut(&mut S),
+) -> Result<i64, AocError> {
+    let mut detector = CycleDetector::new();
+    let mut history = Vec::new();
+    loop {
+        checkpoint()?;
+        history.push(metric(state));
+        if history.len() > n {
+            return Ok(history[n]);
+        }
+        if let Some(cycle) = detector.observe(key(state)) {
+            return Ok(cycle.extrapolate(&history, n));
+        }
+        step(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{metric_after, Cycle, CycleDetector};
+
+    #[test]
+    fn test_detector() {
+        // 0, 1, 2, 3, 4, 2, 3, 4, ...
+        let mut detector = CycleDetector::new();
+        let states = [0, 1, 2, 3, 4, 2];
+        let found = states
+            .iter()
+            .map(|state| detector.observe(*state))
+            .collect::<Vec<_>>();
+        assert!(found[..5].iter().all(Option::is_none));
+        assert_eq!(
+            found[5],
+            Some(Cycle {
+                prefix: 2,
+                period: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let cycle = Cycle {
+            prefix: 2,
+            period: 3,
+        };
+        // Grows by 1, 2, 4 each time round after a prefix of 10, 20.
+        let history = [10, 20, 21, 23, 27, 28];
+        assert_eq!(cycle.delta(&history), 7);
+        assert_eq!(cycle.extrapolate(&history, 4), 27);
+        assert_eq!(cycle.extrapolate(&history, 6), 30);
+        assert_eq!(cycle.extrapolate(&history, 7), 34);
+        assert_eq!(cycle.extrapolate(&history, 2 + 3 * 1000), 21 + 7 * 1000);
+    }
+
+    #[test]
+    fn test_metric_after() {
+        // A counter that wraps at 7, and a total of everything it has shown.
+        let run = |n| {
+            let mut state = (0u32, 0i64);
+            metric_after(
+                &mut state,
+                n,
+                |&(counter, _)| counter,
+                |&(_, total)| total,
+                |(counter, total)| {
+                    *counter = (*counter + 1) % 7;
+                    *total += *counter as i64;
+                },
+            )
+            .unwrap()
+        };
+        let brute_force = |n: i64| (1..=n).map(|i| i % 7).sum::<i64>();
+        for n in [0, 1, 6, 7, 20, 1000] {
+            assert_eq!(run(n as usize), brute_force(n));
+        }
+        // Each time round adds 21.
+        let n = 1_000_000_000_000;
+        let (cycles, rest) = (n / 7, n % 7);
+        assert_eq!(run(n as usize), 21 * cycles + rest * (rest + 1) / 2);
+    }
+}
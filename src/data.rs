@@ -0,0 +1,168 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::AocError;
+
+/// Overrides every other location when set.
+pub const DATA_DIR_VAR: &str = "AOC_DATA_DIR";
+
+/// Opens `filepath` for reading, where `-` means stdin. The input is tidied
+/// up as it is read, as described on [`Normalized`].
+pub fn get_data(filepath: &Path) -> Result<Box<dyn BufRead>, AocError> {
+    if filepath.as_os_str() == "-" {
+        return Ok(Box::new(Normalized::new(BufReader::new(io::stdin()))));
+    }
+    match fs::File::open(filepath) {
+        Ok(f) => Ok(Box::new(Normalized::new(BufReader::new(f)))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Err(AocError::MissingFile {
+            searched: vec![filepath.to_path_buf()],
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads an input one line at a time, turning `\r\n` into `\n` and
+/// dropping trailing whitespace from each line and blank lines from the end.
+/// Inputs saved on Windows or pasted from a browser then read the same as
+/// any other.
+pub struct Normalized<R> {
+    inner: R,
+    /// The tidied line being handed out, and how much of it has been read.
+    line: Vec<u8>,
+    pos: usize,
+    /// Blank lines seen since the last line with something on it.
+    blank: usize,
+}
+
+impl<R: BufRead> Normalized<R> {
+    pub fn new(inner: R) -> Normalized<R> {
+        Normalized {
+            inner,
+            line: Vec::new(),
+            pos: 0,
+            blank: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Normalized<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Normalized<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.line.len() {
+            self.line.clear();
+            self.pos = 0;
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                break;
+            }
+            let ended = self.line.ends_with(b"\n");
+            self.line.truncate(self.line.trim_ascii_end().len());
+            if self.line.is_empty() {
+                self.blank += 1;
+                continue;
+            }
+            let text = std::mem::take(&mut self.line);
+            self.line = vec![b'\n'; std::mem::take(&mut self.blank)];
+            self.line.extend(text);
+            if ended {
+                self.line.push(b'\n');
+            }
+        }
+        Ok(&self.line[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.line.len());
+    }
+}
+
+/// Every place the input for `day` is looked for, in order:
+///
+/// 1. `$AOC_DATA_DIR`
+/// 2. `data/` in the current directory
+/// 3. the per-user data directory, e.g. `~/.local/share/advent-of-code-2022`
+/// 4. `data/` in this crate's source tree
+pub fn input_search_paths(day: usize) -> Vec<PathBuf> {
+    search_paths(&format!("day{day:02}.txt"), |key| env::var_os(key))
+}
+
+/// The first of [`input_search_paths`] that exists.
+pub fn find_input(day: usize) -> Result<PathBuf, AocError> {
+    find_data_file(&format!("day{day:02}.txt"))
+}
+
+/// Looks for `filename` in the same places as the puzzle inputs.
+pub fn find_data_file(filename: &str) -> Result<PathBuf, AocError> {
+    let searched = search_paths(filename, |key| env::var_os(key));
+    searched
+        .iter()
+        .find(|path| path.is_file())
+        .cloned()
+        .ok_or(AocError::MissingFile { searched })
+}
+
+fn search_paths(filename: &str, var: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = var(DATA_DIR_VAR) {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs.push(PathBuf::from("data"));
+    if let Some(dir) = user_data_dir(&var) {
+        dirs.push(dir.join(env!("CARGO_PKG_NAME")));
+    }
+    dirs.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("data"));
+    dirs.into_iter().map(|dir| dir.join(filename)).collect()
+}
+
+/// Follows the XDG base directory spec, with `%APPDATA%` for Windows.
+fn user_data_dir(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+        .or_else(|| var("APPDATA").map(PathBuf::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search_paths, Normalized, DATA_DIR_VAR};
+    use std::{ffi::OsString, io::Read, path::PathBuf};
+
+    #[test]
+    fn test_search_order() {
+        let paths = search_paths("day07.txt", |key| match key {
+            DATA_DIR_VAR => Some(OsString::from("/inputs")),
+            "HOME" => Some(OsString::from("/home/elf")),
+            _ => None,
+        });
+        assert_eq!(paths[0], PathBuf::from("/inputs/day07.txt"));
+        assert_eq!(paths[1], PathBuf::from("data/day07.txt"));
+        assert_eq!(
+            paths[2],
+            PathBuf::from("/home/elf/.local/share/advent-of-code-2022/day07.txt")
+        );
+        assert_eq!(paths.len(), 4);
+    }
+
+    #[test]
+    fn test_normalized() {
+        let mut read = String::new();
+        // Saved on Windows, with trailing spaces and blank lines at the end.
+        Normalized::new("a  \r\n\r\n  b\r\n\r\n \r\n".as_bytes())
+            .read_to_string(&mut read)
+            .unwrap();
+        assert_eq!(read, "a\n\n  b\n");
+    }
+}
@@ -1,33 +1,169 @@
-use std::io::BufRead;
-
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    buf.split("\n\n")
-        .map(|elve| {
-            elve.lines()
-                .map(|x| x.parse::<usize>().unwrap())
-                .sum::<usize>()
-        })
-        .max()
-        .unwrap()
-        .to_string()
-}
-
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let mut elves = buf
-        .split("\n\n")
-        .map(|elve| elve.lines().map(|x| x.parse::<usize>().unwrap()).sum())
-        .collect::<Vec<_>>();
-    elves.sort();
-    elves.iter().rev().take(3).sum::<usize>().to_string()
+//! Day 1: Calorie Counting
+//!
+//! Each elf writes down the calories of every food they carry, with a blank
+//! line between elves. [`parse`] reads each elf's inventory as an [`Elf`],
+//! and [`part_one`] and [`part_two`] find who is carrying the most. The elves
+//! can also be streamed from [`elves`] into [`top`], so the whole list never
+//! has to be in memory.
+
+use crate::{input::parse_lines, Answer, AocError, Solution};
+use itertools::{process_results, Itertools};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    io::BufRead,
+};
+
+/// One elf's inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elf {
+    /// Which elf this is, counting from one as the puzzle does.
+    pub number: usize,
+    /// The calories of each food, in the order they were written down.
+    pub items: Vec<usize>,
+}
+
+impl Elf {
+    /// The calories of all this elf's food.
+    pub fn total(&self) -> usize {
+        self.items.iter().sum()
+    }
+}
+
+/// Each elf's inventory, as it is read.
+pub fn elves(input: impl BufRead) -> impl Iterator<Item = Result<Elf, AocError>> {
+    let calories = parse_lines(input, |line| {
+        if line.text.is_empty() {
+            Ok(None)
+        } else {
+            line.parse::<usize>(line.text).map(Some)
+        }
+    });
+    // A blank line ends an elf, and any number of them may come between two.
+    let inventories = calories.batching(|calories| {
+        let mut items = None;
+        for food in calories.by_ref() {
+            match food {
+                Ok(Some(food)) => items.get_or_insert_with(Vec::new).push(food),
+                Ok(None) if items.is_some() => break,
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        items.map(Ok)
+    });
+    inventories
+        .zip(1..)
+        .map(|(items, number)| items.map(|items| Elf { number, items }))
+}
+
+/// Reads each elf's inventory. Every bad line is reported, not just the
+/// first.
+pub fn parse(input: impl BufRead) -> Result<Vec<Elf>, AocError> {
+    AocError::collect(elves(input))
+}
+
+/// An elf ranked by the calories it carries, with the earlier of two elves
+/// carrying the same ranked higher.
+struct Ranked {
+    total: usize,
+    elf: Elf,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.total, Reverse(self.elf.number)).cmp(&(other.total, Reverse(other.elf.number)))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// The `k` elves carrying the most calories, most first. Only those `k` are
+/// kept while the rest go by.
+pub fn top(elves: impl IntoIterator<Item = Elf>, k: usize) -> Vec<Elf> {
+    // The lowest ranked is on top, ready to make way for a better one.
+    // It grows as elves come, so `k` can be far more than there are.
+    let mut heap = BinaryHeap::new();
+    for elf in elves {
+        heap.push(Reverse(Ranked {
+            total: elf.total(),
+            elf,
+        }));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked.elf)
+        .collect()
+}
+
+/// The most calories carried by any one elf.
+pub fn part_one(elves: impl IntoIterator<Item = Elf>) -> Result<usize, AocError> {
+    top(elves, 1)
+        .first()
+        .map(Elf::total)
+        .ok_or_else(|| AocError::unsolvable("No elves in input"))
+}
+
+/// The calories carried by the three elves carrying the most.
+pub fn part_two(elves: impl IntoIterator<Item = Elf>) -> usize {
+    top(elves, 3).iter().map(Elf::total).sum()
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    AocError::check(elves(input).filter_map(Result::err).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(elves(input), |elves| part_one(elves))??.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(elves(input), |elves| part_two(elves))?.into())
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    fn day(&self) -> usize {
+        1
+    }
+
+    fn title(&self) -> &'static str {
+        "Calorie Counting"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{star_one, star_two};
+    use super::{parse, star_one, star_two, top, Elf};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -48,8 +184,9 @@ mod tests {
 9000
 
 10000"
-            )),
-            "24000"
+            ))
+            .unwrap(),
+            Answer::Integer(24000)
         );
     }
 
@@ -71,8 +208,62 @@ mod tests {
 9000
 
 10000"
-            )),
-            "45000"
+            ))
+            .unwrap(),
+            Answer::Integer(45000)
         );
     }
+
+    #[test]
+    fn test_top() {
+        let elves = parse(Cursor::new(
+            b"1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000",
+        ))
+        .unwrap();
+        assert_eq!(
+            top(elves.clone(), 2),
+            vec![
+                Elf {
+                    number: 4,
+                    items: vec![7000, 8000, 9000]
+                },
+                Elf {
+                    number: 3,
+                    items: vec![5000, 6000]
+                },
+            ]
+        );
+        let numbers = |k| {
+            top(elves.clone(), k)
+                .iter()
+                .map(|elf| elf.number)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(0), vec![]);
+        assert_eq!(numbers(10), vec![4, 3, 5, 1, 2]);
+        assert_eq!(numbers(usize::MAX), vec![4, 3, 5, 1, 2]);
+    }
+
+    #[test]
+    fn test_top_ties() {
+        let elves = (1..=5).map(|number| Elf {
+            number,
+            items: vec![100],
+        });
+        let numbers: Vec<_> = top(elves, 3).iter().map(|elf| elf.number).collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+    }
 }
@@ -1,88 +1,329 @@
-use std::io::BufRead;
-
-pub fn star_one(input: impl BufRead) -> String {
-    let data = input.lines().map(|line| line.unwrap()).map(|line| {
-        let (a, b) = line.split_once(' ').unwrap();
-        match a {
-            "A" => match b {
-                "X" => 1 + 3,
-                "Y" => 2 + 6,
-                "Z" => 3,
-                x => panic!("B didnt match: {x}"),
-            },
-            "B" => match b {
-                "X" => 1,
-                "Y" => 2 + 3,
-                "Z" => 3 + 6,
-                x => panic!("B didnt match: {x}"),
-            },
-            "C" => match b {
-                "X" => 1 + 6,
-                "Y" => 2,
-                "Z" => 3 + 3,
-                x => panic!("B didnt match: {x}"),
-            },
-            x => panic!("{}", x),
+//! Day 2: Rock Paper Scissors
+//!
+//! An elf hands over a strategy guide for a rock paper scissors tournament.
+//! [`parse`] reads its [`Round`]s, and [`part_one`] and [`part_two`] score
+//! them under the two readings of its second column. The scoring is done by
+//! a [`Game`], which can have any odd number of shapes.
+
+use crate::{
+    input::{parse_lines, Line},
+    Answer, AocError, Solution,
+};
+use itertools::process_results;
+use std::{cmp::Reverse, fmt::Display, io::BufRead};
+
+/// A shape, numbered from zero by where it is in its game's cycle. Other
+/// than the ones named here, shapes come from [`Game::shape`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shape(usize);
+
+impl Shape {
+    pub const ROCK: Shape = Shape(0);
+    pub const PAPER: Shape = Shape(1);
+    pub const SCISSORS: Shape = Shape(2);
+    pub const SPOCK: Shape = Shape(3);
+    pub const LIZARD: Shape = Shape(4);
+
+    /// Where the shape is in its game's cycle.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+/// A game of shapes in a cycle, where each shape beats those an odd number
+/// of steps behind it and loses to the rest. With an odd number of shapes,
+/// each beats exactly half of the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    /// What playing each shape scores.
+    shape_scores: Vec<usize>,
+    /// What losing, drawing and winning score, in that order.
+    outcome_scores: [usize; 3],
+}
+
+impl Game {
+    /// A game with a shape for each of `shape_scores`, in the order of the
+    /// cycle, scoring 0, 3 and 6 for losing, drawing and winning. There can
+    /// be up to 25 shapes, so that each has a letter in the strategy guide.
+    pub fn new(shape_scores: Vec<usize>) -> Result<Game, AocError> {
+        let shapes = shape_scores.len();
+        if !(3..=25).contains(&shapes) || shapes.is_multiple_of(2) {
+            return Err(AocError::unsolvable(format!(
+                "A game needs an odd number of shapes from three to 25, not {shapes}"
+            )));
+        }
+        Ok(Game {
+            shape_scores,
+            outcome_scores: [0, 3, 6],
+        })
+    }
+
+    /// Rock, paper and scissors, scoring 1, 2 and 3.
+    pub fn rock_paper_scissors() -> Game {
+        Game::new(vec![1, 2, 3]).expect("three shapes make a game")
+    }
+
+    /// Rock, paper, scissors, Spock and lizard, scoring 1 to 5. Scissors
+    /// cuts paper, paper covers rock, rock crushes lizard, lizard poisons
+    /// Spock, Spock smashes scissors, and so on.
+    pub fn rock_paper_scissors_spock_lizard() -> Game {
+        Game::new(vec![1, 2, 3, 4, 5]).expect("five shapes make a game")
+    }
+
+    /// Scores rounds that are lost, drawn and won with these instead.
+    pub fn outcome_scores(mut self, lose: usize, draw: usize, win: usize) -> Game {
+        self.outcome_scores = [lose, draw, win];
+        self
+    }
+
+    /// Every shape in the game.
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> {
+        (0..self.shape_scores.len()).map(Shape)
+    }
+
+    /// The shape `index` steps round the cycle, if the game has that many.
+    pub fn shape(&self, index: usize) -> Option<Shape> {
+        (index < self.shape_scores.len()).then_some(Shape(index))
+    }
+
+    /// How playing `shape` against `opponent` turns out, if both are shapes
+    /// of this game.
+    pub fn outcome(&self, shape: Shape, opponent: Shape) -> Option<Outcome> {
+        let shapes = self.shape_scores.len();
+        if shape.0 >= shapes || opponent.0 >= shapes {
+            return None;
         }
-    });
-    data.sum::<usize>().to_string()
-}
-
-pub fn star_two(input: impl BufRead) -> String {
-    let data = input
-        .lines()
-        .map(|line| line.unwrap())
-        .map(|line| {
-            let (a, b) = line.split_once(' ').unwrap();
-            match a {
-                "A" => match b {
-                    "X" => ("A", "C"),
-                    "Y" => ("A", "A"),
-                    "Z" => ("A", "B"),
-                    x => panic!("B didnt match: {x}"),
-                },
-                "B" => match b {
-                    "X" => ("B", "A"),
-                    "Y" => ("B", "B"),
-                    "Z" => ("B", "C"),
-                    x => panic!("B didnt match: {x}"),
-                },
-                "C" => match b {
-                    "X" => ("C", "B"),
-                    "Y" => ("C", "C"),
-                    "Z" => ("C", "A"),
-                    x => panic!("B didnt match: {x}"),
-                },
-                x => panic!("{}", x),
-            }
+        Some(match (shape.0 + shapes - opponent.0) % shapes {
+            0 => Outcome::Draw,
+            steps if steps % 2 == 1 => Outcome::Win,
+            _ => Outcome::Lose,
         })
-        .map(|(a, b)| match a {
-            "A" => match b {
-                "A" => 1 + 3,
-                "B" => 2 + 6,
-                "C" => 3,
-                x => panic!("B didnt match: {x}"),
-            },
-            "B" => match b {
-                "A" => 1,
-                "B" => 2 + 3,
-                "C" => 3 + 6,
-                x => panic!("B didnt match: {x}"),
-            },
-            "C" => match b {
-                "A" => 1 + 6,
-                "B" => 2,
-                "C" => 3 + 3,
-                x => panic!("B didnt match: {x}"),
-            },
-            x => panic!("{}", x),
-        });
-    data.sum::<usize>().to_string()
+    }
+
+    /// The score for playing `shape` against `opponent`, if both are shapes
+    /// of this game.
+    pub fn score(&self, shape: Shape, opponent: Shape) -> Option<usize> {
+        let outcome = self.outcome(shape, opponent)?;
+        Some(self.shape_scores[shape.0] + self.outcome_scores[outcome as usize])
+    }
+
+    /// The best scoring shape to play against `opponent` for `outcome`.
+    /// Most outcomes can be had with more than one shape once there are more
+    /// than three.
+    pub fn shape_for(&self, outcome: Outcome, opponent: Shape) -> Option<Shape> {
+        self.best(
+            self.shapes()
+                .filter(|&shape| self.outcome(shape, opponent) == Some(outcome)),
+            opponent,
+        )
+    }
+
+    /// The best scoring shape to play against `opponent`, whatever the
+    /// outcome.
+    pub fn best_response(&self, opponent: Shape) -> Option<Shape> {
+        self.best(self.shapes(), opponent)
+    }
+
+    /// The best scoring of `shapes` against `opponent`, and the first of
+    /// them if several score the same. There is none if `opponent` is not in
+    /// the game.
+    fn best(&self, shapes: impl Iterator<Item = Shape>, opponent: Shape) -> Option<Shape> {
+        shapes
+            .filter_map(|shape| Some((self.score(shape, opponent)?, shape)))
+            .max_by_key(|&(score, shape)| (score, Reverse(shape.0)))
+            .map(|(_, shape)| shape)
+    }
+
+    /// A strategy guide scoring as much as possible against `opponents`,
+    /// saying what to play in each round. There is none if any of them is
+    /// not in the game.
+    pub fn strategy(&self, opponents: impl IntoIterator<Item = Shape>) -> Option<Vec<Shape>> {
+        opponents
+            .into_iter()
+            .map(|opponent| self.best_response(opponent))
+            .collect()
+    }
+
+    /// The letter the second column uses for each shape: the last letters
+    /// of the alphabet, so `X`, `Y` and `Z` with three shapes.
+    fn column_letters(&self) -> impl Iterator<Item = char> {
+        (b'Z' + 1 - self.shape_scores.len() as u8..=b'Z').map(char::from)
+    }
+
+    /// The shape the second column of `round` says to play, read as
+    /// `reading`, if this game has it.
+    pub fn play(&self, round: Round, reading: Reading) -> Option<Shape> {
+        match reading {
+            Reading::Shape => self
+                .column_letters()
+                .position(|letter| letter == round.column.0)
+                .map(Shape),
+            Reading::Outcome => self.shape_for(round.column.outcome()?, round.opponent),
+        }
+    }
+
+    /// The total score from following the strategy guide, read as `reading`.
+    pub fn score_guide(
+        &self,
+        rounds: impl IntoIterator<Item = Round>,
+        reading: Reading,
+    ) -> Result<usize, AocError> {
+        rounds
+            .into_iter()
+            .map(|round| {
+                self.play(round, reading)
+                    .and_then(|shape| self.score(shape, round.opponent))
+                    .ok_or_else(|| {
+                        AocError::unsolvable(format!(
+                            "{round} cannot be played in a game of {} shapes",
+                            self.shape_scores.len()
+                        ))
+                    })
+            })
+            .sum()
+    }
+
+    /// Splits a line of the strategy guide into its two columns, checking
+    /// both are letters for this game's shapes. The opponent's shapes are
+    /// lettered from `A`.
+    fn parse_round(&self, line: &Line) -> Result<Round, AocError> {
+        let (a, b) = line.split_once(line.text, " ")?;
+        let letter = |column: &str| {
+            let mut chars = column.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        };
+        let opponent = letter(a)
+            .filter(char::is_ascii_uppercase)
+            .and_then(|c| self.shape((c as u8 - b'A') as usize))
+            .ok_or_else(|| line.error(a, format!("Unknown opponent shape: {a}")))?;
+        let column = letter(b)
+            .filter(|&c| self.column_letters().any(|letter| letter == c))
+            .map(Column)
+            .ok_or_else(|| line.error(b, format!("Unknown second column: {b}")))?;
+        Ok(Round { opponent, column })
+    }
+
+    /// Each round of a strategy guide for this game, as it is read.
+    pub fn rounds(&self, input: impl BufRead) -> impl Iterator<Item = Result<Round, AocError>> {
+        let game = self.clone();
+        parse_lines(input, move |line| game.parse_round(&line))
+    }
+}
+
+/// The second column of the strategy guide, which means something different
+/// in each part. It is one of the letters [`Game::rounds`] knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column(char);
+
+impl Column {
+    pub fn letter(self) -> char {
+        self.0
+    }
+
+    /// The column read as how the round should end, which only `X`, `Y` and
+    /// `Z` say.
+    pub fn outcome(self) -> Option<Outcome> {
+        match self.0 {
+            'X' => Some(Outcome::Lose),
+            'Y' => Some(Outcome::Draw),
+            'Z' => Some(Outcome::Win),
+            _ => None,
+        }
+    }
+}
+
+/// How to read the second column of the strategy guide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reading {
+    /// The shape to play.
+    Shape,
+    /// How the round should end.
+    Outcome,
+}
+
+/// One line of the strategy guide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Round {
+    pub opponent: Shape,
+    pub column: Column,
+}
+
+impl Display for Round {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // No game has more than 25 shapes, so each has a letter.
+        let opponent = char::from(b'A' + self.opponent.0 as u8);
+        write!(f, "{opponent} {}", self.column.0)
+    }
+}
+
+/// Each round of the rock paper scissors strategy guide, as it is read.
+pub fn rounds(input: impl BufRead) -> impl Iterator<Item = Result<Round, AocError>> {
+    Game::rock_paper_scissors().rounds(input)
+}
+
+/// Reads the whole strategy guide. Every bad line is reported, not just the
+/// first.
+pub fn parse(input: impl BufRead) -> Result<Vec<Round>, AocError> {
+    AocError::collect(rounds(input))
+}
+
+/// The total score when the second column is the shape to play.
+pub fn part_one(rounds: impl IntoIterator<Item = Round>) -> Result<usize, AocError> {
+    Game::rock_paper_scissors().score_guide(rounds, Reading::Shape)
+}
+
+/// The total score when the second column is how the round should end.
+pub fn part_two(rounds: impl IntoIterator<Item = Round>) -> Result<usize, AocError> {
+    Game::rock_paper_scissors().score_guide(rounds, Reading::Outcome)
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    AocError::check(rounds(input).filter_map(Result::err).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(rounds(input), |rounds| part_one(rounds))??.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(rounds(input), |rounds| part_two(rounds))??.into())
+}
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    fn day(&self) -> usize {
+        2
+    }
+
+    fn title(&self) -> &'static str {
+        "Rock Paper Scissors"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{star_one, star_two};
+    use super::{star_one, star_two, Game, Outcome, Reading, Shape};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -92,8 +333,9 @@ mod tests {
                 b"A Y
 B X
 C Z"
-            )),
-            "15"
+            ))
+            .unwrap(),
+            Answer::Integer(15)
         );
     }
 
@@ -104,8 +346,135 @@ C Z"
                 b"A Y
 B X
 C Z"
-            )),
-            "12"
+            ))
+            .unwrap(),
+            Answer::Integer(12)
+        );
+    }
+
+    #[test]
+    fn test_rock_paper_scissors() {
+        let game = Game::rock_paper_scissors();
+        let beats = [
+            (Shape::ROCK, Shape::SCISSORS),
+            (Shape::SCISSORS, Shape::PAPER),
+            (Shape::PAPER, Shape::ROCK),
+        ];
+        for (winner, loser) in beats {
+            assert_eq!(game.outcome(winner, loser), Some(Outcome::Win));
+            assert_eq!(game.outcome(loser, winner), Some(Outcome::Lose));
+            assert_eq!(game.shape_for(Outcome::Win, loser), Some(winner));
+            assert_eq!(game.shape_for(Outcome::Lose, winner), Some(loser));
+        }
+        assert_eq!(
+            game.outcome(Shape::PAPER, Shape::PAPER),
+            Some(Outcome::Draw)
         );
     }
+
+    #[test]
+    fn test_rock_paper_scissors_spock_lizard() {
+        let game = Game::rock_paper_scissors_spock_lizard();
+        for shape in game.shapes() {
+            let wins = game
+                .shapes()
+                .filter(|&other| game.outcome(shape, other) == Some(Outcome::Win))
+                .count();
+            assert_eq!(wins, 2, "{shape:?}");
+        }
+        assert_eq!(
+            game.outcome(Shape::SPOCK, Shape::SCISSORS),
+            Some(Outcome::Win)
+        );
+        assert_eq!(game.outcome(Shape::SPOCK, Shape::ROCK), Some(Outcome::Win));
+        assert_eq!(
+            game.outcome(Shape::LIZARD, Shape::SPOCK),
+            Some(Outcome::Win)
+        );
+        assert_eq!(
+            game.outcome(Shape::LIZARD, Shape::PAPER),
+            Some(Outcome::Win)
+        );
+        assert_eq!(game.outcome(Shape::ROCK, Shape::LIZARD), Some(Outcome::Win));
+        // Spock and paper both beat rock, and Spock scores more.
+        assert_eq!(
+            game.shape_for(Outcome::Win, Shape::ROCK),
+            Some(Shape::SPOCK)
+        );
+    }
+
+    #[test]
+    fn test_strategy() {
+        let game = Game::rock_paper_scissors();
+        let opponents = [Shape::ROCK, Shape::PAPER, Shape::SCISSORS];
+        assert_eq!(
+            game.strategy(opponents),
+            Some(vec![Shape::PAPER, Shape::SCISSORS, Shape::ROCK])
+        );
+        // Drawing with scissors beats winning with rock if winning is worth
+        // little enough.
+        let game = game.outcome_scores(0, 3, 4);
+        assert_eq!(
+            game.strategy(opponents),
+            Some(vec![Shape::PAPER, Shape::SCISSORS, Shape::SCISSORS])
+        );
+    }
+
+    #[test]
+    fn test_shapes_outside_the_game() {
+        let game = Game::rock_paper_scissors();
+        assert_eq!(game.shape(2), Some(Shape::SCISSORS));
+        assert_eq!(game.shape(3), None);
+        assert_eq!(
+            Game::rock_paper_scissors_spock_lizard().shape(3),
+            Some(Shape::SPOCK)
+        );
+        // Spock and lizard are not in plain rock paper scissors.
+        assert_eq!(game.outcome(Shape::LIZARD, Shape::ROCK), None);
+        assert_eq!(game.outcome(Shape::ROCK, Shape::SPOCK), None);
+        assert_eq!(game.score(Shape::LIZARD, Shape::ROCK), None);
+        assert_eq!(game.best_response(Shape::SPOCK), None);
+        assert_eq!(game.strategy([Shape::ROCK, Shape::LIZARD]), None);
+    }
+
+    #[test]
+    fn test_bigger_guides() {
+        let game = Game::rock_paper_scissors_spock_lizard();
+        let rounds = |input: &str| {
+            game.rounds(Cursor::new(input.to_string()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        // Five shapes take the letters A to E, and V to Z.
+        let guide = rounds("D V\nE Z\nA Y").unwrap();
+        assert_eq!(guide[0].opponent, Shape::SPOCK);
+        assert_eq!(game.play(guide[0], Reading::Shape), Some(Shape::ROCK));
+        assert_eq!(
+            game.score_guide(guide.clone(), Reading::Shape).unwrap(),
+            // Rock loses to Spock, lizard draws and Spock beats rock.
+            1 + (5 + 3) + (4 + 6)
+        );
+        // Rock and scissors both beat lizard, and scissors scores more.
+        assert_eq!(game.play(guide[1], Reading::Outcome), Some(Shape::SCISSORS));
+        // V and W do not say how a round should end.
+        let error = game.score_guide(guide, Reading::Outcome).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no solution: D V cannot be played in a game of 5 shapes"
+        );
+        assert!(rounds("F X").is_err());
+        assert!(rounds("A U").is_err());
+        // A guide read for five shapes cannot be scored with three.
+        let guide = rounds("E Z").unwrap();
+        assert!(Game::rock_paper_scissors()
+            .score_guide(guide, Reading::Shape)
+            .is_err());
+    }
+
+    #[test]
+    fn test_game_needs_odd_shapes() {
+        assert!(Game::new(vec![1, 2, 3, 4]).is_err());
+        assert!(Game::new(vec![1]).is_err());
+        assert!(Game::new(vec![1; 7]).is_ok());
+        assert!(Game::new(vec![1; 27]).is_err());
+    }
 }
@@ -1,94 +1,382 @@
-use std::{collections::HashSet, io::BufRead};
-
-fn get_priority(c: &char) -> usize {
-    let offset = if c.is_ascii_lowercase() {
-        'a' as usize - 1
-    } else {
-        'A' as usize - 27
-    };
-    *c as usize - offset
-}
-
-pub fn star_one(input: impl BufRead) -> String {
-    let data = input.lines().map(|line| line.unwrap()).map(|line| {
-        let (first, last) = line.split_at(line.len() / 2);
-        let first_set: HashSet<char> = HashSet::from_iter(first.chars());
-        let last_set = HashSet::from_iter(last.chars());
-
-        first_set
-            .intersection(&last_set)
-            .map(get_priority)
-            .sum::<usize>()
-    });
-    data.sum::<usize>().to_string()
-}
-
-pub fn star_two(input: impl BufRead) -> String {
-    let data = input
-        .lines()
-        .map(|line| line.unwrap())
-        .collect::<Vec<String>>()
-        .chunks(3)
-        .map(|lines| {
-            let line0_set: HashSet<char> = HashSet::from_iter(lines[0].chars());
-            let line1_set = HashSet::from_iter(lines[1].chars());
-            let line2_set = HashSet::from_iter(lines[2].chars());
-
-            let int: HashSet<_> = line0_set
-                .intersection(&line1_set)
-                .map(|s| s.to_owned())
-                .collect();
-
-            let possible_badges = int
-                .intersection(&line2_set)
-                .map(|c| c.to_owned())
-                .collect::<Vec<char>>();
-
-            possible_badges
+//! Day 3: Rucksack Reorganization
+//!
+//! Every elf's rucksack has two compartments, and every group of three elves
+//! shares a badge. [`parse`] reads the [`Rucksack`]s, and [`part_one`] and
+//! [`part_two`] add up the priorities of the items they have in common. Sets
+//! of items are kept as [`Items`], so finding what they share is cheap.
+
+use crate::{
+    input::{parse_lines, Line},
+    Answer, AocError, Solution,
+};
+use itertools::{process_results, Itertools};
+use std::io::BufRead;
+
+/// The priority of an item, from 1 for `a` to 52 for `Z`. Anything but a
+/// letter is not an item, and has none.
+pub fn priority(c: char) -> Option<usize> {
+    match c {
+        'a'..='z' => Some(c as usize - 'a' as usize + 1),
+        'A'..='Z' => Some(c as usize - 'A' as usize + 27),
+        _ => None,
+    }
+}
+
+/// The item with a priority, the opposite of [`priority`].
+fn item(priority: u32) -> char {
+    let offset = if priority <= 26 { b'a' - 1 } else { b'A' - 27 };
+    (priority as u8 + offset) as char
+}
+
+/// A set of items, with a bit for each item's priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Items(u64);
+
+impl Items {
+    /// Every item there is.
+    pub const ALL: Items = Items(((1 << 52) - 1) << 1);
+
+    pub fn contains(self, item: char) -> bool {
+        priority(item).is_some_and(|p| self.0 & (1 << p) != 0)
+    }
+
+    pub fn intersection(self, other: Items) -> Items {
+        Items(self.0 & other.0)
+    }
+
+    /// The items every one of `sets` has, or every item if there are none.
+    pub fn common(sets: impl IntoIterator<Item = Items>) -> Items {
+        sets.into_iter().fold(Items::ALL, Items::intersection)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The item, if there is exactly one.
+    pub fn only(self) -> Option<char> {
+        (self.len() == 1).then(|| item(self.0.trailing_zeros()))
+    }
+
+    /// Each item, lowest priority first.
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        (1..=52).filter(move |p| self.0 & (1 << p) != 0).map(item)
+    }
+
+    /// The total priority of the items.
+    pub fn priority(self) -> usize {
+        self.iter().filter_map(priority).sum()
+    }
+}
+
+/// Leaves out anything that is not an item.
+impl FromIterator<char> for Items {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        Items(
+            iter.into_iter()
+                .filter_map(priority)
+                .fold(0, |set, p| set | 1 << p),
+        )
+    }
+}
+
+/// The items in one elf's rucksack, each a letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rucksack(String);
+
+impl Rucksack {
+    pub fn items(&self) -> &str {
+        &self.0
+    }
+
+    /// Every different item in the rucksack.
+    pub fn item_set(&self) -> Items {
+        self.0.chars().collect()
+    }
+
+    /// The items in each of `count` compartments, which are as near the same
+    /// size as they can be.
+    pub fn compartments(&self, count: usize) -> impl Iterator<Item = Items> + '_ {
+        let len = self.0.len();
+        (0..count).map(move |i| {
+            self.0[i * len / count..(i + 1) * len / count]
+                .chars()
+                .collect()
         })
-        .collect::<Vec<_>>();
+    }
 
-    // data.sort_by_key(|possible_badges| possible_badges.len());
+    /// The items packed in both compartments.
+    pub fn misplaced(&self) -> Items {
+        Items::common(self.compartments(2))
+    }
+}
 
-    data.iter()
-        .flatten()
-        .map(get_priority)
-        .sum::<usize>()
-        .to_string()
+/// The one item every rucksack in the `number`th group of `size` has.
+fn shared_item(number: usize, size: usize, group: &[Items]) -> Result<char, AocError> {
+    if group.len() != size {
+        return Err(AocError::unsolvable(format!(
+            "Group {number} has {} elves, not {size}",
+            group.len()
+        )));
+    }
+    let common = Items::common(group.iter().copied());
+    common.only().ok_or_else(|| {
+        AocError::unsolvable(format!(
+            "Group {number} has {} items in common, not one",
+            common.len()
+        ))
+    })
+}
+
+/// The badge of each group of `size` rucksacks, as the groups go by. A group
+/// is a mistake if it is short, or its elves share anything but exactly one
+/// item. Groups must have at least one elf.
+pub fn badges(
+    rucksacks: impl IntoIterator<Item = Rucksack>,
+    size: usize,
+) -> Result<impl Iterator<Item = Result<char, AocError>>, AocError> {
+    if size == 0 {
+        return Err(AocError::unsolvable("A group needs at least one elf"));
+    }
+    Ok(rucksacks
+        .into_iter()
+        .map(|rucksack| rucksack.item_set())
+        .batching(move |sets| {
+            let group = sets.take(size).collect::<Vec<_>>();
+            (!group.is_empty()).then_some(group)
+        })
+        .zip(1..)
+        .map(move |(group, number)| shared_item(number, size, &group)))
+}
+
+/// Checks every item in the rucksack is a letter, as nothing else has a
+/// priority, and that the items split evenly between its two compartments.
+fn parse_rucksack(line: &Line) -> Result<Rucksack, AocError> {
+    let mut problems = Vec::new();
+    if let Some((i, c)) = line
+        .text
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_alphabetic())
+    {
+        problems.push(line.error(&line.text[i..], format!("Unknown item: {c}")));
+    }
+    let count = line.text.chars().count();
+    if !count.is_multiple_of(2) {
+        problems.push(line.error(
+            line.text,
+            format!(
+                "A rucksack needs an even number of items to fill both compartments, not {count}"
+            ),
+        ));
+    }
+    AocError::check(problems)?;
+    Ok(Rucksack(line.text.to_string()))
+}
+
+/// Each rucksack, as it is read.
+pub fn rucksacks(input: impl BufRead) -> impl Iterator<Item = Result<Rucksack, AocError>> {
+    parse_lines(input, |line| parse_rucksack(&line))
+}
+
+/// Reads every rucksack. Every bad line is reported, not just the first.
+pub fn parse(input: impl BufRead) -> Result<Vec<Rucksack>, AocError> {
+    AocError::collect(rucksacks(input))
+}
+
+/// The total priority of the items packed in both compartments.
+pub fn part_one(rucksacks: impl IntoIterator<Item = Rucksack>) -> usize {
+    rucksacks
+        .into_iter()
+        .map(|rucksack| rucksack.misplaced().priority())
+        .sum()
+}
+
+/// The total priority of each group of three's badge.
+pub fn part_two(rucksacks: impl IntoIterator<Item = Rucksack>) -> Result<usize, AocError> {
+    badges(rucksacks, 3)?.filter_map_ok(priority).sum()
+}
+
+/// Checks every rucksack, and that each group of three shares exactly one
+/// item. A group with a bad rucksack in it is not checked.
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    let mut problems = Vec::new();
+    for (group, number) in rucksacks(input).chunks(3).into_iter().zip(1..) {
+        let (group, errors): (Vec<_>, Vec<_>) = group
+            .map_ok(|rucksack| rucksack.item_set())
+            .partition_result();
+        if errors.is_empty() {
+            problems.extend(shared_item(number, 3, &group).err());
+        } else {
+            problems.extend(errors);
+        }
+    }
+    AocError::check(problems)
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(rucksacks(input), |rucksacks| part_one(rucksacks))?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(rucksacks(input), |rucksacks| part_two(rucksacks))??.into())
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    fn day(&self) -> usize {
+        3
+    }
+
+    fn title(&self) -> &'static str {
+        "Rucksack Reorganization"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{star_one, star_two};
-    use std::io::Cursor;
+    use super::{badges, parse, priority, star_one, star_two, validate, Items, Rucksack};
+    use crate::{Answer, AocError};
 
-    #[test]
-    fn test_star_one() {
-        assert_eq!(
-            star_one(Cursor::new(
-                b"vJrwpWtwJgWrhcsFMMfFFhFp
+    const EXAMPLE: &[u8] = b"vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
 PmmdzqPrVvPwwTWBwg
 wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw"
-            )),
-            "157"
-        );
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    #[test]
+    fn test_star_one() {
+        assert_eq!(star_one(EXAMPLE).unwrap(), Answer::Integer(157));
     }
 
     #[test]
     fn test_star_two() {
+        assert_eq!(star_two(EXAMPLE).unwrap(), Answer::Integer(70));
+    }
+
+    #[test]
+    fn test_items() {
+        let items: Items = "abZzZ".chars().collect();
+        assert_eq!(items.len(), 4);
+        assert!(items.contains('Z') && !items.contains('A'));
+        assert_eq!(items.iter().collect::<String>(), "abzZ");
+        assert_eq!(items.priority(), 1 + 2 + 26 + 52);
+        assert_eq!(Items::ALL.len(), 52);
+        assert_eq!(Items::common([]), Items::ALL);
+        let other: Items = "Zq".chars().collect();
+        assert_eq!(Items::common([items, other]).only(), Some('Z'));
+        assert_eq!(items.only(), None);
+        // Anything but a letter is not an item.
+        let odd: Items = "a!é".chars().collect();
+        assert_eq!(odd.iter().collect::<String>(), "a");
+        assert!(!odd.contains('!') && !Items::ALL.contains('é'));
+        assert_eq!((priority('a'), priority('Z')), (Some(1), Some(52)));
+        assert_eq!((priority('!'), priority('é')), (None, None));
+    }
+
+    #[test]
+    fn test_compartments() {
+        let rucksack = Rucksack("abcabcab".to_string());
+        assert_eq!(rucksack.misplaced().iter().collect::<String>(), "abc");
+        let thirds = rucksack.compartments(3).collect::<Vec<_>>();
+        assert_eq!(thirds.len(), 3);
+        assert_eq!(Items::common(thirds).iter().collect::<String>(), "ab");
+    }
+
+    #[test]
+    fn test_badges() {
+        let rucksacks = parse(EXAMPLE).unwrap();
+        let badges3 = badges(rucksacks.clone(), 3)
+            .unwrap()
+            .collect::<Result<String, _>>();
+        assert_eq!(badges3.unwrap(), "rZ");
+        // Nothing is in all six.
+        let badges6 = badges(rucksacks.clone(), 6)
+            .unwrap()
+            .collect::<Result<String, _>>();
+        assert!(badges6.is_err());
+        let short = badges(rucksacks.clone(), 4).unwrap().last();
+        match short {
+            Some(Err(AocError::Unsolvable(message))) => {
+                assert_eq!(message, "Group 2 has 2 elves, not 4")
+            }
+            x => panic!("Expected the last group to be short, got {x:?}"),
+        }
+        let many = badges(rucksacks.clone(), 1)
+            .unwrap()
+            .collect::<Result<String, _>>();
+        assert!(many.is_err());
+        match badges(rucksacks, 0) {
+            Err(AocError::Unsolvable(message)) => {
+                assert_eq!(message, "A group needs at least one elf")
+            }
+            Err(x) => panic!("Expected groups of none to be refused, got {x:?}"),
+            Ok(_) => panic!("Expected groups of none to be refused"),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(EXAMPLE).is_ok());
+        let problems = match validate(&b"abcd\nabef\nabgh\nx1\nxy\nzy"[..]) {
+            Err(e) => e
+                .problems()
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>(),
+            x => panic!("Expected both groups to be wrong, got {x:?}"),
+        };
         assert_eq!(
-            star_two(Cursor::new(
-                b"vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw"
-            )),
-            "70"
+            problems,
+            [
+                AocError::unsolvable("Group 1 has 2 items in common, not one").to_string(),
+                AocError::parse(4, 2, "Unknown item: 1").to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_odd_rucksacks() {
+        let problems = match parse(&b"abc\nab\na1b"[..]) {
+            Err(e) => e
+                .problems()
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>(),
+            x => panic!("Expected the odd rucksacks to be refused, got {x:?}"),
+        };
+        let odd = |line, count| {
+            AocError::parse(
+                line,
+                1,
+                format!("A rucksack needs an even number of items to fill both compartments, not {count}"),
+            )
+            .to_string()
+        };
+        assert_eq!(
+            problems,
+            [
+                odd(1, 3),
+                AocError::parse(3, 2, "Unknown item: 1").to_string(),
+                odd(3, 3),
+            ]
         );
     }
 }
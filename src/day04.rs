@@ -1,73 +1,116 @@
+//! Day 4: Camp Cleanup
+//!
+//! Pairs of elves are each given a range of sections to clean. [`parse`]
+//! reads the pairs of [`Assignment`]s, and [`part_one`] and [`part_two`]
+//! count the pairs that cover the same ground.
+
+use crate::{
+    input::{parse_lines, Line},
+    Answer, AocError, Solution,
+};
+use itertools::process_results;
 use std::io::BufRead;
 
-/// Returns true if the second is contained by the first
-fn range_contains(first: &(usize, usize), second: &(usize, usize)) -> bool {
-    first.0 <= second.0 && first.1 >= second.1
+/// The sections from `start` to `end` inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub start: usize,
+    pub end: usize,
 }
 
-fn range_overlaps(first: &(usize, usize), second: &(usize, usize)) -> bool {
-    first.0 >= second.0 && first.0 <= second.1 || second.0 >= first.0 && second.0 <= first.1
+impl Assignment {
+    /// Returns true if `other` is contained by this one.
+    pub fn contains(&self, other: &Assignment) -> bool {
+        self.start <= other.start && self.end >= other.end
+    }
+
+    pub fn overlaps(&self, other: &Assignment) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
 }
 
-pub fn star_one(input: impl BufRead) -> String {
-    let data = input
-        .lines()
-        .map(|line| line.unwrap())
-        .map(|line| {
-            let (first, second) = line.split_once(',').unwrap();
-            let first_range = {
-                let (start, end) = first.split_once('-').unwrap();
-                (
-                    start.parse::<usize>().unwrap(),
-                    end.parse::<usize>().unwrap(),
-                )
-            };
-
-            let second_range = {
-                let (start, end) = second.split_once('-').unwrap();
-                (
-                    start.parse::<usize>().unwrap(),
-                    end.parse::<usize>().unwrap(),
-                )
-            };
-
-            (first_range, second_range)
+/// The assignments of the two elves in a pair.
+pub type Pair = (Assignment, Assignment);
+
+fn parse_pair(line: &Line) -> Result<Pair, AocError> {
+    let parse_range = |range: &str| -> Result<Assignment, AocError> {
+        let (start, end) = line.split_once(range, "-")?;
+        Ok(Assignment {
+            start: line.parse(start)?,
+            end: line.parse(end)?,
         })
-        .filter(|(first, second)| range_contains(first, second) || range_contains(second, first));
-    data.count().to_string()
+    };
+    let (first, second) = line.split_once(line.text, ",")?;
+    Ok((parse_range(first)?, parse_range(second)?))
 }
 
-pub fn star_two(input: impl BufRead) -> String {
-    let data = input
-        .lines()
-        .map(|line| line.unwrap())
-        .map(|line| {
-            let (first, second) = line.split_once(',').unwrap();
-            let first_range = {
-                let (start, end) = first.split_once('-').unwrap();
-                (
-                    start.parse::<usize>().unwrap(),
-                    end.parse::<usize>().unwrap(),
-                )
-            };
-
-            let second_range = {
-                let (start, end) = second.split_once('-').unwrap();
-                (
-                    start.parse::<usize>().unwrap(),
-                    end.parse::<usize>().unwrap(),
-                )
-            };
-
-            (first_range, second_range)
-        })
-        .filter(|(first, second)| range_overlaps(first, second) || range_overlaps(second, first));
-    data.count().to_string()
+/// Each pair of assignments, as it is read.
+pub fn pairs(input: impl BufRead) -> impl Iterator<Item = Result<Pair, AocError>> {
+    parse_lines(input, |line| parse_pair(&line))
+}
+
+/// Reads every pair of assignments. Every bad line is reported, not just
+/// the first.
+pub fn parse(input: impl BufRead) -> Result<Vec<Pair>, AocError> {
+    AocError::collect(pairs(input))
+}
+
+/// How many pairs have one assignment inside the other.
+pub fn part_one(pairs: impl IntoIterator<Item = Pair>) -> usize {
+    pairs
+        .into_iter()
+        .filter(|(first, second)| first.contains(second) || second.contains(first))
+        .count()
+}
+
+/// How many pairs have assignments that overlap at all.
+pub fn part_two(pairs: impl IntoIterator<Item = Pair>) -> usize {
+    pairs
+        .into_iter()
+        .filter(|(first, second)| first.overlaps(second))
+        .count()
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    AocError::check(pairs(input).filter_map(Result::err).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(pairs(input), |pairs| part_one(pairs))?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(pairs(input), |pairs| part_two(pairs))?.into())
+}
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    fn day(&self) -> usize {
+        4
+    }
+
+    fn title(&self) -> &'static str {
+        "Camp Cleanup"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -80,8 +123,9 @@ mod tests {
 2-8,3-7
 6-6,4-6
 2-6,4-8"
-            )),
-            "2"
+            ))
+            .unwrap(),
+            Answer::Integer(2)
         );
     }
 
@@ -95,8 +139,9 @@ mod tests {
 2-8,3-7
 6-6,4-6
 2-6,4-8"
-            )),
-            "4"
+            ))
+            .unwrap(),
+            Answer::Integer(4)
         );
     }
 }
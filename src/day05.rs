@@ -1,107 +1,199 @@
-use regex::Regex;
+//! Day 5: Supply Stacks
+//!
+//! A crane moves crates between stacks. [`parse`] reads the starting
+//! [`Procedure`], and [`part_one`] and [`part_two`] run it with cranes that
+//! move one crate or several at a time.
+
+use crate::{
+    input::{read_input, Line},
+    parse::{blocks, parse_line, unsigned},
+    Answer, AocError, Solution,
+};
+use nom::{bytes::complete::tag, character::complete::digit1, sequence::preceded};
 use std::io::BufRead;
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let (stack_input, moves) = buf.split_once("\n\n").unwrap();
+/// Move `count` crates from one stack to another. Stacks are numbered from
+/// 0 here, rather than from 1 as in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
 
-    let mut stack_lines = stack_input.lines().rev();
+/// The stacks of crates, each listed bottom first, and the moves to make.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Procedure {
+    pub stacks: Vec<Vec<char>>,
+    pub moves: Vec<Move>,
+}
 
-    let mut stacks = stack_lines
-        .next()
-        .map(|l| {
-            l.chars()
-                .filter(|c| c.is_ascii_digit())
-                .map(|_| Vec::<char>::new())
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_else(Vec::new);
-    for line in stack_lines {
-        let line_chars: Vec<char> = line.chars().collect();
-        for i in 0..stacks.len() {
-            if !line_chars[4 * i + 1].is_ascii_whitespace() {
-                stacks[i].push(line_chars[4 * i + 1])
+/// Reads the crates, bottom first, from the lines above the stack numbers.
+fn parse_stacks(crate_lines: &[Line], stack_count: usize) -> Result<Vec<Vec<char>>, AocError> {
+    let mut stacks = vec![Vec::new(); stack_count];
+    let mut problems = Vec::new();
+    for line in crate_lines.iter().rev() {
+        let line_chars: Vec<char> = line.text.chars().collect();
+        for (i, stack) in stacks.iter_mut().enumerate() {
+            // Trailing spaces are often trimmed, so a short line just means empty stacks.
+            match line_chars.get(4 * i + 1) {
+                Some(c) if c.is_ascii_alphabetic() => stack.push(*c),
+                Some(c) if c.is_ascii_whitespace() => {}
+                None => {}
+                Some(c) => problems.push(AocError::parse(
+                    line.number,
+                    4 * i + 2,
+                    format!("Unexpected crate: {c}"),
+                )),
             }
         }
     }
+    AocError::check(problems)?;
+    Ok(stacks)
+}
 
-    let re = Regex::new(r"^move (\d+) from (\d) to (\d)$").unwrap();
-
-    for line in moves.lines() {
+fn parse_moves(move_lines: &[Line], stack_count: usize) -> Result<Vec<Move>, AocError> {
+    AocError::collect(move_lines.iter().map(|line| {
         //move 1 from 2 to 1
-        let cap = re
-            .captures(line)
-            .unwrap_or_else(|| panic!("Did not match '{line}'"));
-        let count = cap[1].parse::<usize>().unwrap();
-        let from_stack = cap[2].parse::<usize>().unwrap();
-        let to_stack = cap[3].parse::<usize>().unwrap();
-        for _i in 0..count {
-            let container = stacks[from_stack - 1].pop().unwrap();
-            stacks[to_stack - 1].push(container);
+        let (count, from_part, to_part) = parse_line(
+            line,
+            (
+                preceded(tag("move "), unsigned),
+                preceded(tag(" from "), digit1),
+                preceded(tag(" to "), digit1),
+            ),
+        )?;
+        let from = line.parse::<usize>(from_part)?;
+        let to = line.parse::<usize>(to_part)?;
+        for (stack, part) in [(from, from_part), (to, to_part)] {
+            if stack == 0 || stack > stack_count {
+                return Err(line.error(part, format!("There is no stack {stack}")));
+            }
         }
-    }
+        Ok(Move {
+            count,
+            from: from - 1,
+            to: to - 1,
+        })
+    }))
+}
+
+fn parse_input(buf: &str) -> Result<Procedure, AocError> {
+    let blocks = blocks(buf);
+    let (stack_lines, move_lines) = match blocks.as_slice() {
+        [stack_lines, move_lines] => (stack_lines, move_lines),
+        _ => {
+            return Err(AocError::parse(
+                1,
+                1,
+                "Expected the stacks and the moves separated by a blank line",
+            ))
+        }
+    };
+    let Some((numbers, crate_lines)) = stack_lines.split_last() else {
+        return Err(AocError::parse(1, 1, "Expected a line of stack numbers"));
+    };
+    let stack_count = numbers.text.chars().filter(|c| c.is_ascii_digit()).count();
+    let (stacks, moves) = AocError::both(
+        parse_stacks(crate_lines, stack_count),
+        parse_moves(move_lines, stack_count),
+    )?;
+    Ok(Procedure { stacks, moves })
+}
+
+fn pop(stacks: &mut [Vec<char>], stack: usize) -> Result<char, AocError> {
+    stacks[stack]
+        .pop()
+        .ok_or_else(|| AocError::unsolvable(format!("Stack {} ran out of crates", stack + 1)))
+}
 
+fn top_crates(stacks: Vec<Vec<char>>) -> Result<String, AocError> {
     stacks
         .into_iter()
-        .map(|stack| stack.last().unwrap().to_owned())
+        .enumerate()
+        .map(|(i, stack)| {
+            stack
+                .last()
+                .copied()
+                .ok_or_else(|| AocError::unsolvable(format!("Stack {} ended up empty", i + 1)))
+        })
         .collect()
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let (stack_input, moves) = buf.split_once("\n\n").unwrap();
-
-    let mut stack_lines = stack_input.lines().rev();
+/// Reads the starting stacks and the moves to make.
+pub fn parse(input: impl BufRead) -> Result<Procedure, AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    let mut stacks = stack_lines
-        .next()
-        .map(|l| {
-            l.chars()
-                .filter(|c| c.is_ascii_digit())
-                .map(|_| Vec::<char>::new())
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_else(Vec::new);
-    for line in stack_lines {
-        let line_chars: Vec<char> = line.chars().collect();
-        for i in 0..stacks.len() {
-            if !line_chars[4 * i + 1].is_ascii_whitespace() {
-                stacks[i].push(line_chars[4 * i + 1])
-            }
+/// The crates on top of each stack after moving them one at a time.
+pub fn part_one(procedure: &Procedure) -> Result<String, AocError> {
+    let mut stacks = procedure.stacks.clone();
+    for m in &procedure.moves {
+        for _i in 0..m.count {
+            let container = pop(&mut stacks, m.from)?;
+            stacks[m.to].push(container);
         }
     }
+    top_crates(stacks)
+}
 
-    let re = Regex::new(r"^move (\d+) from (\d) to (\d)$").unwrap();
-
-    for line in moves.lines() {
-        //move 1 from 2 to 1
-        let cap = re
-            .captures(line)
-            .unwrap_or_else(|| panic!("Did not match '{line}'"));
-        let count = cap[1].parse::<usize>().unwrap();
-        let from_stack = cap[2].parse::<usize>().unwrap();
-        let to_stack = cap[3].parse::<usize>().unwrap();
+/// The crates on top of each stack after moving them several at a time.
+pub fn part_two(procedure: &Procedure) -> Result<String, AocError> {
+    let mut stacks = procedure.stacks.clone();
+    for m in &procedure.moves {
         let mut tmp = Vec::new();
-        for _i in 0..count {
-            let container = stacks[from_stack - 1].pop().unwrap();
+        for _i in 0..m.count {
+            let container = pop(&mut stacks, m.from)?;
             tmp.push(container);
         }
         for container in tmp.into_iter().rev() {
-            stacks[to_stack - 1].push(container);
+            stacks[m.to].push(container);
         }
     }
+    top_crates(stacks)
+}
 
-    stacks
-        .into_iter()
-        .map(|stack| stack.last().unwrap().to_owned())
-        .collect()
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    fn day(&self) -> usize {
+        5
+    }
+
+    fn title(&self) -> &'static str {
+        "Supply Stacks"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -117,8 +209,9 @@ move 1 from 2 to 1
 move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2"
-            )),
-            "CMZ"
+            ))
+            .unwrap(),
+            Answer::from("CMZ")
         );
     }
 
@@ -135,8 +228,27 @@ move 1 from 2 to 1
 move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2"
-            )),
-            "MCD"
+            ))
+            .unwrap(),
+            Answer::from("MCD")
         );
     }
+
+    #[test]
+    fn test_crlf() {
+        // Trimming the \r\n and trailing spaces leaves the drawing's rows at
+        // different lengths, but the spaces at their start still say which
+        // stack each crate is on.
+        let input = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+    1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2 \n\n"
+            .replace('\n', "\r\n");
+        assert_eq!(star_one(Cursor::new(input)).unwrap(), Answer::from("CMZ"));
+    }
 }
@@ -1,71 +1,147 @@
-use itertools::Itertools;
+//! Day 6: Tuning Trouble
+//!
+//! A device has to lock on to a datastream of letters. [`parse`] reads the
+//! datastream, and [`part_one`] and [`part_two`] find where the packets and
+//! messages in it start.
+
+use crate::{
+    input::{lines, read_input},
+    Answer, AocError, Solution,
+};
 use std::{collections::HashSet, io::BufRead};
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    buf.chars()
-        .tuple_windows()
-        .position(|(a, b, c, d)| a != b && a != c && a != d && b != c && b != d && c != d)
-        .map(|p| p + 4)
-        .unwrap()
-        .to_string()
+/// Reads the datastream, which should be a single line of lowercase letters.
+pub fn parse(input: impl BufRead) -> Result<String, AocError> {
+    let buf = read_input(input)?;
+    let mut problems = Vec::new();
+    for line in lines(&buf) {
+        if line.number > 1 {
+            problems.push(line.error(line.text, "Expected a single datastream"));
+            continue;
+        }
+        problems.extend(
+            line.text
+                .char_indices()
+                .filter(|(_, c)| !c.is_ascii_lowercase())
+                .map(|(i, c)| line.error(&line.text[i..], format!("Unexpected character: {c}"))),
+        );
+    }
+    AocError::check(problems)?;
+    Ok(buf.trim_end().to_string())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let chars: Vec<char> = buf.chars().collect();
+/// How many characters have been read by the end of the first `len`
+/// characters that are all different.
+pub fn marker(datastream: &str, len: usize) -> Option<usize> {
+    let chars: Vec<char> = datastream.chars().collect();
     chars
-        .windows(14)
+        .windows(len)
         .position(|v| {
             let hs: HashSet<char> = HashSet::from_iter(v.iter().cloned());
             hs.len() == v.len()
         })
-        .map(|p| p + 14)
-        .unwrap()
-        .to_string()
+        .map(|p| p + len)
+}
+
+/// Where the first packet starts.
+pub fn part_one(datastream: &str) -> Result<usize, AocError> {
+    marker(datastream, 4).ok_or_else(|| AocError::unsolvable("No start-of-packet marker"))
+}
+
+/// Where the first message starts.
+pub fn part_two(datastream: &str) -> Result<usize, AocError> {
+    marker(datastream, 14).ok_or_else(|| AocError::unsolvable("No start-of-message marker"))
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    fn day(&self) -> usize {
+        6
+    }
+
+    fn title(&self) -> &'static str {
+        "Tuning Trouble"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
     fn test_star_one() {
         assert_eq!(
-            star_one(Cursor::new(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb")),
-            "7"
+            star_one(Cursor::new(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb")).unwrap(),
+            Answer::Integer(7)
         );
-        assert_eq!(star_one(Cursor::new(b"bvwbjplbgvbhsrlpgdmjqwftvncz")), "5");
-        assert_eq!(star_one(Cursor::new(b"nppdvjthqldpwncqszvftbrmjlhg")), "6");
         assert_eq!(
-            star_one(Cursor::new(b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg")),
-            "10"
+            star_one(Cursor::new(b"bvwbjplbgvbhsrlpgdmjqwftvncz")).unwrap(),
+            Answer::Integer(5)
+        );
+        assert_eq!(
+            star_one(Cursor::new(b"nppdvjthqldpwncqszvftbrmjlhg")).unwrap(),
+            Answer::Integer(6)
+        );
+        assert_eq!(
+            star_one(Cursor::new(b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg")).unwrap(),
+            Answer::Integer(10)
         );
 
         assert_eq!(
-            star_one(Cursor::new(b"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw")),
-            "11"
+            star_one(Cursor::new(b"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw")).unwrap(),
+            Answer::Integer(11)
         );
     }
 
     #[test]
     fn test_star_two() {
         assert_eq!(
-            star_two(Cursor::new(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb")),
-            "19"
+            star_two(Cursor::new(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb")).unwrap(),
+            Answer::Integer(19)
+        );
+        assert_eq!(
+            star_two(Cursor::new(b"bvwbjplbgvbhsrlpgdmjqwftvncz")).unwrap(),
+            Answer::Integer(23)
+        );
+        assert_eq!(
+            star_two(Cursor::new(b"nppdvjthqldpwncqszvftbrmjlhg")).unwrap(),
+            Answer::Integer(23)
         );
-        assert_eq!(star_two(Cursor::new(b"bvwbjplbgvbhsrlpgdmjqwftvncz")), "23");
-        assert_eq!(star_two(Cursor::new(b"nppdvjthqldpwncqszvftbrmjlhg")), "23");
         assert_eq!(
-            star_two(Cursor::new(b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg")),
-            "29"
+            star_two(Cursor::new(b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg")).unwrap(),
+            Answer::Integer(29)
         );
         assert_eq!(
-            star_two(Cursor::new(b"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw")),
-            "26"
+            star_two(Cursor::new(b"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw")).unwrap(),
+            Answer::Integer(26)
         );
     }
 }
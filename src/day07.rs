@@ -1,3 +1,13 @@
+//! Day 7: No Space Left On Device
+//!
+//! A device's disk is full. [`parse`] rebuilds its [`FileSystem`] from a
+//! terminal session, and [`part_one`] and [`part_two`] look for directories
+//! to delete.
+
+use crate::{
+    input::{lines, read_input},
+    Answer, AocError, Solution,
+};
 use itertools::Itertools;
 use std::{
     cell::RefCell,
@@ -30,6 +40,23 @@ impl Node_ {
 
 type Node = Rc<RefCell<Node_>>;
 
+/// The files and directories found by a terminal session.
+#[derive(Debug)]
+pub struct FileSystem(Node);
+
+impl FileSystem {
+    /// The size of every file.
+    pub fn total_size(&self) -> usize {
+        get_directory_size(&self.0)
+    }
+
+    /// The size of every directory, counting everything inside it, with `/`
+    /// first.
+    pub fn directory_sizes(&self) -> Vec<usize> {
+        visit(&self.0).iter().map(get_directory_size).collect()
+    }
+}
+
 fn get_directory_size(root: &Node) -> usize {
     match *root.as_ref().borrow() {
         Node_::File { size, .. } => size,
@@ -48,8 +75,8 @@ fn visit(root: &Node) -> Vec<Node> {
     }
 }
 
-fn parse_lines(buf: String) -> Node {
-    let mut lines = buf.lines().peekable();
+fn parse_lines(buf: &str) -> Result<Node, AocError> {
+    let mut lines = lines(buf).peekable();
     let root = Rc::new(RefCell::new(Node_::Directory {
         name: "/".to_string(),
         children: Vec::new(),
@@ -59,56 +86,59 @@ fn parse_lines(buf: String) -> Node {
     let mut current_directory = root.clone();
 
     while let Some(line) = lines.next() {
-        match line {
+        match line.text {
             x if x.starts_with("$ cd") => {
-                let new_directory = x.rsplit(' ').next().unwrap();
+                let new_directory = line.strip_prefix(x, "$ cd ")?;
                 match new_directory {
                     ".." => {
                         // Move up one
                         let parent = match &*current_directory.as_ref().borrow() {
-                            Node_::File { parent, .. } => parent.clone(),
-                            Node_::Directory { parent, .. } => parent.as_ref().cloned().unwrap(),
+                            Node_::File { parent, .. } => Some(parent.clone()),
+                            Node_::Directory { parent, .. } => parent.as_ref().cloned(),
                         };
-                        current_directory = parent.upgrade().unwrap()
+                        current_directory = parent
+                            .and_then(|parent| parent.upgrade())
+                            .ok_or_else(|| line.error(new_directory, "Cannot move above /"))?;
                     }
                     "/" => {
                         current_directory = root.clone();
                     }
-                    x => match *current_directory.clone().as_ref().borrow() {
-                        Node_::File { .. } => todo!(),
-                        Node_::Directory { ref children, .. } => {
-                            current_directory = children
+                    x => {
+                        let child = match *current_directory.as_ref().borrow() {
+                            Node_::File { .. } => None,
+                            Node_::Directory { ref children, .. } => children
                                 .iter()
                                 .find(|c| (*c.as_ref().borrow()).get_name() == x)
-                                .cloned()
-                                .unwrap_or_else(|| panic!("Tried to find: {x}"))
-                        }
-                    },
+                                .cloned(),
+                        };
+                        current_directory =
+                            child.ok_or_else(|| line.error(x, format!("Tried to find: {x}")))?;
+                    }
                 }
             }
             x if x.starts_with("$ ls") => {
-                let new_children =
+                let new_children = AocError::collect::<_, Vec<_>>(
                     lines
-                        .peeking_take_while(|line| !line.starts_with('$'))
-                        .map(|x| {
-                            if x.starts_with("dir") {
-                                let (_type, name) = x.split_once(' ').unwrap();
+                        .peeking_take_while(|line| !line.text.starts_with('$'))
+                        .map(|line| {
+                            let (size, name) = line.split_once(line.text, " ")?;
+                            Ok(if size == "dir" {
                                 Rc::new(RefCell::new(Node_::Directory {
                                     name: name.to_string(),
                                     children: Vec::new(),
                                     parent: Some(Rc::downgrade(&current_directory)),
                                 }))
                             } else {
-                                let (size, name) = x.split_once(' ').unwrap();
                                 Rc::new(RefCell::new(Node_::File {
                                     name: name.to_string(),
-                                    size: size.parse::<usize>().unwrap(),
+                                    size: line.parse::<usize>(size)?,
                                     parent: Rc::downgrade(&current_directory),
                                 }))
-                            }
-                        });
+                            })
+                        }),
+                )?;
                 match *current_directory.borrow_mut() {
-                    Node_::File { .. } => panic!("Should not be on a directory"),
+                    Node_::File { .. } => return Err(line.error(x, "Cannot list a file")),
                     Node_::Directory {
                         ref mut children, ..
                     } => {
@@ -116,57 +146,86 @@ fn parse_lines(buf: String) -> Node {
                     }
                 }
             }
-            x => panic!("{}", x),
+            x => return Err(line.error(x, format!("Unknown command: {x}"))),
         }
     }
-    root
+    Ok(root)
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let root = parse_lines(buf);
-
-    visit(&root)
-        .iter()
-        .map(|dir| ((*dir).as_ref().borrow().get_name(), get_directory_size(dir)))
-        .filter(|x| x.1 <= 100_000)
-        .map(|x| x.1)
-        .sum::<usize>()
-        .to_string()
+/// Reads the terminal session.
+pub fn parse(input: impl BufRead) -> Result<FileSystem, AocError> {
+    Ok(FileSystem(parse_lines(&read_input(input)?)?))
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let root = parse_lines(buf);
+/// The total size of the directories of at most 100000.
+pub fn part_one(file_system: &FileSystem) -> usize {
+    file_system
+        .directory_sizes()
+        .into_iter()
+        .filter(|&size| size <= 100_000)
+        .sum()
+}
 
-    let mut dir_sizes: Vec<usize> = visit(&root)
-        .iter()
-        .map(|dir| ((*dir).as_ref().borrow().get_name(), get_directory_size(dir)))
-        .map(|x| x.1)
-        .collect();
+/// The size of the smallest directory to delete to have enough space for
+/// the update.
+pub fn part_two(file_system: &FileSystem) -> Result<usize, AocError> {
+    let mut dir_sizes = file_system.directory_sizes();
 
     dir_sizes.sort();
 
     let space_needed = 30_000_000;
-    let space_used = get_directory_size(&root);
-    let total_disk = 70000000;
+    let space_used = file_system.total_size();
+    let total_disk: usize = 70000000;
 
-    let to_delete = space_needed - (total_disk - space_used);
+    let to_delete = space_needed - total_disk.saturating_sub(space_used).min(space_needed);
 
     dir_sizes
-        .iter()
-        .find(|&&x| x > to_delete)
-        .unwrap()
-        .to_string()
+        .into_iter()
+        .find(|&x| x > to_delete)
+        .ok_or_else(|| AocError::unsolvable("No directory is big enough to free the space"))
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?).into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    fn day(&self) -> usize {
+        7
+    }
+
+    fn title(&self) -> &'static str {
+        "No Space Left On Device"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -196,8 +255,9 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k"
-            )),
-            "95437"
+            ))
+            .unwrap(),
+            Answer::Integer(95437)
         );
     }
 
@@ -228,8 +288,9 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k"
-            )),
-            "24933642"
+            ))
+            .unwrap(),
+            Answer::Integer(24933642)
         );
     }
 }
@@ -1,131 +1,109 @@
+//! Day 8: Treetop Tree House
+//!
+//! The elves want to build a tree house in a patch of trees. [`parse`] reads
+//! the height of each tree, and [`part_one`] and [`part_two`] look for good
+//! spots.
+
+use crate::{
+    grid::{DenseGrid, Grid, Pos, NEIGHBOURS_4},
+    input::read_input,
+    Answer, AocError, Solution,
+};
 use std::io::BufRead;
 
-/// Not a scalable solution but does the job.
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let map: Vec<Vec<u32>> = buf
-        .lines()
-        .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect();
-    let mut visible: Vec<Vec<bool>> = buf
-        .lines()
-        .enumerate()
-        .map(|(i, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(j, _c)| i == 0 || i == (map.len() - 1) || j == 0 || j == (map[i].len() - 1))
-                .collect()
-        })
-        .collect();
-
-    for i in 0..map.len() {
-        let mut max_height = map[i][0];
-        for j in 0..map[i].len() {
-            visible[i][j] = visible[i][j] || map[i][j] > max_height;
-            max_height = max_height.max(map[i][j]);
-        }
+/// Reads the tree heights, checking the map is a non-empty rectangle.
+fn parse_map(buf: &str) -> Result<DenseGrid<u32>, AocError> {
+    let map = DenseGrid::parse(buf, |c| c.to_digit(10))?;
+    if map.bounds().is_none() {
+        return Err(AocError::unsolvable("No trees in input"));
     }
+    Ok(map)
+}
 
-    for j in 0..map[0].len() {
-        let mut max_height = map[0][j];
-        for i in 0..map.len() {
-            visible[i][j] = visible[i][j] || map[i][j] > max_height;
-            max_height = max_height.max(map[i][j]);
-        }
-    }
+/// Whether every tree between `pos` and an edge is shorter than it.
+pub fn is_visible(map: &DenseGrid<u32>, pos: Pos) -> bool {
+    NEIGHBOURS_4
+        .iter()
+        .any(|&delta| map.ray(pos, delta).all(|(_, height)| *height < map[pos]))
+}
 
-    for i in (0..map.len()).rev() {
-        let mut max_height = map[i][map[i].len() - 1];
-        for j in (0..map[i].len()).rev() {
-            visible[i][j] = visible[i][j] || map[i][j] > max_height;
-            max_height = max_height.max(map[i][j]);
-        }
-    }
+/// How many trees can be seen from `pos` in each direction, multiplied
+/// together.
+pub fn scenic_score(map: &DenseGrid<u32>, pos: Pos) -> usize {
+    NEIGHBOURS_4
+        .iter()
+        .map(|&delta| {
+            let mut max_distance = 0;
+            for (_, height) in map.ray(pos, delta) {
+                max_distance += 1;
+                if *height >= map[pos] {
+                    break;
+                }
+            }
+            max_distance
+        })
+        .product()
+}
 
-    for j in (0..map[0].len()).rev() {
-        let mut max_height = map[map.len() - 1][j];
-        for i in (0..map.len()).rev() {
-            visible[i][j] = visible[i][j] || map[i][j] > max_height;
-            max_height = max_height.max(map[i][j]);
-        }
-    }
+/// Reads the height of each tree.
+pub fn parse(input: impl BufRead) -> Result<DenseGrid<u32>, AocError> {
+    parse_map(&read_input(input)?)
+}
 
-    visible
-        .iter()
-        .map(|row| row.iter().filter(|&&x| x).count())
-        .sum::<usize>()
-        .to_string()
+/// How many trees can be seen from outside the patch.
+pub fn part_one(map: &DenseGrid<u32>) -> usize {
+    map.positions().filter(|&pos| is_visible(map, pos)).count()
 }
 
-fn get_score(map: &[Vec<u32>], i: usize, j: usize) -> usize {
-    let up = {
-        let mut max_distance = 0;
-        for y in (0..i).rev() {
-            max_distance += 1;
-            if map[y][j] >= map[i][j] {
-                break;
-            }
-        }
-        max_distance
-    };
-
-    let down = {
-        let mut max_distance = 0;
-        for y in (i + 1)..map.len() {
-            max_distance += 1;
-            if map[y][j] >= map[i][j] {
-                break;
-            }
-        }
-        max_distance
-    };
-
-    let left = {
-        let mut max_distance = 0;
-        for x in (0..j).rev() {
-            max_distance += 1;
-            if map[i][x] >= map[i][j] {
-                break;
-            }
-        }
-        max_distance
-    };
-
-    let right = {
-        let mut max_distance = 0;
-        for x in (j + 1)..map[i].len() {
-            max_distance += 1;
-            if map[i][x] >= map[i][j] {
-                break;
-            }
-        }
-        max_distance
-    };
+/// The highest scenic score of any tree.
+pub fn part_two(map: &DenseGrid<u32>) -> usize {
+    map.positions()
+        .map(|pos| scenic_score(map, pos))
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
 
-    up * down * right * left
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?).into())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let map: Vec<Vec<u32>> = buf
-        .lines()
-        .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect();
-
-    let mut max_score = 0;
-    for (i, row) in map.iter().enumerate() {
-        for (j, _tree) in row.iter().enumerate() {
-            max_score = max_score.max(get_score(&map, i, j));
-        }
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?).into())
+}
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    fn day(&self) -> usize {
+        8
+    }
+
+    fn title(&self) -> &'static str {
+        "Treetop Tree House"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
     }
-    max_score.to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -137,8 +115,9 @@ mod tests {
 65332
 33549
 35390"
-            )),
-            "21"
+            ))
+            .unwrap(),
+            Answer::Integer(21)
         );
     }
 
@@ -151,8 +130,9 @@ mod tests {
 65332
 33549
 35390"
-            )),
-            "8"
+            ))
+            .unwrap(),
+            Answer::Integer(8)
         );
     }
 }
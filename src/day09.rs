@@ -1,94 +1,133 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    io::BufRead,
+//! Day 9: Rope Bridge
+//!
+//! The head of a rope is moved around, and each knot follows the one in
+//! front. [`parse`] reads the [`Motion`]s of the head, and [`part_one`] and
+//! [`part_two`] count the places the tail of a short and a long rope visit.
+
+use crate::{
+    checkpoint,
+    input::{parse_lines, Line},
+    point::{Direction, Point2},
+    Answer, AocError, Solution,
 };
+use itertools::process_results;
+use std::{collections::HashSet, io::BufRead};
+
+/// Move the head `distance` steps in `direction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Motion {
+    pub direction: Direction,
+    pub distance: usize,
+}
 
-#[derive(Clone, Debug)]
-enum Dir {
-    Up,
-    Down,
-    Left,
-    Right,
+/// Reads the direction and distance of a move of the head.
+fn parse_motion(line: &Line) -> Result<Motion, AocError> {
+    let (dir, amount) = line.split_once(line.text, " ")?;
+    let direction = match dir {
+        "U" => Direction::Up,
+        "D" => Direction::Down,
+        "L" => Direction::Left,
+        "R" => Direction::Right,
+        x => return Err(line.error(x, format!("Unknown direction: {x}"))),
+    };
+    Ok(Motion {
+        direction,
+        distance: line.parse::<usize>(amount)?,
+    })
 }
 
-type Position = (isize, isize);
+/// Each motion of the head, as it is read.
+pub fn motions(input: impl BufRead) -> impl Iterator<Item = Result<Motion, AocError>> {
+    parse_lines(input, |line| parse_motion(&line))
+}
 
-fn simulate_rope(mut state: VecDeque<Position>, buf: String) -> usize {
+/// Reads every motion of the head. Every bad line is reported, not just the
+/// first.
+pub fn parse(input: impl BufRead) -> Result<Vec<Motion>, AocError> {
+    AocError::collect(motions(input))
+}
+
+/// Moves the head of a rope of `knots` knots, with each knot after it
+/// following the one before. Returns how many places the tail visits.
+pub fn simulate_rope(
+    knots: usize,
+    motions: impl IntoIterator<Item = Motion>,
+) -> Result<usize, AocError> {
+    let mut state = vec![Point2::ZERO; knots];
     let mut visited = HashSet::new();
-    for line in buf.lines() {
-        let (dir, amount) = line.split_once(' ').unwrap();
-        let direction = match dir {
-            "U" => Dir::Up,
-            "D" => Dir::Down,
-            "L" => Dir::Left,
-            "R" => Dir::Right,
-            x => panic!("Unknown direction: {x}"),
-        };
-        let distance = amount.parse::<usize>().unwrap();
+    for Motion {
+        direction,
+        distance,
+    } in motions
+    {
         for _i in 0..distance {
-            let head = state.pop_front().unwrap();
-            let new_head_position = match &direction {
-                Dir::Up => (head.0 + 1, head.1),
-                Dir::Down => (head.0 - 1, head.1),
-                Dir::Left => (head.0, head.1 - 1),
-                Dir::Right => (head.0, head.1 + 1),
-            };
-            let mut new_knots = VecDeque::new();
-            new_knots.push_back(new_head_position);
-            while let Some(knot) = state.pop_front() {
-                let previous_knot = new_knots.back().unwrap();
-
-                let new_knot_position = if (knot.0 - previous_knot.0)
-                    .abs()
-                    .max((knot.1 - previous_knot.1).abs())
-                    > 1
-                {
-                    let dy = match previous_knot.0.cmp(&knot.0) {
-                        std::cmp::Ordering::Less => -1,
-                        std::cmp::Ordering::Equal => 0,
-                        std::cmp::Ordering::Greater => 1,
-                    };
-
-                    let dx = match previous_knot.1.cmp(&knot.1) {
-                        std::cmp::Ordering::Less => -1,
-                        std::cmp::Ordering::Equal => 0,
-                        std::cmp::Ordering::Greater => 1,
-                    };
-                    (knot.0 + dy, knot.1 + dx)
-                } else {
-                    knot
-                };
-                new_knots.push_back(new_knot_position);
+            checkpoint()?;
+            state[0] += direction;
+            for i in 1..state.len() {
+                let (previous_knot, knot) = (state[i - 1], state[i]);
+                if knot.chebyshev(previous_knot) > 1 {
+                    state[i] += (previous_knot - knot).signum();
+                }
             }
-            visited.insert(*new_knots.back().unwrap());
-            state = new_knots;
+            visited.insert(state[state.len() - 1]);
         }
     }
-    visited.len()
+    Ok(visited.len())
+}
+
+/// How many places the tail of a rope of two knots visits.
+pub fn part_one(motions: impl IntoIterator<Item = Motion>) -> Result<usize, AocError> {
+    simulate_rope(2, motions)
+}
+
+/// How many places the tail of a rope of ten knots visits.
+pub fn part_two(motions: impl IntoIterator<Item = Motion>) -> Result<usize, AocError> {
+    simulate_rope(10, motions)
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    AocError::check(motions(input).filter_map(Result::err).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(motions(input), |motions| part_one(motions))??.into())
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let mut knots: VecDeque<(isize, isize)> = VecDeque::new();
-    knots.push_back((0, 0));
-    knots.push_back((0, 0));
-    simulate_rope(knots, buf).to_string()
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(motions(input), |motions| part_two(motions))??.into())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let mut knots: VecDeque<(isize, isize)> = VecDeque::new();
-    for _i in 0..10 {
-        knots.push_back((0, 0));
+pub struct Day09;
+
+impl Solution for Day09 {
+    fn day(&self) -> usize {
+        9
+    }
+
+    fn title(&self) -> &'static str {
+        "Rope Bridge"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
     }
-    simulate_rope(knots, buf).to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::{
+        generate::{self, check},
+        Answer,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -103,8 +142,9 @@ R 4
 D 1
 L 5
 R 2"
-            )),
-            "13"
+            ))
+            .unwrap(),
+            Answer::Integer(13)
         );
     }
 
@@ -120,8 +160,9 @@ R 4
 D 1
 L 5
 R 2"
-            )),
-            "1"
+            ))
+            .unwrap(),
+            Answer::Integer(1)
         );
 
         assert_eq!(
@@ -134,8 +175,23 @@ R 17
 D 10
 L 25
 U 20"
-            )),
-            "36"
+            ))
+            .unwrap(),
+            Answer::Integer(36)
+        );
+    }
+
+    #[test]
+    fn test_longer_rope_visits_less() {
+        // Each knot can only follow where the one before it has been.
+        check(
+            100,
+            |rng| generate::rope_moves(rng, 50),
+            |input| {
+                let one = star_one(Cursor::new(input)).unwrap();
+                let two = star_two(Cursor::new(input)).unwrap();
+                matches!((one, two), (Answer::Integer(one), Answer::Integer(two)) if two <= one)
+            },
         );
     }
 }
@@ -1,80 +1,128 @@
-use std::io::BufRead;
+//! Day 10: Cathode-Ray Tube
+//!
+//! A simple CPU drives a CRT. [`parse`] reads the program's
+//! [`Instruction`]s, and [`part_one`] and [`part_two`] run it, the second
+//! drawing what appears on the screen.
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+use crate::{
+    input::{parse_lines, Line},
+    Answer, AocError, Solution,
+};
+use itertools::process_results;
+use std::{io::BufRead, iter};
 
-    let mut signal_strengths = 0;
-    let mut x = 1;
-    let mut i = 1;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Addx(isize),
+}
 
-    for line in buf.lines() {
-        match line.split_once(' ') {
-            Some((_addx, amount)) => {
-                for _ in 0..2 {
-                    if i > 19 && (i - 20) % 40 == 0 {
-                        // println!("addx {} * {}: {} ({} {})", i, x, i * x, addx, amount);
-                        signal_strengths += i * x
-                    }
-                    i += 1;
-                }
-                // println!("Adding {} to {}", amount, x);
-                x += amount.parse::<isize>().unwrap();
-            }
-            None => {
-                if i > 19 && (i - 20) % 40 == 0 {
-                    // println!("noop {} * {}: {}", i, x, i * x);
-                    signal_strengths += i * x
-                }
-                i += 1;
-            }
-        }
+fn parse_instruction(line: &Line) -> Result<Instruction, AocError> {
+    match line.text.split_once(' ') {
+        Some(("addx", amount)) => Ok(Instruction::Addx(line.parse::<isize>(amount)?)),
+        None if line.text == "noop" => Ok(Instruction::Noop),
+        _ => Err(line.error(line.text, format!("Unknown instruction: {}", line.text))),
     }
-    signal_strengths.to_string()
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// Each instruction of the program, as it is read.
+pub fn instructions(input: impl BufRead) -> impl Iterator<Item = Result<Instruction, AocError>> {
+    parse_lines(input, |line| parse_instruction(&line))
+}
 
-    let mut x = 1;
-    let mut i = 0;
-    let mut screen = Vec::new();
+/// Reads the whole program. Every bad line is reported, not just the first.
+pub fn parse(input: impl BufRead) -> Result<Vec<Instruction>, AocError> {
+    AocError::collect(instructions(input))
+}
 
-    for line in buf.lines() {
-        match line.split_once(' ') {
-            Some((_addx, amount)) => {
-                for _ in 0..2 {
-                    if i % 40 >= x - 1 && i % 40 <= x + 1 {
-                        screen.push('#');
-                    } else {
-                        screen.push('.');
-                    }
-                    i += 1;
-                }
-                // println!("Adding {} to {}", amount, x);
-                x += amount.parse::<isize>().unwrap();
-            }
-            None => {
-                if i % 40 >= x - 1 && i % 40 <= x + 1 {
-                    screen.push('#');
-                } else {
-                    screen.push('.');
-                }
-                i += 1;
+/// The value of the X register during each cycle, starting from the first.
+/// `addx` takes two cycles, and only changes X once they are over.
+pub fn register(
+    instructions: impl IntoIterator<Item = Instruction>,
+) -> impl Iterator<Item = isize> {
+    let mut x = 1;
+    instructions
+        .into_iter()
+        .flat_map(move |instruction| match instruction {
+            Instruction::Noop => iter::repeat_n(x, 1),
+            Instruction::Addx(amount) => {
+                x += amount;
+                iter::repeat_n(x - amount, 2)
             }
+        })
+}
+
+/// The sum of the signal strengths during the 20th cycle and every 40th
+/// after that.
+pub fn part_one(instructions: impl IntoIterator<Item = Instruction>) -> isize {
+    register(instructions)
+        .zip(1..)
+        .filter(|(_, cycle)| *cycle >= 20 && (cycle - 20) % 40 == 0)
+        .map(|(x, cycle)| cycle * x)
+        .sum()
+}
+
+/// The screen is 40 pixels wide and six rows tall.
+const WIDTH: usize = 40;
+const HEIGHT: usize = 6;
+
+/// The rows of the screen. A pixel is lit when the sprite, three pixels wide
+/// and centred on X, covers it as it is drawn. Cycles after the last pixel
+/// are still run, but draw nothing, so a long program needs no more memory
+/// than the screen.
+pub fn part_two(instructions: impl IntoIterator<Item = Instruction>) -> Vec<String> {
+    let mut screen = [['.'; WIDTH]; HEIGHT];
+    for (x, i) in register(instructions).zip(0..) {
+        if i < WIDTH * HEIGHT && x.abs_diff((i % WIDTH) as isize) <= 1 {
+            screen[i / WIDTH][i % WIDTH] = '#';
         }
     }
-    screen
-        .chunks(40)
-        .map(|c| c.iter().collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n")
+    screen.iter().map(|row| row.iter().collect()).collect()
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    AocError::check(instructions(input).filter_map(Result::err).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(process_results(instructions(input), |instructions| part_one(instructions))?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(Answer::Grid(process_results(
+        instructions(input),
+        |instructions| part_two(instructions),
+    )?))
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    fn day(&self) -> usize {
+        10
+    }
+
+    fn title(&self) -> &'static str {
+        "Cathode-Ray Tube"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -228,8 +276,9 @@ noop
 noop
 noop
 "
-            )),
-            "13140"
+            ))
+            .unwrap(),
+            Answer::Integer(13140)
         );
     }
 
@@ -384,13 +433,16 @@ noop
 noop
 noop
 "
-            )),
-            "##..##..##..##..##..##..##..##..##..##..
+            ))
+            .unwrap(),
+            Answer::from(
+                "##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
 ####....####....####....####....####....
 #####.....#####.....#####.....#####.....
 ######......######......######......####
 #######.......#######.......#######....."
+            )
         );
     }
 }
@@ -1,90 +1,111 @@
-use std::{cmp::Reverse, io::BufRead};
+//! Day 11: Monkey in the Middle
+//!
+//! Monkeys throw your things to each other depending on how worried you are
+//! about each one. [`parse`] reads the notes on every [`Monkey`], and
+//! [`part_one`] and [`part_two`] work out the monkey business after watching
+//! them for a while.
 
-use regex::Regex;
+use crate::{
+    checkpoint,
+    input::read_input,
+    parse::{blocks, comma_list, field, parse_line, unsigned},
+    Answer, AocError, Solution,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{one_of, space1},
+    combinator::opt,
+    sequence::{preceded, separated_pair},
+    Parser,
+};
+use std::{cmp::Reverse, io::BufRead};
 
-#[derive(Debug)]
-enum Operation {
+/// How a monkey changes the worry level of an item it inspects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
     Addition,
     Multiply,
     Divide,
     Subtract,
 }
 
-#[derive(Debug)]
-enum Amount {
+/// What the old worry level is combined with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Amount {
     Old,
     Value(usize),
 }
 
-#[derive(Debug)]
-struct Monkey {
-    items: Vec<usize>,
-    operation: (Operation, Amount),
-    divisor: usize,
-    true_throw_to: usize,
-    false_throw_to: usize,
-    inspected: usize,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monkey {
+    /// The worry level of each item it holds.
+    pub items: Vec<usize>,
+    pub operation: (Operation, Amount),
+    /// Items with a worry level divisible by this go to `true_throw_to`, and
+    /// the rest to `false_throw_to`.
+    pub divisor: usize,
+    pub true_throw_to: usize,
+    pub false_throw_to: usize,
+    /// How many items it has inspected so far.
+    pub inspected: usize,
 }
 
-fn parse_monkeys(buf: String) -> Vec<Monkey> {
-    let operation_regex = Regex::new(r"new = (\w+) ([\*\+/-]) (\w+)$").unwrap();
+fn parse_monkeys(buf: &str) -> Result<Vec<Monkey>, AocError> {
+    let monkeys: Vec<_> = AocError::collect(blocks(buf).iter().map(|lines| {
+        if lines.len() < 6 {
+            let last = lines[lines.len() - 1];
+            return Err(last.error(last.text, "Expected six lines for each monkey"));
+        }
 
-    buf.split("\n\n")
-        .map(|monkey_input| {
-            let lines = monkey_input.lines().collect::<Vec<_>>();
+        // A monkey may start out holding nothing.
+        let items = parse_line(
+            &lines[1],
+            field("Starting items", opt(comma_list(unsigned))),
+        )?
+        .unwrap_or_default();
 
-            let items = {
-                let (_, end) = lines[1].split_once(':').unwrap();
-                end.split(',')
-                    .map(|item| item.trim().parse::<usize>().unwrap())
-                    .collect()
-            };
+        let operation = {
+            let operation = one_of("+*/-").map(|op| match op {
+                '+' => Operation::Addition,
+                '*' => Operation::Multiply,
+                '/' => Operation::Divide,
+                _ => Operation::Subtract,
+            });
+            let amount = alt((tag("old").map(|_| Amount::Old), unsigned.map(Amount::Value)));
+            parse_line(
+                &lines[2],
+                field(
+                    "Operation",
+                    preceded(tag("new = old "), separated_pair(operation, space1, amount)),
+                ),
+            )?
+        };
 
-            let operation = {
-                let (_, end) = lines[2].split_once(':').unwrap();
-                let capture = operation_regex
-                    .captures(end)
-                    .unwrap_or_else(|| panic!("Could not find operation in {end}"));
-                assert_eq!(&capture[1], "old");
-                let op = match &capture[2] {
-                    "+" => Operation::Addition,
-                    "*" => Operation::Multiply,
-                    "/" => Operation::Divide,
-                    "-" => Operation::Subtract,
-                    x => panic!("Found unexpected operation: {x}"),
-                };
-                let amount = if let Ok(x) = capture[3].parse::<usize>() {
-                    Amount::Value(x)
-                } else {
-                    Amount::Old
-                };
-                (op, amount)
-            };
+        let divisor = {
+            let line = lines[3];
+            let divisor = parse_line(
+                &line,
+                field("Test", preceded(tag("divisible by "), unsigned)),
+            )?;
+            if divisor == 0 {
+                return Err(line.error(line.text, "Cannot test divisibility by zero"));
+            }
+            divisor
+        };
 
-            let divisor = {
-                let divisor_regex = Regex::new(r"divisible by (\d+)$").unwrap();
-                let divisor = divisor_regex.captures(lines[3]).unwrap()[1]
-                    .parse::<usize>()
-                    .unwrap();
-                divisor
-            };
+        let true_throw_to = parse_line(
+            &lines[4],
+            field("If true", preceded(tag("throw to monkey "), unsigned)),
+        )?;
 
-            let true_throw_to = {
-                let divisor_regex = Regex::new(r"If true: throw to monkey (\d)").unwrap();
-                let divisor = divisor_regex.captures(lines[4]).unwrap()[1]
-                    .parse::<usize>()
-                    .unwrap();
-                divisor
-            };
-
-            let false_throw_to = {
-                let divisor_regex = Regex::new(r"If false: throw to monkey (\d)").unwrap();
-                let divisor = divisor_regex.captures(lines[5]).unwrap()[1]
-                    .parse::<usize>()
-                    .unwrap();
-                divisor
-            };
+        let false_throw_to = parse_line(
+            &lines[5],
+            field("If false", preceded(tag("throw to monkey "), unsigned)),
+        )?;
 
+        Ok((
+            lines[0],
             Monkey {
                 items,
                 operation,
@@ -92,75 +113,188 @@ fn parse_monkeys(buf: String) -> Vec<Monkey> {
                 true_throw_to,
                 false_throw_to,
                 inspected: 0,
-            }
-        })
-        .collect()
+            },
+        ))
+    }))?;
+
+    let count = monkeys.len();
+    AocError::check(
+        monkeys
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (line, monkey))| {
+                let targets = [monkey.true_throw_to, monkey.false_throw_to];
+                if targets.iter().any(|&to| to >= count) {
+                    Some(line.error(
+                        line.text,
+                        format!(
+                            "Monkey throws to a monkey that does not exist (only {count} monkeys)"
+                        ),
+                    ))
+                } else if targets.contains(&i) {
+                    // It would keep catching its own items and never finish.
+                    Some(line.error(line.text, "Monkey throws to itself"))
+                } else {
+                    None
+                }
+            })
+            .collect(),
+    )?;
+    Ok(monkeys.into_iter().map(|(_, monkey)| monkey).collect())
 }
 
-fn simulate(monkeys: &mut [Monkey], reduce_worry: bool) {
-    let max_div: usize = monkeys.iter().map(|monkey| monkey.divisor).product();
+/// Checks every monkey tests divisibility by something other than zero and
+/// only throws to other monkeys that are there.
+fn check_monkeys(monkeys: &[Monkey]) -> Result<(), AocError> {
+    let count = monkeys.len();
+    for (i, monkey) in monkeys.iter().enumerate() {
+        if monkey.divisor == 0 {
+            return Err(AocError::unsolvable(format!(
+                "Monkey {i} tests divisibility by zero"
+            )));
+        }
+        if let Some(to) = [monkey.true_throw_to, monkey.false_throw_to]
+            .into_iter()
+            .find(|&to| to >= count)
+        {
+            return Err(AocError::unsolvable(format!(
+                "Monkey {i} throws to monkey {to}, but there are only {count} monkeys"
+            )));
+        }
+        if [monkey.true_throw_to, monkey.false_throw_to].contains(&i) {
+            return Err(AocError::unsolvable(format!("Monkey {i} throws to itself")));
+        }
+    }
+    Ok(())
+}
+
+/// Plays a round, with each monkey in turn inspecting and throwing all of its
+/// items. With `reduce_worry` your worry is divided by three after each
+/// inspection, and otherwise it is kept from growing without changing where
+/// any item goes.
+pub fn simulate(monkeys: &mut [Monkey], reduce_worry: bool) -> Result<(), AocError> {
+    check_monkeys(monkeys)?;
+    let max_div = monkeys
+        .iter()
+        .try_fold(1usize, |product, monkey| {
+            product.checked_mul(monkey.divisor)
+        })
+        .ok_or_else(|| AocError::unsolvable("The divisors are too large to multiply together"))?;
     for i in 0..monkeys.len() {
-        let items_length = monkeys.get(i).unwrap().items.len();
-        while let Some(item) = monkeys.get_mut(i).unwrap().items.pop() {
-            let mut new_item = match monkeys[i].operation {
-                (Operation::Addition, Amount::Old) => item + item,
-                (Operation::Addition, Amount::Value(x)) => item + x,
-                (Operation::Multiply, Amount::Old) => item * item,
-                (Operation::Multiply, Amount::Value(x)) => item * x,
-                (Operation::Divide, Amount::Old) => 1,
-                (Operation::Divide, Amount::Value(x)) => item / x,
-                (Operation::Subtract, Amount::Old) => 0,
-                (Operation::Subtract, Amount::Value(x)) => item - x,
-            };
-            if reduce_worry {
-                new_item /= 3;
-            } else {
-                new_item %= max_div;
+        let items_length = monkeys[i].items.len();
+        while let Some(item) = monkeys[i].items.pop() {
+            checkpoint()?;
+            let new_item = match monkeys[i].operation {
+                (Operation::Addition, Amount::Old) => item.checked_add(item),
+                (Operation::Addition, Amount::Value(x)) => item.checked_add(x),
+                (Operation::Multiply, Amount::Old) => item.checked_mul(item),
+                (Operation::Multiply, Amount::Value(x)) => item.checked_mul(x),
+                (Operation::Divide, Amount::Old) => Some(1),
+                (Operation::Divide, Amount::Value(x)) => item.checked_div(x),
+                (Operation::Subtract, Amount::Old) => Some(0),
+                (Operation::Subtract, Amount::Value(x)) => item.checked_sub(x),
             }
-            if new_item % monkeys.get(i).unwrap().divisor == 0 {
-                let true_throw_to = monkeys.get(i).unwrap().true_throw_to;
-                monkeys.get_mut(true_throw_to).unwrap().items.push(new_item);
+            .ok_or_else(|| {
+                AocError::unsolvable(format!(
+                    "Monkey {i} cannot work out a new worry level for {item}"
+                ))
+            })?;
+            let new_item = if reduce_worry {
+                new_item / 3
             } else {
-                let false_throw_to = monkeys.get(i).unwrap().false_throw_to;
-                monkeys
-                    .get_mut(false_throw_to)
-                    .unwrap_or_else(|| panic!("Could not find {}", false_throw_to - 1))
-                    .items
-                    .push(new_item);
-            }
+                new_item % max_div
+            };
+            let to = if new_item % monkeys[i].divisor == 0 {
+                monkeys[i].true_throw_to
+            } else {
+                monkeys[i].false_throw_to
+            };
+            monkeys[to].items.push(new_item);
         }
-        monkeys.get_mut(i).unwrap().inspected += items_length;
+        monkeys[i].inspected += items_length;
+    }
+    Ok(())
+}
+
+/// The number of items inspected by the two busiest monkeys, multiplied.
+pub fn monkey_business(monkeys: &[Monkey]) -> Result<usize, AocError> {
+    if monkeys.len() < 2 {
+        return Err(AocError::unsolvable("Need at least two monkeys"));
     }
+    let mut inspected = monkeys.iter().map(|m| m.inspected).collect::<Vec<_>>();
+    inspected.sort_by_key(|&inspected| Reverse(inspected));
+    Ok(inspected[0] * inspected[1])
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// Reads the notes on every monkey.
+pub fn parse(input: impl BufRead) -> Result<Vec<Monkey>, AocError> {
+    parse_monkeys(&read_input(input)?)
+}
 
-    let mut monkeys = parse_monkeys(buf);
+/// The monkey business after 20 rounds, with worry reduced each time.
+pub fn part_one(monkeys: &[Monkey]) -> Result<usize, AocError> {
+    let mut monkeys = monkeys.to_vec();
     for _round in 0..20 {
-        simulate(&mut monkeys, true)
+        simulate(&mut monkeys, true)?;
+    }
+    monkey_business(&monkeys)
+}
+
+/// The monkey business after 10000 rounds, with worry never reduced.
+pub fn part_two(monkeys: &[Monkey]) -> Result<usize, AocError> {
+    let mut monkeys = monkeys.to_vec();
+    for _round in 0..10000 {
+        simulate(&mut monkeys, false)?;
     }
-    monkeys.sort_by_key(|m| Reverse(m.inspected));
-    (monkeys[0].inspected * monkeys[1].inspected).to_string()
+    monkey_business(&monkeys)
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
 
-    let mut monkeys = parse_monkeys(buf);
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    for _round in 0..10000 {
-        simulate(&mut monkeys, false)
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    fn day(&self) -> usize {
+        11
+    }
+
+    fn title(&self) -> &'static str {
+        "Monkey in the Middle"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
     }
-    monkeys.sort_by_key(|m| Reverse(m.inspected));
-    (monkeys[0].inspected * monkeys[1].inspected).to_string()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{star_one, star_two};
+    use super::{
+        parse_monkeys, part_two, simulate, star_one, star_two, validate, Amount, Monkey, Operation,
+    };
+    use crate::{
+        generate::{self, check},
+        Answer, AocError, CancellationToken,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -194,8 +328,9 @@ Monkey 3:
     Test: divisible by 17
         If true: throw to monkey 0
         If false: throw to monkey 1"
-            )),
-            "10605"
+            ))
+            .unwrap(),
+            Answer::Integer(10605)
         );
     }
 
@@ -230,8 +365,236 @@ Monkey 3:
     Test: divisible by 17
         If true: throw to monkey 0
         If false: throw to monkey 1"
-            )),
-            "2713310158"
+            ))
+            .unwrap(),
+            Answer::Integer(2713310158)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let error = validate(Cursor::new(
+            b"Monkey 0:
+    Starting items: 79, x
+    Operation: new = old * 19
+    Test: divisible by 23
+        If true: throw to monkey 1
+        If false: throw to monkey 2
+
+Monkey 1:
+    Starting items: 54
+    Operation: new = old + 6
+    Test: divisible by 19
+        If true: throw to monkey 2
+        If false: throw to monkey 0
+
+Monkey 2:
+    Starting items: 74
+    Operation: new = old ^ 3
+    Test: divisible by 17
+        If true: throw to monkey 0
+        If false: throw to monkey 1",
+        ))
+        .unwrap_err();
+        // Both bad lines are reported, not just the first.
+        let lines = error
+            .problems()
+            .iter()
+            .map(|problem| match problem {
+                AocError::Parse { line, .. } => *line,
+                x => panic!("Expected a parse error, got {x:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![2, 17]);
+    }
+
+    #[test]
+    fn test_crlf() {
+        // The blank lines between monkeys end in \r\n too, and the ones after
+        // the last monkey do not start another.
+        let input = "Monkey 0:
+    Starting items: 79, 98
+    Operation: new = old * 19
+    Test: divisible by 23
+        If true: throw to monkey 2
+        If false: throw to monkey 3
+
+Monkey 1:
+    Starting items: 54, 65, 75, 74
+    Operation: new = old + 6
+    Test: divisible by 19
+        If true: throw to monkey 2
+        If false: throw to monkey 0
+
+Monkey 2:
+    Starting items: 79, 60, 97
+    Operation: new = old * old
+    Test: divisible by 13
+        If true: throw to monkey 1
+        If false: throw to monkey 3
+
+Monkey 3:
+    Starting items: 74
+    Operation: new = old + 3
+    Test: divisible by 17
+        If true: throw to monkey 0
+        If false: throw to monkey 1 \n\n\n"
+            .replace('\n', "\r\n");
+        assert_eq!(
+            star_two(Cursor::new(input)).unwrap(),
+            Answer::Integer(2713310158)
+        );
+    }
+
+    #[test]
+    fn test_self_throw() {
+        // Monkey 1 would catch its own items forever.
+        let error = validate(Cursor::new(
+            b"Monkey 0:
+  Starting items: 79
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 54
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+
+Monkey 2:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 2
+    If false: throw to monkey 3",
+        ))
+        .unwrap_err();
+        let problems = error
+            .problems()
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                AocError::parse(8, 1, "Monkey throws to itself").to_string(),
+                AocError::parse(
+                    15,
+                    1,
+                    "Monkey throws to a monkey that does not exist (only 3 monkeys)"
+                )
+                .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_starting_items() {
+        let monkeys = parse_monkeys(
+            "Monkey 0:
+  Starting items:
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 54
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 0
+    If false: throw to monkey 0",
+        )
+        .unwrap();
+        assert_eq!(monkeys[0].items, vec![]);
+        assert_eq!(monkeys[1].items, vec![54]);
+    }
+
+    #[test]
+    fn test_simulate_checks_monkeys() {
+        let monkey = Monkey {
+            items: vec![5],
+            operation: (Operation::Subtract, Amount::Value(1)),
+            divisor: 2,
+            true_throw_to: 1,
+            false_throw_to: 1,
+            inspected: 0,
+        };
+        let mut other = monkey.clone();
+        (other.true_throw_to, other.false_throw_to) = (0, 0);
+        let run = |monkeys: &mut [Monkey]| simulate(monkeys, false).unwrap_err().to_string();
+
+        assert_eq!(
+            run(&mut [monkey.clone()]),
+            "no solution: Monkey 0 throws to monkey 1, but there are only 1 monkeys"
+        );
+        let mut zero = monkey.clone();
+        zero.divisor = 0;
+        assert_eq!(
+            run(&mut [zero, other.clone()]),
+            "no solution: Monkey 0 tests divisibility by zero"
+        );
+        assert_eq!(
+            run(&mut [other.clone(), monkey.clone()]),
+            "no solution: Monkey 0 throws to itself"
+        );
+        let mut small = monkey.clone();
+        small.items = vec![0];
+        assert_eq!(
+            run(&mut [small, other]),
+            "no solution: Monkey 0 cannot work out a new worry level for 0"
+        );
+    }
+
+    #[test]
+    fn test_cancelled() {
+        // Two monkeys passing one item back and forth, ten thousand times.
+        let monkeys = parse_monkeys(
+            "Monkey 0:
+  Starting items: 1
+  Operation: new = old + 1
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items:
+  Operation: new = old + 1
+  Test: divisible by 3
+    If true: throw to monkey 0
+    If false: throw to monkey 0",
+        )
+        .unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(matches!(
+            token.run(|| part_two(&monkeys)),
+            Err(AocError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_items_are_kept() {
+        // Monkeys only pass items around, they never drop or make them.
+        check(
+            100,
+            |rng| {
+                let count = rng.range(2..9) as usize;
+                generate::monkeys(rng, count)
+            },
+            |input| {
+                let items =
+                    |monkeys: &[Monkey]| monkeys.iter().map(|m| m.items.len()).sum::<usize>();
+                let mut monkeys = parse_monkeys(input).unwrap();
+                let before = items(&monkeys);
+                for _round in 0..100 {
+                    simulate(&mut monkeys, false).unwrap();
+                }
+                items(&monkeys) == before
+            },
         );
     }
 }
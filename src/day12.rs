@@ -1,186 +1,149 @@
-use std::{
-    collections::{BinaryHeap, HashSet},
-    io::BufRead,
+//! Day 12: Hill Climbing Algorithm
+//!
+//! You need to climb to where the signal is best. [`parse`] reads the
+//! heightmap, and [`part_one`] and [`part_two`] find the shortest climbs to
+//! the top.
+
+use crate::{
+    grid::{DenseGrid, Grid, Pos},
+    input::read_input,
+    search::Search,
+    Answer, AocError, Solution,
 };
+use std::io::BufRead;
 
+/// A square of the heightmap. The start is as low as `a`, and the end as
+/// high as `z`.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
-enum Position {
+pub enum Position {
     Start,
     End,
     Value(char),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct State {
-    path: Vec<(usize, usize)>,
+impl Position {
+    fn height(self) -> u32 {
+        match self {
+            Position::Start => 'a' as u32,
+            Position::End => 'z' as u32,
+            Position::Value(c) => c as u32,
+        }
+    }
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+/// Whether you can step from `pos1` to `pos2`, which is at most one higher.
+fn is_climbable(pos1: &Position, pos2: &Position) -> bool {
+    pos2.height() <= pos1.height() + 1
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.path.len().cmp(&self.path.len())
+/// Reads the heightmap, checking it is rectangular and has exactly one start
+/// and one end.
+fn parse_map(buf: &str) -> Result<DenseGrid<Position>, AocError> {
+    let map = DenseGrid::parse(buf, |c| match c {
+        'S' => Some(Position::Start),
+        'E' => Some(Position::End),
+        'a'..='z' => Some(Position::Value(c)),
+        _ => None,
+    })?;
+    let count = |wanted| map.iter().filter(|(_, p)| **p == wanted).count();
+    let (starts, ends) = (count(Position::Start), count(Position::End));
+    if starts != 1 || ends != 1 {
+        return Err(AocError::unsolvable(format!(
+            "Expected one start and one end, found {starts} and {ends}"
+        )));
     }
+    Ok(map)
 }
 
-fn is_climbable(pos1: &Position, pos2: &Position) -> bool {
-    match (pos1, pos2) {
-        (Position::Start, Position::Start) => panic!("Impossible"),
-        (Position::Start, Position::End) => true,
-        (Position::Start, Position::Value(_)) => true,
-        (Position::End, Position::Start) => false,
-        (Position::End, Position::End) => panic!("Impossible end end"),
-        (Position::End, Position::Value(_)) => false,
-        (Position::Value(_), Position::Start) => false,
-        (Position::Value(c), Position::End) => 'z' as usize <= *c as usize + 1,
-        (Position::Value(c1), Position::Value(c2)) => *c2 as usize <= *c1 as usize + 1,
-    }
+/// The fewest steps from any of `starts` to the end.
+pub fn climb(
+    map: &DenseGrid<Position>,
+    starts: impl Iterator<Item = Pos>,
+) -> Result<usize, AocError> {
+    let path = Search::from_starts(starts)
+        .bfs(
+            |&pos| {
+                map.neighbours4(pos)
+                    .filter(|(_, position)| is_climbable(&map[pos], position))
+                    .map(|(neighbour, _)| neighbour)
+                    .collect::<Vec<_>>()
+            },
+            |&pos| matches!(map[pos], Position::End),
+        )?
+        .ok_or_else(|| AocError::unsolvable("The end cannot be reached"))?;
+    Ok(path.cost)
 }
 
-fn parse_map(buf: String) -> Vec<Vec<Position>> {
-    buf.lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| match c {
-                    'S' => Position::Start,
-                    'E' => Position::End,
-                    x => Position::Value(x),
-                })
-                .collect()
-        })
-        .collect()
+/// Reads the heightmap.
+pub fn parse(input: impl BufRead) -> Result<DenseGrid<Position>, AocError> {
+    parse_map(&read_input(input)?)
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// The fewest steps from the start to the end.
+pub fn part_one(map: &DenseGrid<Position>) -> Result<usize, AocError> {
+    let start = map
+        .iter()
+        .find(|(_, x)| **x == Position::Start)
+        .map(|(pos, _)| pos)
+        .ok_or_else(|| AocError::unsolvable("No start position"))?;
 
-    let map = parse_map(buf);
+    climb(map, std::iter::once(start))
+}
 
-    let start = map
+/// The fewest steps from any of the lowest squares to the end.
+pub fn part_two(map: &DenseGrid<Position>) -> Result<usize, AocError> {
+    let starts = map
         .iter()
-        .enumerate()
-        .find_map(|(i, row)| {
-            row.iter()
-                .position(|x| x == &Position::Start)
-                .map(|j| (i, j))
-        })
-        .unwrap();
-
-    let mut stack = BinaryHeap::new();
-    stack.push(State { path: vec![start] });
-    let mut visited = HashSet::new();
-
-    while let Some(state) = stack.pop() {
-        let next = state.path.last().cloned().unwrap();
-        if !visited.contains(&next) {
-            visited.insert(next);
-            if matches!(map[next.0][next.1], Position::End) {
-                return (state.path.len() - 1).to_string();
-            }
-
-            if next.0 >= 1 && is_climbable(&map[next.0][next.1], &map[next.0 - 1][next.1]) {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0 - 1, next.1));
-                stack.push(new_state);
-            }
-
-            if next.0 < map.len() - 1
-                && is_climbable(&map[next.0][next.1], &map[next.0 + 1][next.1])
-            {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0 + 1, next.1));
-                stack.push(new_state);
-            }
-
-            if next.1 >= 1 && is_climbable(&map[next.0][next.1], &map[next.0][next.1 - 1]) {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0, next.1 - 1));
-                stack.push(new_state);
-            }
-
-            if next.1 < map[next.0].len() - 1
-                && is_climbable(&map[next.0][next.1], &map[next.0][next.1 + 1])
-            {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0, next.1 + 1));
-                stack.push(new_state);
-            }
-        }
-    }
+        .filter(|(_, x)| **x == Position::Start || **x == Position::Value('a'))
+        .map(|(pos, _)| pos);
 
-    unreachable!()
+    climb(map, starts)
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
 
-    let map = parse_map(buf);
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    let starts = map
-        .iter()
-        .enumerate()
-        .flat_map(|(i, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|&(_j, x)| x == &Position::Start || x == &Position::Value('a'))
-                .map(move |(j, _x)| (i, j))
-        })
-        .map(|start| State { path: vec![start] });
-
-    let mut stack = BinaryHeap::new();
-    stack.extend(starts);
-    let mut visited = HashSet::new();
-
-    while let Some(state) = stack.pop() {
-        let next = state.path.last().cloned().unwrap();
-        if !visited.contains(&next) {
-            visited.insert(next);
-            if matches!(map[next.0][next.1], Position::End) {
-                return (state.path.len() - 1).to_string();
-            }
-
-            if next.0 >= 1 && is_climbable(&map[next.0][next.1], &map[next.0 - 1][next.1]) {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0 - 1, next.1));
-                stack.push(new_state);
-            }
-
-            if next.0 < map.len() - 1
-                && is_climbable(&map[next.0][next.1], &map[next.0 + 1][next.1])
-            {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0 + 1, next.1));
-                stack.push(new_state);
-            }
-
-            if next.1 >= 1 && is_climbable(&map[next.0][next.1], &map[next.0][next.1 - 1]) {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0, next.1 - 1));
-                stack.push(new_state);
-            }
-
-            if next.1 < map[next.0].len() - 1
-                && is_climbable(&map[next.0][next.1], &map[next.0][next.1 + 1])
-            {
-                let mut new_state = state.clone();
-                new_state.path.push((next.0, next.1 + 1));
-                stack.push(new_state);
-            }
-        }
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    fn day(&self) -> usize {
+        12
+    }
+
+    fn title(&self) -> &'static str {
+        "Hill Climbing Algorithm"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
     }
 
-    unreachable!()
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{star_one, star_two};
+    use super::{part_one, part_two, star_one, star_two, Position};
+    use crate::{
+        grid::{DenseGrid, Pos},
+        Answer,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -192,8 +155,9 @@ abcryxxl
 accszExk
 acctuvwj
 abdefghi"
-            )),
-            "31"
+            ))
+            .unwrap(),
+            Answer::Integer(31)
         );
     }
 
@@ -206,8 +170,27 @@ abcryxxl
 accszExk
 acctuvwj
 abdefghi"
-            )),
-            "29"
+            ))
+            .unwrap(),
+            Answer::Integer(29)
         );
     }
+
+    #[test]
+    fn test_hand_built_map() {
+        // Parsing only allows one start and one end, but a map made by hand
+        // can have two of each, here either end of a ramp from b up to y.
+        let ramp = ('b'..='y').map(Position::Value);
+        let row = [Position::Start; 2]
+            .into_iter()
+            .chain(ramp)
+            .chain([Position::End; 2])
+            .collect::<Vec<_>>();
+        let mut map = DenseGrid::new(1, row.len(), Position::Start);
+        for (x, position) in row.into_iter().enumerate() {
+            map[Pos::new(x as isize, 0)] = position;
+        }
+        assert_eq!(part_one(&map).unwrap(), 26);
+        assert_eq!(part_two(&map).unwrap(), 25);
+    }
 }
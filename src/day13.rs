@@ -1,3 +1,14 @@
+//! Day 13: Distress Signal
+//!
+//! The packets of a distress signal arrive out of order. [`parse`] reads the
+//! pairs of packets, each a [`Node`], and [`part_one`] and [`part_two`] put
+//! them in order.
+
+use crate::{
+    input::read_input,
+    parse::{blocks, parse_line},
+    Answer, AocError, Solution,
+};
 use itertools::Itertools;
 use nom::{
     branch::alt,
@@ -9,8 +20,9 @@ use nom::{
 };
 use std::{cmp::Ordering, io::BufRead, iter};
 
-#[derive(Debug, PartialEq, Eq)]
-enum Node {
+/// A packet, or a part of one: either an integer or a list of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
     List(Vec<Node>),
     Value(usize),
 }
@@ -27,16 +39,17 @@ fn parse_node(input: &str) -> IResult<&str, Node> {
     alt((delimited(char('['), parse_list, char(']')), parse_value)).parse(input)
 }
 
-fn parse_input(buf: String) -> Vec<(Node, Node)> {
-    buf.split("\n\n")
-        .map(|pair_input| {
-            let (l1, l2) = pair_input.split_once('\n').unwrap();
-            (parse_node(l1).unwrap().1, parse_node(l2).unwrap().1)
-        })
-        .collect()
+fn parse_input(buf: &str) -> Result<Vec<(Node, Node)>, AocError> {
+    AocError::collect(blocks(buf).iter().map(|pair| match pair.as_slice() {
+        [l1, l2] => AocError::both(parse_line(l1, parse_node), parse_line(l2, parse_node)),
+        _ => Err(pair[0].error(pair[0].text, "Expected packets in pairs")),
+    }))
 }
 
-fn is_right_order(left: &Node, right: &Node) -> Ordering {
+/// Compares two packets, with [`Ordering::Less`] meaning they are in the
+/// right order. An integer compared with a list is treated as a list of just
+/// that integer.
+pub fn is_right_order(left: &Node, right: &Node) -> Ordering {
     match (left, right) {
         (Node::List(l1), Node::List(l2)) => {
             for p in l1.iter().zip_longest(l2.iter()) {
@@ -87,28 +100,27 @@ fn is_right_order(left: &Node, right: &Node) -> Ordering {
     }
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let lists = parse_input(buf);
+/// Reads the pairs of packets.
+pub fn parse(input: impl BufRead) -> Result<Vec<(Node, Node)>, AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    lists
+/// The sum of the 1-based indices of the pairs already in the right order.
+pub fn part_one(pairs: &[(Node, Node)]) -> usize {
+    pairs
         .iter()
         .enumerate()
         .filter(|(_, pair)| is_right_order(&pair.0, &pair.1) == Ordering::Less)
         .map(|(i, _)| i + 1)
-        .sum::<usize>()
-        .to_string()
+        .sum()
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let lists = parse_input(buf);
-
-    let mut all_packets: Vec<Node> = lists
-        .into_iter()
-        .flat_map(|pair| vec![pair.0, pair.1])
+/// Puts every packet and two divider packets in order, and multiplies the
+/// 1-based positions of the dividers.
+pub fn part_two(pairs: &[(Node, Node)]) -> usize {
+    let mut all_packets: Vec<Node> = pairs
+        .iter()
+        .flat_map(|pair| vec![pair.0.clone(), pair.1.clone()])
         .collect();
 
     all_packets.push(Node::List(vec![Node::List(vec![Node::Value(2)])]));
@@ -126,12 +138,50 @@ pub fn star_two(mut input: impl BufRead) -> String {
         .position(|n| n == &Node::List(vec![Node::List(vec![Node::Value(6)])]))
         .unwrap()
         + 1;
-    (pos_2 * pos_6).to_string()
+    pos_2 * pos_6
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?).into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?).into())
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    fn day(&self) -> usize {
+        13
+    }
+
+    fn title(&self) -> &'static str {
+        "Distress Signal"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{parse_node, star_one, star_two, Node};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -175,8 +225,9 @@ mod tests {
 [1,[2,[3,[4,[5,6,7]]]],8,9]
 [1,[2,[3,[4,[5,6,0]]]],8,9]
 "
-            )),
-            "13"
+            ))
+            .unwrap(),
+            Answer::Integer(13)
         );
     }
 
@@ -207,8 +258,44 @@ mod tests {
 
 [1,[2,[3,[4,[5,6,7]]]],8,9]
 [1,[2,[3,[4,[5,6,0]]]],8,9]"
-            )),
-            "140"
+            ))
+            .unwrap(),
+            Answer::Integer(140)
+        );
+    }
+
+    #[test]
+    fn test_crlf() {
+        // Packets come in pairs split by blank lines, so the blank lines at
+        // the end must not make an empty pair.
+        let input = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9] \n\n\n"
+            .replace('\n', "\r\n");
+        assert_eq!(
+            star_one(Cursor::new(input.clone())).unwrap(),
+            Answer::Integer(13)
         );
+        assert_eq!(star_two(Cursor::new(input)).unwrap(), Answer::Integer(140));
     }
 }
@@ -1,4 +1,17 @@
-use std::{collections::HashMap, fmt::Display, io::BufRead, ops::RangeInclusive};
+//! Day 14: Regolith Reservoir
+//!
+//! Sand pours into a cave from above. [`parse`] reads the paths of rock in
+//! the cave, and [`part_one`] and [`part_two`] count how much sand comes to
+//! rest.
+
+use crate::{
+    checkpoint,
+    grid::{Grid, Pos, SparseGrid},
+    input::{lines, read_input},
+    point::{Direction, Point2},
+    Answer, AocError, Solution,
+};
+use std::{fmt::Display, io::BufRead, ops::RangeInclusive};
 
 #[derive(Debug, PartialEq, Eq)]
 enum Position {
@@ -6,22 +19,25 @@ enum Position {
     Sand,
 }
 
-fn parse_input(buf: String) -> Vec<Vec<(usize, usize)>> {
-    buf.lines()
-        .map(|line| {
-            line.split("->")
-                .map(|coord| {
-                    let (x, y) = coord.split_once(',').unwrap();
-                    (
-                        x.trim().parse::<usize>().unwrap(),
-                        y.trim()
-                            .parse::<usize>()
-                            .unwrap_or_else(|_e| panic!("Could not parse: {y}")),
-                    )
-                })
-                .collect()
-        })
-        .collect()
+/// The corners of a line of rock, as `(x, y)` with `y` going down.
+pub type Path = Vec<(usize, usize)>;
+
+/// Reads the rock paths, which may only be made of horizontal and vertical
+/// lines.
+fn parse_input(buf: &str) -> Result<Vec<Path>, AocError> {
+    AocError::collect(lines(buf).map(|line| {
+        let path: Vec<_> = AocError::collect(line.text.split("->").map(|coord| {
+            let (x, y) = line.split_once(coord, ",")?;
+            Ok((line.parse::<usize>(x)?, line.parse::<usize>(y)?))
+        }))?;
+        if path
+            .windows(2)
+            .any(|w| w[0].0 != w[1].0 && w[0].1 != w[1].1)
+        {
+            return Err(line.error(line.text, "Diagonal lines are not supported"));
+        }
+        Ok(path)
+    }))
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -32,105 +48,99 @@ enum SandPosition {
 }
 
 struct Map {
-    positions: HashMap<(usize, usize), Position>,
-    max_y: usize,
-    min_x: Option<usize>,
-    max_x: Option<usize>,
-    bottom: Option<usize>,
+    positions: SparseGrid<Position>,
+    max_y: isize,
+    bottom: Option<isize>,
 }
 
 impl Map {
     fn new() -> Map {
         Map {
-            positions: HashMap::new(),
+            positions: SparseGrid::new(),
             max_y: 0,
-            min_x: None,
-            max_x: None,
             bottom: None,
         }
     }
 
-    fn add_rocks(&mut self, mut lists: Vec<Vec<(usize, usize)>>) {
+    fn add_rocks(&mut self, mut lists: Vec<Path>) -> Result<(), AocError> {
         while let Some(mut line) = lists.pop() {
-            let mut previous = line.pop().unwrap();
+            let Some(mut previous) = line.pop() else {
+                continue;
+            };
             while let Some(pos) = line.pop() {
                 if previous.0 == pos.0 {
                     // Vertical Line
-                    self.add_vertical_rock(pos.0, previous.1.min(pos.1)..=pos.1.max(previous.1));
-                } else if previous.1 == pos.1 {
-                    self.add_horizontal_rock(pos.1, previous.0.min(pos.0)..=pos.0.max(previous.0));
+                    self.add_vertical_rock(pos.0, previous.1.min(pos.1)..=pos.1.max(previous.1))?;
                 } else {
-                    panic!("diagonal lines not supported");
+                    self.add_horizontal_rock(pos.1, previous.0.min(pos.0)..=pos.0.max(previous.0))?;
                 }
                 previous = pos;
             }
         }
+        Ok(())
     }
 
-    fn add_horizontal_rock(&mut self, y: usize, range: RangeInclusive<usize>) {
-        let start = *range.start();
-        let end = *range.end();
+    fn add_horizontal_rock(
+        &mut self,
+        y: usize,
+        range: RangeInclusive<usize>,
+    ) -> Result<(), AocError> {
         for x in range {
-            self.positions.insert((x, y), Position::Rock);
+            checkpoint()?;
+            self.positions
+                .insert(Point2::new(x as isize, y as isize), Position::Rock);
         }
-        self.max_x = self.max_x.max(Some(end));
-        self.max_y = self.max_y.max(y);
-        self.min_x = self.min_x.min(Some(start));
+        self.max_y = self.max_y.max(y as isize);
+        Ok(())
     }
 
-    fn add_vertical_rock(&mut self, x: usize, range: RangeInclusive<usize>) {
+    fn add_vertical_rock(
+        &mut self,
+        x: usize,
+        range: RangeInclusive<usize>,
+    ) -> Result<(), AocError> {
         let end = *range.end();
         for y in range {
-            self.positions.insert((x, y), Position::Rock);
+            checkpoint()?;
+            self.positions
+                .insert(Point2::new(x as isize, y as isize), Position::Rock);
         }
-        self.max_x = self.max_x.max(Some(x));
-        self.max_y = self.max_y.max(end);
-        self.min_x = self.min_x.min(Some(x));
+        self.max_y = self.max_y.max(end as isize);
+        Ok(())
     }
 
-    fn add_bottom(&mut self, value: usize) {
+    fn add_bottom(&mut self, value: isize) {
         self.bottom = Some(self.max_y + value);
     }
 
-    fn add_sand(&mut self) -> SandPosition {
-        let mut sand_position = (500, 0);
+    fn add_sand(&mut self) -> Result<SandPosition, AocError> {
+        const SOURCE: Pos = Point2::new(500, 0);
+        let mut sand_position = SOURCE;
         loop {
-            match self.positions.get(&(sand_position.0, sand_position.1 + 1)) {
-                Some(_) => {
-                    if !self
-                        .positions
-                        .contains_key(&(sand_position.0 - 1, sand_position.1 + 1))
-                    {
-                        sand_position = (sand_position.0 - 1, sand_position.1 + 1);
-                    } else if !self
-                        .positions
-                        .contains_key(&(sand_position.0 + 1, sand_position.1 + 1))
-                    {
-                        sand_position = (sand_position.0 + 1, sand_position.1 + 1);
+            checkpoint()?;
+            let below = sand_position + Direction::Down;
+            if self.positions.contains(below) {
+                if let Some(next) = [Point2::new(-1, 1), Point2::new(1, 1)]
+                    .into_iter()
+                    .map(|delta| sand_position + delta)
+                    .find(|next| !self.positions.contains(*next))
+                {
+                    sand_position = next;
+                } else {
+                    self.positions.insert(sand_position, Position::Sand);
+                    if sand_position == SOURCE {
+                        return Ok(SandPosition::Start);
                     } else {
-                        // self.sand_units += 1;
-                        self.positions.insert(sand_position, Position::Sand);
-                        if sand_position == (500, 0) {
-                            return SandPosition::Start;
-                        } else {
-                            return SandPosition::InMap;
-                        }
+                        return Ok(SandPosition::InMap);
                     }
                 }
-                None => {
-                    if self
-                        .bottom
-                        .map(|v| v - 1 == sand_position.1)
-                        .unwrap_or(false)
-                    {
-                        // sand_units += 1;
-                        self.positions.insert(sand_position, Position::Sand);
-                        return SandPosition::InMap;
-                    } else if sand_position.1 > self.max_y {
-                        return SandPosition::Overflow;
-                    }
-                    sand_position = (sand_position.0, sand_position.1 + 1);
-                }
+            } else if self.bottom == Some(below.y) {
+                self.positions.insert(sand_position, Position::Sand);
+                return Ok(SandPosition::InMap);
+            } else if sand_position.y > self.max_y {
+                return Ok(SandPosition::Overflow);
+            } else {
+                sand_position = below;
             }
         }
     }
@@ -138,9 +148,12 @@ impl Map {
 
 impl Display for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for j in 0..=self.max_y {
-            for i in self.min_x.unwrap_or(0)..=self.max_x.unwrap_or(0) {
-                let v = match self.positions.get(&(i, j)) {
+        let Some(bounds) = self.positions.bounds() else {
+            return Ok(());
+        };
+        for y in 0..=self.max_y {
+            for x in bounds.min.x..=bounds.max.x {
+                let v = match self.positions.get(Point2::new(x, y)) {
                     Some(Position::Rock) => '#',
                     Some(Position::Sand) => 'o',
                     None => '.',
@@ -153,49 +166,78 @@ impl Display for Map {
     }
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let lists = parse_input(buf);
-
-    let mut map = Map::new(); // TODO
+/// Reads the paths of rock.
+pub fn parse(input: impl BufRead) -> Result<Vec<Path>, AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    map.add_rocks(lists);
-    // println!("{}", map2);
+/// How much sand comes to rest before the rest falls into the abyss.
+pub fn part_one(paths: &[Path]) -> Result<usize, AocError> {
+    let mut map = Map::new();
+    map.add_rocks(paths.to_vec())?;
 
     let mut sand_units = 0;
-    loop {
-        if SandPosition::Overflow == map.add_sand() {
-            return sand_units.to_string();
-        }
+    while map.add_sand()? != SandPosition::Overflow {
+        sand_units += 1;
+    }
+    Ok(sand_units)
+}
+
+/// How much sand comes to rest on a floor two below the lowest rock before
+/// it blocks the source.
+pub fn part_two(paths: &[Path]) -> Result<usize, AocError> {
+    let mut map = Map::new();
+    map.add_rocks(paths.to_vec())?;
+    map.add_bottom(2);
+
+    let mut sand_units = 1;
+    while map.add_sand()? != SandPosition::Start {
         sand_units += 1;
     }
+    Ok(sand_units)
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let lists = parse_input(buf);
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
 
-    let mut map = Map::new(); // TODO
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    map.add_rocks(lists);
-    // println!("{}", map2);
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
 
-    map.add_bottom(2);
+pub struct Day14;
 
-    let mut sand_units = 0;
-    loop {
-        sand_units += 1;
-        if SandPosition::Start == map.add_sand() {
-            return sand_units.to_string();
-        }
+impl Solution for Day14 {
+    fn day(&self) -> usize {
+        14
+    }
+
+    fn title(&self) -> &'static str {
+        "Regolith Reservoir"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -204,8 +246,9 @@ mod tests {
             star_one(Cursor::new(
                 b"498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9"
-            )),
-            "24"
+            ))
+            .unwrap(),
+            Answer::Integer(24)
         );
     }
 
@@ -215,8 +258,9 @@ mod tests {
             star_two(Cursor::new(
                 b"498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9"
-            )),
-            "93"
+            ))
+            .unwrap(),
+            Answer::Integer(93)
         );
     }
 }
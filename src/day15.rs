@@ -1,44 +1,74 @@
+//! Day 15: Beacon Exclusion Zone
+//!
+//! Sensors each report the beacon closest to them. [`parse`] reads the
+//! [`Report`], and [`part_one`] and [`part_two`] work out where the distress
+//! beacon can and cannot be.
+
+use crate::{
+    checkpoint,
+    input::{lines, read_input},
+    parse::{parse_line, signed, Error},
+    point::Point2,
+    Answer, AocError, Solution,
+};
+use nom::{
+    bytes::complete::tag,
+    character::complete::space1,
+    sequence::{preceded, separated_pair},
+    IResult, Parser,
+};
 use std::{collections::HashMap, fmt::Display, io::BufRead, ops::Add};
 
-fn parse_input(buf: String) -> ((isize, isize), Vec<(Coord, Coord)>) {
-    let mut lines = buf.lines();
-    let meta = lines
-        .next()
-        .map(|l| {
-            let (row, max_search) = l.split_once(' ').unwrap();
-            (
-                row.parse::<isize>().unwrap(),
-                max_search.parse::<isize>().unwrap(),
-            )
-        })
-        .unwrap();
-    let lists = lines
-        .map(|line| {
-            let (sensor_input, beacon_input) = line.split_once(':').unwrap();
-            let sensor = {
-                let (x_input, y_input) = sensor_input.split_once(',').unwrap();
-                (
-                    x_input
-                        .replace("Sensor at x=", "")
-                        .parse::<isize>()
-                        .unwrap(),
-                    y_input.replace(" y=", "").parse::<isize>().unwrap(),
-                )
-            };
-            let beacon = {
-                let (x_input, y_input) = beacon_input.split_once(',').unwrap();
-                (
-                    x_input
-                        .replace(" closest beacon is at x=", "")
-                        .parse::<isize>()
-                        .unwrap(),
-                    y_input.replace(" y=", "").parse::<isize>().unwrap(),
-                )
-            };
-            (sensor, beacon)
-        })
-        .collect();
-    (meta, lists)
+/// `x=2, y=18`
+fn coord(input: &str) -> IResult<&str, Point2, Error<'_>> {
+    separated_pair(
+        preceded(tag("x="), signed),
+        tag(", "),
+        preceded(tag("y="), signed),
+    )
+    .map(|(x, y)| Point2::new(x, y))
+    .parse(input)
+}
+
+/// A sensor and the beacon closest to it.
+pub type Reading = (Point2, Point2);
+
+/// Every sensor's reading, along with where to look for the distress beacon.
+/// These differ between the example and the real input, so the input starts
+/// with a line giving them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The row to check in part one.
+    pub row: isize,
+    /// The distress beacon is somewhere from 0 to this in both directions.
+    pub size: isize,
+    pub readings: Vec<Reading>,
+}
+
+fn parse_input(buf: &str) -> Result<Report, AocError> {
+    let mut lines = lines(buf);
+    let meta = {
+        let line = lines
+            .next()
+            .ok_or_else(|| AocError::parse(1, 1, "Missing row and search size"))?;
+        parse_line(&line, separated_pair(signed, space1, signed))
+    };
+    let lists = AocError::collect(lines.map(|line| {
+        parse_line(
+            &line,
+            separated_pair(
+                preceded(tag("Sensor at "), coord),
+                tag(": closest beacon is at "),
+                coord,
+            ),
+        )
+    }));
+    let ((row, size), readings) = AocError::both(meta, lists)?;
+    Ok(Report {
+        row,
+        size,
+        readings,
+    })
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -48,9 +78,7 @@ enum Position {
     NearestSensor,
 }
 
-type Coord = (isize, isize);
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct Span {
     start: isize,
     end: isize,
@@ -78,7 +106,7 @@ impl Add for Span {
 
 #[derive(Debug)]
 struct Map {
-    positions: HashMap<Coord, Position>,
+    positions: HashMap<Point2, Position>,
     min_y: Option<isize>,
     max_y: Option<isize>,
     min_x: Option<isize>,
@@ -89,7 +117,7 @@ impl Display for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for j in 0..=self.max_y.unwrap() {
             for i in self.min_x.unwrap_or(0)..=self.max_x.unwrap_or(0) {
-                let v = match self.positions.get(&(i, j)) {
+                let v = match self.positions.get(&Point2::new(i, j)) {
                     Some(Position::Sensor) => 'S',
                     Some(Position::Beacon) => 'B',
                     Some(Position::NearestSensor) => '#',
@@ -115,71 +143,70 @@ impl Map {
     }
 
     fn not_beacon_row(&self, row: isize) -> usize {
-        (self.min_x.unwrap()..=self.max_x.unwrap())
-            .filter_map(|i| self.positions.get(&(i, row)).map(|x| ((i, row), x)))
-            .filter(|x| matches!(x.1, Position::NearestSensor))
+        let (Some(min_x), Some(max_x)) = (self.min_x, self.max_x) else {
+            return 0;
+        };
+        (min_x..=max_x)
+            .filter_map(|i| self.positions.get(&Point2::new(i, row)))
+            // A sensor takes up its own position, so there is no beacon
+            // there either.
+            .filter(|x| matches!(x, Position::NearestSensor | Position::Sensor))
             .count()
     }
 
-    fn add_sensor_beacon(&mut self, sensor: Coord, beacon: Coord, row: isize) {
+    fn add_sensor_beacon(
+        &mut self,
+        sensor: Point2,
+        beacon: Point2,
+        row: isize,
+    ) -> Result<(), AocError> {
         self.positions.insert(sensor, Position::Sensor);
         self.positions.insert(beacon, Position::Beacon);
 
-        let max_distance = (sensor.0 - beacon.0).abs() + (sensor.1 - beacon.1).abs();
+        let max_distance = sensor.manhattan(beacon);
 
-        for i in sensor.0 - max_distance..=(sensor.0 + max_distance) {
-            let j = row;
-            if (sensor.0 - i).abs() + (sensor.1 - j).abs() <= max_distance
-                && !self.positions.contains_key(&(i, j))
-            {
-                self.positions.insert((i, j), Position::NearestSensor);
+        for i in sensor.x - max_distance..=(sensor.x + max_distance) {
+            checkpoint()?;
+            let pos = Point2::new(i, row);
+            if sensor.manhattan(pos) <= max_distance && !self.positions.contains_key(&pos) {
+                self.positions.insert(pos, Position::NearestSensor);
             }
         }
-        self.max_x = self.max_x.max(Some(sensor.0 + max_distance));
-        self.max_y = self.max_y.max(Some(sensor.1 + max_distance));
+        self.max_x = self.max_x.max(Some(sensor.x + max_distance));
+        self.max_y = self.max_y.max(Some(sensor.y + max_distance));
         self.min_x = self
             .min_x
-            .map(|x| x.min(sensor.0 - max_distance))
-            .or(Some(sensor.0 - max_distance));
+            .map(|x| x.min(sensor.x - max_distance))
+            .or(Some(sensor.x - max_distance));
         self.min_y = self
             .min_x
-            .map(|x| x.min(sensor.1 - max_distance))
-            .or(Some(sensor.1 - max_distance));
-    }
-}
-
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let ((row, _), lists) = parse_input(buf);
-
-    let mut map = Map::new();
-
-    for pair in lists {
-        map.add_sensor_beacon(pair.0, pair.1, row);
+            .map(|x| x.min(sensor.y - max_distance))
+            .or(Some(sensor.y - max_distance));
+        Ok(())
     }
-
-    map.not_beacon_row(row).to_string()
 }
 
 struct Map2 {
-    min: Option<Coord>,
-    max: Option<Coord>,
+    min: Option<Point2>,
+    max: Option<Point2>,
     spans: Vec<Vec<Span>>,
 }
 
 impl Map2 {
-    fn new_with_size(min: Coord, max: Coord) -> Map2 {
+    fn new_with_size(min: Point2, max: Point2) -> Map2 {
         Map2 {
             min: Some(min),
             max: Some(max),
-            spans: (min.0..=max.0).map(|_| Vec::new()).collect(),
+            spans: (min.x..=max.x).map(|_| Vec::new()).collect(),
         }
     }
 
     fn collapse_spans(&mut self) {
         for v in self.spans.iter_mut() {
             v.sort_by_key(|span| span.start);
+            if v.is_empty() {
+                continue;
+            }
             let tmp = v.remove(0);
             let new_v = v.drain(..).fold(vec![tmp], |mut current, next| {
                 let s = current.pop().unwrap();
@@ -195,48 +222,57 @@ impl Map2 {
         }
     }
 
-    fn not_beacon(&mut self) -> Coord {
+    fn not_beacon(&mut self) -> Option<Point2> {
         self.collapse_spans();
 
         let max = self.max.unwrap();
         let min = self.min.unwrap();
 
-        for i in min.0..=max.0 {
-            let mut j = min.1;
+        // The spans in each column are sorted and merged, so the first gap
+        // is either before one of them or after the last.
+        for i in min.x..=max.x {
+            let mut j = min.y;
             for span in &self.spans[i as usize] {
-                if span.start <= j && j <= span.end {
-                    j = span.end + 1;
-                } else if j < max.1 {
-                    return (i, j);
+                if span.start > j {
+                    break;
                 }
+                j = j.max(span.end + 1);
+            }
+            if j <= max.y {
+                return Some(Point2::new(i, j));
             }
         }
-        unreachable!()
+        None
     }
 
-    fn add_sensor_beacon(&mut self, sensor: Coord, beacon: Coord) {
-        let max_distance = (sensor.0 - beacon.0).abs() + (sensor.1 - beacon.1).abs();
-        let max = self.max.unwrap_or((isize::MAX, isize::MAX));
-        let min = self.min.unwrap_or((isize::MIN, isize::MIN));
+    fn add_sensor_beacon(&mut self, sensor: Point2, beacon: Point2) -> Result<(), AocError> {
+        let max_distance = sensor.manhattan(beacon);
+        let max = self.max.unwrap_or(Point2::new(isize::MAX, isize::MAX));
+        let min = self.min.unwrap_or(Point2::new(isize::MIN, isize::MIN));
 
-        for distance in 0..max_distance {
-            self.spans[((sensor.0 + distance).min(max.0)) as usize].push(Span {
-                start: (sensor.1 - max_distance + distance).max(min.1),
-                end: (sensor.1 + max_distance - distance).min(max.1),
-            });
-
-            self.spans[((sensor.0 - distance).max(min.0)) as usize].push(Span {
-                start: (sensor.1 - max_distance + distance).max(min.1),
-                end: (sensor.1 + max_distance - distance).min(max.1),
-            });
+        for distance in 0..=max_distance {
+            checkpoint()?;
+            let span = Span {
+                start: (sensor.y - max_distance + distance).max(min.y),
+                end: (sensor.y + max_distance - distance).min(max.y),
+            };
+            if span.start > span.end {
+                continue;
+            }
+            for x in [sensor.x - distance, sensor.x + distance] {
+                if min.x <= x && x <= max.x {
+                    self.spans[x as usize].push(span);
+                }
+            }
         }
+        Ok(())
     }
 }
 
 impl Display for Map2 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for i in self.min.unwrap().0..=self.max.unwrap().0 {
-            for j in self.min.unwrap().0..=self.max.unwrap().0 {
+        for i in self.min.unwrap().x..=self.max.unwrap().x {
+            for j in self.min.unwrap().x..=self.max.unwrap().x {
                 write!(
                     f,
                     "{}",
@@ -253,25 +289,123 @@ impl Display for Map2 {
     }
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let ((_row, max_search), lists) = parse_input(buf);
+/// Reads the row to check, the size of the search area and the sensor
+/// readings.
+pub fn parse(input: impl BufRead) -> Result<Report, AocError> {
+    parse_input(&read_input(input)?)
+}
+
+/// How many positions in the row to check cannot hold a beacon.
+pub fn part_one(report: &Report) -> Result<usize, AocError> {
+    let mut map = Map::new();
+
+    for pair in &report.readings {
+        map.add_sensor_beacon(pair.0, pair.1, report.row)?;
+    }
+
+    Ok(map.not_beacon_row(report.row))
+}
+
+/// The tuning frequency of the only place in the search area the distress
+/// beacon can be.
+pub fn part_two(report: &Report) -> Result<isize, AocError> {
+    let mut map = Map2::new_with_size(Point2::ZERO, Point2::new(report.size, report.size));
+
+    for pair in &report.readings {
+        map.add_sensor_beacon(pair.0, pair.1)?;
+    }
+
+    let beacon = map
+        .not_beacon()
+        .ok_or_else(|| AocError::unsolvable("Every position is covered by a sensor"))?;
+
+    Ok(beacon.x * 4000000 + beacon.y)
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+/// Checks every position one at a time, rather than working with spans.
+#[cfg(feature = "reference")]
+pub mod reference {
+    use super::{parse_input, Report};
+    use crate::{input::read_input, point::Point2, Answer, AocError};
+    use std::io::BufRead;
+
+    fn is_covered(readings: &[(Point2, Point2)], pos: Point2) -> bool {
+        readings
+            .iter()
+            .any(|&(sensor, beacon)| sensor.manhattan(pos) <= sensor.manhattan(beacon))
+    }
+
+    pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+        let buf = read_input(input)?;
+        let Report { row, readings, .. } = parse_input(&buf)?;
+        let reach = readings
+            .iter()
+            .map(|&(sensor, beacon)| sensor.x.abs() + sensor.manhattan(beacon))
+            .max()
+            .unwrap_or(0);
+        Ok((-reach..=reach)
+            .map(|x| Point2::new(x, row))
+            .filter(|&pos| is_covered(&readings, pos))
+            .filter(|pos| readings.iter().all(|(_, beacon)| beacon != pos))
+            .count()
+            .into())
+    }
+
+    pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+        let buf = read_input(input)?;
+        let Report { size, readings, .. } = parse_input(&buf)?;
+        let beacon = (0..=size)
+            .flat_map(|x| (0..=size).map(move |y| Point2::new(x, y)))
+            .find(|&pos| !is_covered(&readings, pos))
+            .ok_or_else(|| AocError::unsolvable("Every position is covered by a sensor"))?;
+        Ok((beacon.x * 4000000 + beacon.y).into())
+    }
+}
+
+pub struct Day15;
 
-    let mut map = Map2::new_with_size((0, 0), (max_search, max_search));
+impl Solution for Day15 {
+    fn day(&self) -> usize {
+        15
+    }
+
+    fn title(&self) -> &'static str {
+        "Beacon Exclusion Zone"
+    }
 
-    for pair in lists {
-        map.add_sensor_beacon(pair.0, pair.1);
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
     }
 
-    let beacon = map.not_beacon();
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
 
-    (beacon.0 * 4000000 + beacon.1).to_string()
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::{
+        generate::{self, check, shuffle_lines},
+        Answer,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -293,8 +427,9 @@ Sensor at x=17, y=20: closest beacon is at x=21, y=22
 Sensor at x=16, y=7: closest beacon is at x=15, y=3
 Sensor at x=14, y=3: closest beacon is at x=15, y=3
 Sensor at x=20, y=1: closest beacon is at x=15, y=3"
-            )),
-            "26"
+            ))
+            .unwrap(),
+            Answer::Integer(26)
         );
     }
 
@@ -317,8 +452,94 @@ Sensor at x=17, y=20: closest beacon is at x=21, y=22
 Sensor at x=16, y=7: closest beacon is at x=15, y=3
 Sensor at x=14, y=3: closest beacon is at x=15, y=3
 Sensor at x=20, y=1: closest beacon is at x=15, y=3"
-            )),
-            "56000011"
+            ))
+            .unwrap(),
+            Answer::Integer(56000011)
+        );
+    }
+
+    #[test]
+    fn test_sensor_in_row() {
+        // The sensor's own position cannot be the beacon either.
+        assert_eq!(
+            star_one(Cursor::new(
+                "0 4\nSensor at x=0, y=0: closest beacon is at x=2, y=0"
+            ))
+            .unwrap(),
+            Answer::Integer(4)
+        );
+    }
+
+    #[test]
+    fn test_search_area_edges() {
+        let tuning_frequency = |readings: &str| star_two(Cursor::new(format!("0 2\n{readings}")));
+
+        // The left and right tips of a sensor's diamond are covered too,
+        // leaving (2, 1) and not (2, 0).
+        assert_eq!(
+            tuning_frequency(
+                "Sensor at x=0, y=0: closest beacon is at x=0, y=-2
+Sensor at x=1, y=2: closest beacon is at x=1, y=1"
+            )
+            .unwrap(),
+            Answer::Integer(8000001)
+        );
+        // The gap is after the last span of the last column.
+        assert_eq!(
+            tuning_frequency("Sensor at x=0, y=-2: closest beacon is at x=2, y=-5").unwrap(),
+            Answer::Integer(8000002)
+        );
+        // Columns of a sensor outside the area are left out, rather than
+        // piled onto its edge.
+        assert_eq!(
+            tuning_frequency(
+                "Sensor at x=1, y=4: closest beacon is at x=-1, y=3
+Sensor at x=-1, y=-1: closest beacon is at x=0, y=-4"
+            )
+            .unwrap(),
+            Answer::Integer(8000001)
+        );
+    }
+
+    #[test]
+    fn test_sensor_order() {
+        check(
+            100,
+            |rng| {
+                let input = generate::sensors(rng, 10, 1000);
+                let shuffled = shuffle_lines(rng, &input, 1);
+                (input, shuffled)
+            },
+            |(input, shuffled)| {
+                star_one(Cursor::new(input)).unwrap() == star_one(Cursor::new(shuffled)).unwrap()
+            },
+        );
+    }
+
+    #[cfg(feature = "reference")]
+    #[test]
+    fn test_against_reference() {
+        use super::reference;
+        use crate::generate::differential;
+
+        differential(
+            300,
+            |rng| {
+                let count = rng.range(1..6) as usize;
+                generate::sensors(rng, count, 20)
+            },
+            |input| {
+                (
+                    star_one(Cursor::new(input)).ok(),
+                    star_two(Cursor::new(input)).ok(),
+                )
+            },
+            |input| {
+                (
+                    reference::star_one(Cursor::new(input)).ok(),
+                    reference::star_two(Cursor::new(input)).ok(),
+                )
+            },
         );
     }
 }
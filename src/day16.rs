@@ -1,238 +1,249 @@
+//! Day 16: Proboscidea Volcanium
+//!
+//! Valves release pressure once opened, and there is only so much time to
+//! walk between them. [`parse`] reads the [`Valve`]s, and [`part_one`] and
+//! [`part_two`] find the most pressure that can be released alone and with
+//! an elephant's help.
+
+use crate::{
+    checkpoint,
+    input::{lines, read_input},
+    parse::{comma_list, parse_line, unsigned},
+    search::Search,
+    Answer, AocError, Solution,
+};
+use nom::{branch::alt, bytes::complete::tag, character::complete::alpha1, sequence::preceded};
 use std::{
-    collections::{BinaryHeap, HashMap, HashSet},
+    collections::{HashMap, HashSet},
     io::BufRead,
 };
 
-use regex::Regex;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Valve {
+    /// Pressure released each minute once it is open.
+    pub flow_rate: usize,
+    /// The valves its tunnels lead to.
+    pub tunnels: Vec<String>,
+}
 
-// TODO Add check about if possible to get maximum with remaining valves and time.
+/// Every valve, by name.
+pub type Valves = HashMap<String, Valve>;
 
-struct State<'a> {
-    room: &'a str,
-    time_left: usize,
-    pressure_released: usize,
-    opened: HashSet<&'a str>,
+/// Reads the valves, checking that the starting valve exists and that every
+/// tunnel leads somewhere.
+fn parse_input(buf: &str) -> Result<Valves, AocError> {
+    let valves: Vec<_> = AocError::collect(lines(buf).map(|line| {
+        let (name, flow_rate, output_valves) = parse_line(
+            &line,
+            (
+                preceded(tag("Valve "), alpha1),
+                preceded(tag(" has flow rate="), unsigned),
+                preceded(
+                    alt((
+                        tag("; tunnels lead to valves "),
+                        tag("; tunnel leads to valve "),
+                    )),
+                    comma_list(alpha1),
+                ),
+            ),
+        )?;
+        Ok((line, name, flow_rate, output_valves))
+    }))?;
+
+    let names = valves
+        .iter()
+        .map(|(_, name, _, _)| *name)
+        .collect::<HashSet<_>>();
+    let mut problems = Vec::new();
+    if !names.contains("AA") {
+        problems.push(AocError::unsolvable("There is no valve AA to start from"));
+    }
+    for (line, _, _, output_valves) in &valves {
+        problems.extend(
+            output_valves
+                .iter()
+                .filter(|v| !names.contains(*v))
+                .map(|missing| {
+                    line.error(missing, format!("Tunnel leads to unknown valve {missing}"))
+                }),
+        );
+    }
+    AocError::check(problems)?;
+
+    Ok(valves
+        .into_iter()
+        .map(|(_, name, flow_rate, output_valves)| {
+            let valve = Valve {
+                flow_rate,
+                tunnels: output_valves.into_iter().map(str::to_string).collect(),
+            };
+            (name.to_string(), valve)
+        })
+        .collect())
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct State2<'a> {
-    room: &'a str,
-    elephant: &'a str,
-    time_left: usize,
-    pressure_released: usize,
-    opened: HashSet<&'a str>,
+/// The valves worth opening, and how many minutes it takes to walk between
+/// them.
+struct Network {
+    flow_rates: Vec<usize>,
+    /// `distances[from][to]`, or `None` if there is no way there.
+    distances: Vec<Vec<Option<usize>>>,
+    /// Where the walk starts from, valve AA.
+    start: usize,
 }
 
-impl PartialOrd for State2<'_> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+/// Drops the valves with no flow, other than the starting one, and works out
+/// the shortest walk between each pair that are left.
+fn compress(map: &Valves) -> Result<Network, AocError> {
+    let mut names = map
+        .iter()
+        .filter(|(name, valve)| valve.flow_rate > 0 || *name == "AA")
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    if names.len() > u64::BITS as usize {
+        return Err(AocError::unsolvable(format!(
+            "Too many working valves: {}",
+            names.len()
+        )));
     }
+
+    let distances = names
+        .iter()
+        .map(|&from| {
+            let reached =
+                Search::new(from).bfs_all(|&room| map[room].tunnels.iter().map(String::as_str))?;
+            Ok(names.iter().map(|to| reached.distance(to)).collect())
+        })
+        .collect::<Result<_, AocError>>()?;
+
+    Ok(Network {
+        flow_rates: names.iter().map(|&name| map[name].flow_rate).collect(),
+        distances,
+        start: names.iter().position(|&name| name == "AA").unwrap(),
+    })
 }
 
-impl Ord for State2<'_> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.pressure_released.cmp(&other.pressure_released)
+/// Tries opening the valves in every order that fits in the time, keeping
+/// the most pressure released for each set of valves opened.
+fn explore(
+    network: &Network,
+    at: usize,
+    time_left: usize,
+    opened: u64,
+    released: usize,
+    best: &mut HashMap<u64, usize>,
+) -> Result<(), AocError> {
+    checkpoint()?;
+    let entry = best.entry(opened).or_default();
+    *entry = (*entry).max(released);
+
+    for (next, &flow_rate) in network.flow_rates.iter().enumerate() {
+        if flow_rate == 0 || opened & (1 << next) != 0 {
+            continue;
+        }
+        // Walking there, then a minute to open it.
+        let Some(cost) = network.distances[at][next].map(|distance| distance + 1) else {
+            continue;
+        };
+        if cost < time_left {
+            let time_left = time_left - cost;
+            explore(
+                network,
+                next,
+                time_left,
+                opened | (1 << next),
+                released + flow_rate * time_left,
+                best,
+            )?;
+        }
     }
+    Ok(())
 }
 
-fn parse_input(buf: String) -> HashMap<String, (usize, Vec<String>)> {
-    let input_re = Regex::new(r"^Valve (\w\w) has flow rate=(\d+)$").unwrap();
-    let output_re = Regex::new(r"leads? to valves? (.+)").unwrap();
+fn best_releases(network: &Network, time: usize) -> Result<HashMap<u64, usize>, AocError> {
+    let mut best = HashMap::new();
+    explore(network, network.start, time, 0, 0, &mut best)?;
+    Ok(best)
+}
 
-    let lines = buf.lines().map(|line| {
-        let (input, output) = line.split_once(';').unwrap();
-        let (name, flow_rate) = {
-            let captures = input_re
-                .captures(input)
-                .unwrap_or_else(|| panic!("Could not parse {input}"));
-            (
-                captures[1].trim().to_string(),
-                captures[2].parse::<usize>().unwrap(),
-            )
-        };
-        let output_valves = {
-            let captures = output_re
-                .captures(output)
-                .unwrap_or_else(|| panic!("Could not parse {input}"));
-            captures[1]
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect()
-        };
-        (name, (flow_rate, output_valves))
-    });
-    lines.collect()
+/// Reads the valves, checking that the starting valve exists and that every
+/// tunnel leads somewhere.
+pub fn parse(input: impl BufRead) -> Result<Valves, AocError> {
+    parse_input(&read_input(input)?)
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// The most pressure that can be released in 30 minutes, starting from AA.
+pub fn part_one(valves: &Valves) -> Result<usize, AocError> {
+    let network = compress(valves)?;
 
-    let map = parse_input(buf);
+    let best = best_releases(&network, 30)?;
+    Ok(best.values().max().copied().unwrap_or(0))
+}
 
-    let mut stack = vec![State {
-        room: "AA",
-        time_left: 30,
-        pressure_released: 0,
-        opened: HashSet::new(),
-    }]; // TODO Change to binary heap
+/// The most pressure that can be released in 26 minutes with an elephant
+/// opening valves too.
+pub fn part_two(valves: &Valves) -> Result<usize, AocError> {
+    let network = compress(valves)?;
 
+    // We and the elephant each take a separate set of valves.
+    let best = best_releases(&network, 26)?.into_iter().collect::<Vec<_>>();
     let mut max_pressure = 0;
-    let mut seen = HashSet::new();
-
-    while let Some(state) = stack.pop() {
-        if seen.contains(&(state.room, state.time_left, state.pressure_released)) {
-            continue;
-        }
-        seen.insert((state.room, state.time_left, state.pressure_released));
-        let (flow_rate, connected) = map
-            .get(state.room)
-            .unwrap_or_else(|| panic!("Failed to get '{}' from {:?}", state.room, map));
-        if state.time_left == 0 {
-            if max_pressure < state.pressure_released {
-                max_pressure = state.pressure_released;
-            }
-        } else {
-            if flow_rate > &0 && !state.opened.contains(&state.room) {
-                let mut new_state = State {
-                    room: state.room,
-                    time_left: state.time_left - 1,
-                    pressure_released: state.pressure_released + flow_rate * (state.time_left - 1),
-                    opened: state.opened.clone(),
-                };
-                new_state.opened.insert(new_state.room);
-                stack.push(new_state);
-            }
-            for valve in connected {
-                stack.push(State {
-                    room: valve.as_str(),
-                    time_left: state.time_left - 1,
-                    pressure_released: state.pressure_released,
-                    opened: state.opened.clone(),
-                })
+    for (i, &(mine, my_pressure)) in best.iter().enumerate() {
+        checkpoint()?;
+        for &(elephants, elephant_pressure) in &best[i..] {
+            if mine & elephants == 0 {
+                max_pressure = max_pressure.max(my_pressure + elephant_pressure);
             }
         }
     }
-    max_pressure.to_string()
+    Ok(max_pressure)
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let map = parse_input(buf);
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
 
-    let mut stack = BinaryHeap::new();
-    stack.push(State2 {
-        room: "AA",
-        elephant: "AA",
-        time_left: 26,
-        pressure_released: 0,
-        opened: HashSet::new(),
-    });
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    let mut max_pressure = 0;
-    let mut seen = HashSet::new();
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
 
-    while let Some(state) = stack.pop() {
-        if seen.contains(&(
-            state.room,
-            state.elephant,
-            state.time_left,
-            state.pressure_released,
-        )) {
-            continue;
-        }
-        seen.insert((
-            state.room,
-            state.elephant,
-            state.time_left,
-            state.pressure_released,
-        ));
+pub struct Day16;
 
-        if state.time_left == 0 {
-            max_pressure = max_pressure.max(state.pressure_released)
-        } else {
-            let (flow_rate, connected) = map
-                .get(state.room)
-                .unwrap_or_else(|| panic!("Failed to get '{}' from {:?}", state.room, map));
-            let (elephant_flow_rate, elephant_connected) = map
-                .get(state.elephant)
-                .unwrap_or_else(|| panic!("Failed to get '{}' from {:?}", state.elephant, map));
+impl Solution for Day16 {
+    fn day(&self) -> usize {
+        16
+    }
 
-            // Both Turn valves on
-            if state.room != state.elephant
-                && flow_rate > &0
-                && elephant_flow_rate > &0
-                && !state.opened.contains(&state.room)
-                && !state.opened.contains(&state.elephant)
-            {
-                let mut new_state = State2 {
-                    room: state.room,
-                    elephant: state.elephant,
-                    time_left: state.time_left - 1,
-                    pressure_released: state.pressure_released
-                        + flow_rate * (state.time_left - 1)
-                        + elephant_flow_rate * (state.time_left - 1),
-                    opened: state.opened.clone(),
-                };
-                new_state.opened.insert(new_state.room);
-                new_state.opened.insert(new_state.elephant);
-                stack.push(new_state);
-            }
+    fn title(&self) -> &'static str {
+        "Proboscidea Volcanium"
+    }
 
-            // I turn valve, elephent move
-            if flow_rate > &0 && !state.opened.contains(&state.room) {
-                for elephant in elephant_connected.iter() {
-                    let mut new_state = State2 {
-                        room: state.room,
-                        elephant,
-                        time_left: state.time_left - 1,
-                        pressure_released: state.pressure_released
-                            + flow_rate * (state.time_left - 1),
-                        opened: state.opened.clone(),
-                    };
-                    new_state.opened.insert(new_state.room);
-                    stack.push(new_state);
-                }
-            }
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
 
-            // I move, elephent turn
-            if elephant_flow_rate > &0 && !state.opened.contains(&state.elephant) {
-                for room in connected.iter() {
-                    let mut new_state = State2 {
-                        room,
-                        elephant: state.elephant,
-                        time_left: state.time_left - 1,
-                        pressure_released: state.pressure_released
-                            + elephant_flow_rate * (state.time_left - 1),
-                        opened: state.opened.clone(),
-                    };
-                    new_state.opened.insert(new_state.elephant);
-                    stack.push(new_state);
-                }
-            }
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
 
-            // Both move
-            for room in connected {
-                for elephant in elephant_connected {
-                    stack.push(State2 {
-                        room,
-                        elephant,
-                        time_left: state.time_left - 1,
-                        pressure_released: state.pressure_released,
-                        opened: state.opened.clone(),
-                    });
-                }
-            }
-        }
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
     }
-    max_pressure.to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
+    use crate::{
+        generate::{self, check, shuffle_lines},
+        Answer,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -249,8 +260,9 @@ Valve GG has flow rate=0; tunnels lead to valves FF, HH
 Valve HH has flow rate=22; tunnel leads to valve GG
 Valve II has flow rate=0; tunnels lead to valves AA, JJ
 Valve JJ has flow rate=21; tunnel leads to valve II"
-            )),
-            "1651"
+            ))
+            .unwrap(),
+            Answer::Integer(1651)
         );
     }
 
@@ -268,8 +280,31 @@ Valve GG has flow rate=0; tunnels lead to valves FF, HH
 Valve HH has flow rate=22; tunnel leads to valve GG
 Valve II has flow rate=0; tunnels lead to valves AA, JJ
 Valve JJ has flow rate=21; tunnel leads to valve II"
-            )),
-            "1707"
+            ))
+            .unwrap(),
+            Answer::Integer(1707)
+        );
+    }
+
+    #[test]
+    fn test_valve_order() {
+        check(
+            30,
+            |rng| {
+                let count = rng.range(2..12) as usize;
+                let input = generate::valves(rng, count);
+                let shuffled = shuffle_lines(rng, &input, 0);
+                (input, shuffled)
+            },
+            |(input, shuffled)| {
+                let answers = |input: &String| {
+                    (
+                        star_one(Cursor::new(input)).unwrap(),
+                        star_two(Cursor::new(input)).unwrap(),
+                    )
+                };
+                answers(input) == answers(shuffled)
+            },
         );
     }
 }
@@ -1,13 +1,29 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Display,
-    io::BufRead,
+//! Day 17: Pyroclastic Flow
+//!
+//! Rocks fall into a narrow cavern, pushed about by jets of gas. [`parse`]
+//! reads the jet pattern as [`Command`]s, and [`part_one`] and [`part_two`]
+//! work out how tall the tower of rocks grows.
+
+use crate::{
+    cycle::metric_after,
+    grid::{Grid, Pos, SparseGrid},
+    input::{lines, read_input},
+    point::Point2,
+    Answer, AocError, Solution,
 };
+use std::{fmt::Display, io::BufRead};
 
+/// `(x, y)`, with `y` going up.
 type Coord = (i64, i64);
 
-#[derive(Debug)]
-enum Command {
+/// Where `coord` is kept in a cavern's grid.
+fn cell((x, y): Coord) -> Pos {
+    Point2::new(x as isize, y as isize)
+}
+
+/// Which way a jet of gas pushes the falling rock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
     Right,
     Left,
 }
@@ -46,7 +62,7 @@ impl Rock {
         // Could change to `any`
         if !self.template.iter().any(|p| {
             let new_pos = (self.origin.0 + p.0 + dir, self.origin.1 + p.1);
-            cavern.positions.contains(&new_pos) || new_pos.0 < 0 || new_pos.0 >= 7
+            cavern.positions.contains(cell(new_pos)) || new_pos.0 < 0 || new_pos.0 >= 7
         }) {
             self.origin.0 += dir;
             true
@@ -59,7 +75,7 @@ impl Rock {
         if !self.template.iter().any(|p| {
             cavern
                 .positions
-                .contains(&(self.origin.0 + p.0, self.origin.1 + p.1 - 1))
+                .contains(cell((self.origin.0 + p.0, self.origin.1 + p.1 - 1)))
             // || self.origin.1 + p.1 - 1 == 0
         }) {
             self.origin.1 -= 1;
@@ -81,7 +97,7 @@ impl Rock {
 }
 
 struct Cavern {
-    positions: HashSet<Coord>,
+    positions: SparseGrid<()>,
     max_y: i64,
 }
 
@@ -93,7 +109,7 @@ impl Display for Cavern {
                 "{}",
                 (0..7)
                     .map(|x| {
-                        if self.positions.contains(&(x, height)) {
+                        if self.positions.contains(cell((x, height))) {
                             '#'
                         } else {
                             '.'
@@ -108,13 +124,13 @@ impl Display for Cavern {
 
 #[derive(Debug, PartialEq, Eq)]
 struct Cavern2 {
-    positions: HashMap<usize, [bool; 7]>,
+    positions: SparseGrid<()>,
 }
 
 impl Cavern2 {
     fn new() -> Cavern2 {
         Cavern2 {
-            positions: HashMap::new(),
+            positions: SparseGrid::new(),
         }
     }
 
@@ -126,13 +142,7 @@ impl Cavern2 {
 
         if rock.template.iter().any(|p| {
             let new_pos = (rock.origin.0 + p.0 + dir, rock.origin.1 + p.1);
-            new_pos.0 < 0
-                || new_pos.0 >= 7
-                || self
-                    .positions
-                    .get(&(new_pos.1 as usize))
-                    .map(|x| x[new_pos.0 as usize])
-                    .unwrap_or(false)
+            new_pos.0 < 0 || new_pos.0 >= 7 || self.positions.contains(cell(new_pos))
         }) {
             false
         } else {
@@ -144,9 +154,7 @@ impl Cavern2 {
     fn fall(&mut self, rock: &mut Rock) -> bool {
         if rock.template.iter().any(|p| {
             self.positions
-                .get(&((rock.origin.1 + p.1 - 1) as usize))
-                .map(|c| c[(rock.origin.0 + p.0) as usize])
-                .unwrap_or(false)
+                .contains(cell((rock.origin.0 + p.0, rock.origin.1 + p.1 - 1)))
                 || rock.origin.1 + p.1 - 1 < 0
         }) {
             false
@@ -158,14 +166,8 @@ impl Cavern2 {
 
     fn heights(&self) -> [isize; 7] {
         let mut heights = [0; 7];
-        for (y, row) in self.positions.iter() {
-            // println!("{:?} {:?}", y, row);
-            for (x, col) in row.iter().enumerate() {
-                // println!("\t{:?} {:?}: {:?}", x, col, heights);
-                if *col {
-                    heights[x] = heights[x].max(*y as isize + 1);
-                }
-            }
+        for Point2 { x, y } in self.positions.positions() {
+            heights[x as usize] = heights[x as usize].max(y + 1);
         }
         heights
     }
@@ -175,27 +177,25 @@ impl Cavern2 {
     }
 
     fn place_rock(&mut self, rock: Rock) {
-        for (x, y) in rock.positions() {
-            self.positions.entry(y as usize).or_insert([false; 7])[x as usize] = true;
-        }
-        if self.positions.keys().len() > 50 {
-            let min = *self.positions.keys().min().unwrap();
-            self.positions.remove(&min);
-        }
-    }
-
-    fn relative_heights(&self) -> [isize; 7] {
-        let mut heights = [0; 7];
-        let min = self.min_height();
-        let absolute_heights = self.heights();
-        for (i, height) in heights.iter_mut().enumerate() {
-            *height = absolute_heights[i] - min;
-        }
-        heights
+        self.positions
+            .extend(rock.positions().map(|coord| (cell(coord), ())));
+        // Rocks never fall this far into the tower, so deeper rows can go.
+        let top = self.max_height();
+        self.positions.retain(|pos, _| pos.y >= top - 50);
     }
 
-    fn min_height(&self) -> isize {
-        *self.heights().iter().min().unwrap()
+    /// Every cell that is kept, measured down from the top of the tower.
+    /// Anything below these is buried, so two caverns with the same surface
+    /// carry on the same way.
+    fn surface(&self) -> Vec<Pos> {
+        let top = self.max_height();
+        let mut cells = self
+            .positions
+            .positions()
+            .map(|Point2 { x, y }| Point2::new(x, top - y))
+            .collect::<Vec<_>>();
+        cells.sort();
+        cells
     }
 
     fn drop_rock(&mut self, mut rock: Rock, commands: &[Command], command_index: &mut usize) {
@@ -210,63 +210,49 @@ impl Cavern2 {
         }
         self.place_rock(rock);
     }
-
-    fn add_height(&mut self, height: isize) {
-        let new_positions = self
-            .positions
-            .iter()
-            .map(|(y, row)| (y + height as usize, *row))
-            .collect::<HashMap<_, _>>();
-        self.positions = new_positions;
-    }
 }
 
-fn parse_input(buf: String) -> Vec<Command> {
-    buf.lines()
+fn parse_input(buf: &str) -> Result<Vec<Command>, AocError> {
+    let line = lines(buf)
         .next()
-        .unwrap()
-        .chars()
-        .map(|c| match c {
-            '>' => Command::Right,
-            '<' => Command::Left,
-            x => panic!("{}", x),
-        })
-        .collect()
+        .ok_or_else(|| AocError::parse(1, 1, "Missing jet pattern"))?;
+    let commands: Vec<_> = AocError::collect(line.text.char_indices().map(|(i, c)| match c {
+        '>' => Ok(Command::Right),
+        '<' => Ok(Command::Left),
+        x => Err(line.error(&line.text[i..], format!("Unknown jet direction: {x}"))),
+    }))?;
+    if commands.is_empty() {
+        return Err(line.error(line.text, "Missing jet pattern"));
+    }
+    Ok(commands)
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let commands = parse_input(buf);
-
-    let rock_types = [
+/// The five rocks, in the order they fall.
+fn rock_types() -> [Rock; 5] {
+    [
         Rock::new(vec![(0, 0), (1, 0), (2, 0), (3, 0)]),
         Rock::new(vec![(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)]),
         Rock::new(vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]),
         Rock::new(vec![(0, 0), (0, 1), (0, 2), (0, 3)]),
         Rock::new(vec![(0, 0), (1, 0), (0, 1), (1, 1)]),
-    ];
+    ]
+}
 
+/// How tall the tower is after `rocks` rocks, dropping every one of them.
+fn simulate(commands: &[Command], rocks: usize) -> i64 {
     let mut cavern = Cavern {
-        positions: HashSet::new(),
+        positions: SparseGrid::new(),
         max_y: 0,
     };
 
-    cavern.positions.insert((0, 0));
-    cavern.positions.insert((1, 0));
-    cavern.positions.insert((2, 0));
-    cavern.positions.insert((3, 0));
-    cavern.positions.insert((4, 0));
-    cavern.positions.insert((5, 0));
-    cavern.positions.insert((6, 0));
+    // The floor
+    cavern.positions.extend((0..7).map(|x| (cell((x, 0)), ())));
 
-    let mut rock_iter = rock_types.iter().cycle();
+    let mut rock_iter = rock_types().into_iter().cycle();
     let mut commands = commands.iter().cycle();
-    let mut rock_count = 0;
 
-    while rock_count < 2022 {
-        let mut rock = rock_iter.next().unwrap().clone();
+    for _rock in 0..rocks {
+        let mut rock = rock_iter.next().unwrap();
         let mut did_fall = true;
         rock.set_current_position((2, cavern.max_y + 4));
 
@@ -276,96 +262,113 @@ pub fn star_one(mut input: impl BufRead) -> String {
             }
             did_fall = rock.fall(&cavern);
         }
-        cavern.positions.extend(rock.positions());
+        cavern
+            .positions
+            .extend(rock.positions().map(|coord| (cell(coord), ())));
         cavern.max_y = cavern.max_y.max(rock.highest());
-
-        rock_count += 1;
     }
 
-    cavern.max_y.to_string()
+    cavern.max_y
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// How tall the tower is after `rocks` rocks, only keeping the top of the
+/// tower and skipping ahead once the rocks start landing the same way.
+pub fn height_after(commands: &[Command], rocks: usize) -> Result<i64, AocError> {
+    let rock_types = rock_types();
+
+    // The cavern, the next jet and how many rocks have fallen.
+    let mut state = (Cavern2::new(), 0, 0);
+
+    metric_after(
+        &mut state,
+        rocks,
+        |(cavern, command_index, rock_count)| {
+            (
+                rock_count % rock_types.len(),
+                *command_index,
+                cavern.surface(),
+            )
+        },
+        |(cavern, _, _)| cavern.max_height() as i64,
+        |(cavern, command_index, rock_count)| {
+            let rock = rock_types[*rock_count % rock_types.len()].clone();
+            cavern.drop_rock(rock, commands, command_index);
+            *rock_count += 1;
+        },
+    )
+}
 
-    let commands = parse_input(buf);
+/// Reads the jet pattern, which repeats for as long as rocks fall.
+pub fn parse(input: impl BufRead) -> Result<Vec<Command>, AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    let rock_types = [
-        Rock::new(vec![(0, 0), (1, 0), (2, 0), (3, 0)]),
-        Rock::new(vec![(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)]),
-        Rock::new(vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]),
-        Rock::new(vec![(0, 0), (0, 1), (0, 2), (0, 3)]),
-        Rock::new(vec![(0, 0), (1, 0), (0, 1), (1, 1)]),
-    ];
-
-    let mut cavern = Cavern2::new();
-
-    let mut command_index = 0;
-
-    let mut rock_count = 0;
-
-    let finish_rock_count = 1_000_000_000_000;
-    // 1_199_999_999_961
-    let mut seen = HashMap::new();
-
-    while rock_count <= finish_rock_count {
-        let rock_index = rock_count % rock_types.len();
-        // println!("{}: {:?}", rock_count, cavern.heights());
-        if let Some((seen_rock_count, seen_height)) =
-            seen.get(&(cavern.relative_heights(), rock_index, command_index))
-        {
-            // println!(
-            //     "We have seen this before! {} {} {} {}",
-            //     rock_count,
-            //     seen_rock_count,
-            //     seen_height,
-            //     cavern.min_height()
-            // );
-            // We have seen this before so we can skip ahead
-            let diff = rock_count - seen_rock_count;
-            let remaining = finish_rock_count - rock_count;
-            let skip = remaining / diff;
-            assert!(
-                skip * seen_rock_count + rock_count <= finish_rock_count,
-                "{}",
-                skip * seen_rock_count + rock_count
-            );
-            // println!(
-            //     "{}, {} {} {} {}",
-            //     rock_count, diff, remaining, skip, finish_rock_count
-            // );
-            cavern.add_height((cavern.min_height() - seen_height) * skip as isize);
-            rock_count += diff * skip;
-            // rock_count += skip * diff;
-            // println!("{}: {:?}", rock_count, cavern.heights());
-            // command_index += 1;
-            // command_index %= commands.len();
-            // break
-        } else {
-            seen.insert(
-                (cavern.relative_heights(), rock_index, command_index),
-                (rock_count, cavern.min_height()),
-            );
-        }
+/// How tall the tower is after 2022 rocks.
+pub fn part_one(commands: &[Command]) -> i64 {
+    simulate(commands, 2022)
+}
+
+/// How tall the tower is after a trillion rocks.
+pub fn part_two(commands: &[Command]) -> Result<i64, AocError> {
+    height_after(commands, 1_000_000_000_000)
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?).into())
+}
 
-        let rock = rock_types[rock_index].clone();
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+/// Drops every rock, with none of the skipping ahead.
+#[cfg(feature = "reference")]
+pub mod reference {
+    use super::{parse_input, simulate};
+    use crate::{input::read_input, Answer, AocError};
+    use std::io::BufRead;
+
+    /// How tall the tower is after `rocks` rocks.
+    pub fn height_after(input: impl BufRead, rocks: usize) -> Result<Answer, AocError> {
+        let buf = read_input(input)?;
+        let commands = parse_input(&buf)?;
+        Ok(simulate(&commands, rocks).into())
+    }
+}
 
-        cavern.drop_rock(rock, &commands, &mut command_index);
+pub struct Day17;
 
-        rock_count += 1;
-        // if rock_count >= 999999999950 || rock_count == 999999999995 {
-        //     println!("{}: {:?}", rock_count, cavern.heights());
-        //     break;
-        // }
+impl Solution for Day17 {
+    fn day(&self) -> usize {
+        17
     }
 
-    (cavern.max_height() - 1).to_string()
+    fn title(&self) -> &'static str {
+        "Pyroclastic Flow"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two, Cavern2, Command, Rock};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -539,17 +542,37 @@ mod tests {
     #[test]
     fn test_star_one() {
         assert_eq!(
-            star_one(Cursor::new(b">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>")),
-            "3068"
+            star_one(Cursor::new(b">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>")).unwrap(),
+            Answer::Integer(3068)
         );
     }
 
     #[test]
-    #[ignore = "not yet implemented"]
     fn test_star_two() {
         assert_eq!(
-            star_two(Cursor::new(b">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>")),
-            "1514285714288"
+            star_two(Cursor::new(b">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>")).unwrap(),
+            Answer::Integer(1514285714288)
+        );
+    }
+
+    #[cfg(feature = "reference")]
+    #[test]
+    fn test_against_reference() {
+        use super::{height_after, parse_input, reference};
+        use crate::generate::{self, differential};
+
+        differential(
+            100,
+            |rng| {
+                let len = rng.range(1..50) as usize;
+                let rocks = rng.range(0..3000) as usize;
+                (generate::jets(rng, len), rocks)
+            },
+            |(input, rocks)| {
+                let commands = parse_input(input).unwrap();
+                height_after(&commands, *rocks).ok().map(Answer::from)
+            },
+            |(input, rocks)| reference::height_after(Cursor::new(input), *rocks).ok(),
         );
     }
 }
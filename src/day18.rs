@@ -1,22 +1,36 @@
+//! Day 18: Boiling Boulders
+//!
+//! A droplet of lava is scanned as a set of unit cubes. [`parse`] reads the
+//! cubes, and [`part_one`] and [`part_two`] measure the droplet's surface
+//! area, first counting the air pockets inside it and then not.
+
+use crate::{
+    checkpoint,
+    input::{lines, read_input},
+    point::Point3,
+    Answer, AocError, Solution,
+};
 use std::{collections::HashSet, io::BufRead};
 
-fn parse_input(buf: &str) -> impl Iterator<Item = (isize, isize, isize)> + '_ {
-    buf.lines().map(|line| {
-        let mut values = line.split(',').map(|v| v.parse::<isize>().unwrap());
-        (
-            values.next().unwrap(),
-            values.next().unwrap(),
-            values.next().unwrap(),
-        )
-    })
+fn parse_input(buf: &str) -> Result<Vec<Point3>, AocError> {
+    AocError::collect(lines(buf).map(|line| {
+        let (x, rest) = line.split_once(line.text, ",")?;
+        let (y, z) = line.split_once(rest, ",")?;
+        Ok(Point3::new(
+            line.parse::<isize>(x)?,
+            line.parse::<isize>(y)?,
+            line.parse::<isize>(z)?,
+        ))
+    }))
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let map = parse_input(&buf);
+/// Reads the position of each cube.
+pub fn parse(input: impl BufRead) -> Result<Vec<Point3>, AocError> {
+    parse_input(&read_input(input)?)
+}
 
+/// The area of every face not touching another cube.
+pub fn part_one(cubes: &[Point3]) -> usize {
     let mut left = HashSet::new();
     let mut right = HashSet::new();
 
@@ -26,89 +40,65 @@ pub fn star_one(mut input: impl BufRead) -> String {
     let mut front = HashSet::new();
     let mut back = HashSet::new();
 
-    for p in map {
+    for &p in cubes {
         left.insert(p);
-        right.insert((p.0 + 1, p.1, p.2));
+        right.insert(p + Point3::new(1, 0, 0));
 
         bottom.insert(p);
-        top.insert((p.0, p.1 + 1, p.2));
+        top.insert(p + Point3::new(0, 1, 0));
 
         front.insert(p);
-        back.insert((p.0, p.1, p.2 + 1));
+        back.insert(p + Point3::new(0, 0, 1));
     }
 
-    (left.symmetric_difference(&right).count()
+    left.symmetric_difference(&right).count()
         + bottom.symmetric_difference(&top).count()
-        + front.symmetric_difference(&back).count())
-    .to_string()
+        + front.symmetric_difference(&back).count()
 }
 
-fn is_within(
-    point: &(isize, isize, isize),
-    bounds: &(isize, isize, isize, isize, isize, isize),
-) -> bool {
-    bounds.0 <= point.0
-        && point.0 <= bounds.1
-        && bounds.2 <= point.1
-        && point.1 <= bounds.3
-        && bounds.4 <= point.2
-        && point.2 <= bounds.5
+fn is_within(point: Point3, (min, max): (Point3, Point3)) -> bool {
+    point.min(min) == min && point.max(max) == max
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let map = parse_input(&buf);
-
-    let droplets: HashSet<(isize, isize, isize)> = map.collect();
+/// The area of the outside of the droplet, leaving out pockets of air
+/// trapped inside it.
+pub fn part_two(cubes: &[Point3]) -> Result<usize, AocError> {
+    let droplets: HashSet<Point3> = cubes.iter().copied().collect();
+    if droplets.is_empty() {
+        return Ok(0);
+    }
 
     // Get bounds
 
-    let bounds = (
-        droplets.iter().min_by_key(|d| d.0).unwrap().0,
-        droplets.iter().max_by_key(|d| d.0).unwrap().0,
-        droplets.iter().min_by_key(|d| d.1).unwrap().1,
-        droplets.iter().max_by_key(|d| d.1).unwrap().1,
-        droplets.iter().min_by_key(|d| d.2).unwrap().2,
-        droplets.iter().max_by_key(|d| d.2).unwrap().2,
-    );
-
-    let mut stack = vec![
-        (bounds.0, bounds.2, bounds.4),
-        (bounds.1, bounds.2, bounds.4),
-        (bounds.0, bounds.3, bounds.4),
-    ];
+    let first = *droplets.iter().next().unwrap();
+    let (min, max) = droplets
+        .iter()
+        .fold((first, first), |(min, max), &d| (min.min(d), max.max(d)));
+
+    // Flood the air from a layer around the droplets, which is all connected.
+    let (min, max) = (min - Point3::new(1, 1, 1), max + Point3::new(1, 1, 1));
+    let mut stack = vec![min];
     let mut visited = HashSet::new();
 
     while let Some(curr) = stack.pop() {
+        checkpoint()?;
         if visited.contains(&curr) {
             continue;
         }
         visited.insert(curr);
 
-        if is_within(&curr, &bounds) && !droplets.contains(&curr) {
-            stack.extend(
-                [
-                    (1, 0, 0),
-                    (-1, 0, 0),
-                    (0, 1, 0),
-                    (0, -1, 0),
-                    (0, 0, 1),
-                    (0, 0, -1),
-                ]
-                .iter()
-                .map(|delta| (delta.0 + curr.0, delta.1 + curr.1, delta.2 + curr.2)),
-            )
+        if is_within(curr, (min, max)) && !droplets.contains(&curr) {
+            stack.extend(curr.neighbours())
         }
     }
 
     let mut contained_air = HashSet::new();
 
-    for i in bounds.0..=bounds.1 {
-        for j in bounds.2..=bounds.3 {
-            for k in bounds.4..=bounds.5 {
-                let pos = (i, j, k);
+    for i in min.x..=max.x {
+        for j in min.y..=max.y {
+            for k in min.z..=max.z {
+                checkpoint()?;
+                let pos = Point3::new(i, j, k);
                 if !visited.contains(&pos) {
                     contained_air.insert(pos);
                 }
@@ -116,10 +106,8 @@ pub fn star_two(mut input: impl BufRead) -> String {
         }
     }
 
-    // println!("{:?}", bounds);
+    // println!("{:?}", (min, max));
     // println!("{:?}", visited);
-    // println!("{:?}", droplets.contains(&(0, bounds.1, 0)));
-    // println!("{:?}", is_within(&(0, bounds.1, 0), &bounds));
     // println!("{:?}", contained_air);
 
     let mut left = HashSet::new();
@@ -133,36 +121,122 @@ pub fn star_two(mut input: impl BufRead) -> String {
 
     for p in droplets {
         left.insert(p);
-        right.insert((p.0 + 1, p.1, p.2));
+        right.insert(p + Point3::new(1, 0, 0));
 
         bottom.insert(p);
-        top.insert((p.0, p.1 + 1, p.2));
+        top.insert(p + Point3::new(0, 1, 0));
 
         front.insert(p);
-        back.insert((p.0, p.1, p.2 + 1));
+        back.insert(p + Point3::new(0, 0, 1));
     }
 
     for p in contained_air {
         left.insert(p);
-        right.insert((p.0 + 1, p.1, p.2));
+        right.insert(p + Point3::new(1, 0, 0));
 
         bottom.insert(p);
-        top.insert((p.0, p.1 + 1, p.2));
+        top.insert(p + Point3::new(0, 1, 0));
 
         front.insert(p);
-        back.insert((p.0, p.1, p.2 + 1));
+        back.insert(p + Point3::new(0, 0, 1));
     }
 
-    (left.symmetric_difference(&right).count()
+    Ok(left.symmetric_difference(&right).count()
         + bottom.symmetric_difference(&top).count()
         + front.symmetric_difference(&back).count())
-    .to_string()
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?).into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+/// Looks at each face of each cube on its own.
+#[cfg(feature = "reference")]
+pub mod reference {
+    use super::parse_input;
+    use crate::{input::read_input, point::Point3, Answer, AocError};
+    use std::{collections::HashSet, io::BufRead};
+
+    /// Whether air at `start` can get out past the edges of the droplets.
+    fn escapes(droplets: &HashSet<Point3>, start: Point3) -> bool {
+        let min = droplets.iter().fold(start, |min, &d| min.min(d));
+        let max = droplets.iter().fold(start, |max, &d| max.max(d));
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(pos) = stack.pop() {
+            if pos.min(min) != min || pos.max(max) != max {
+                return true;
+            }
+            for next in pos.neighbours() {
+                if !droplets.contains(&next) && seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+        let buf = read_input(input)?;
+        let droplets: HashSet<_> = parse_input(&buf)?.into_iter().collect();
+        Ok(droplets
+            .iter()
+            .flat_map(|droplet| droplet.neighbours())
+            .filter(|next| !droplets.contains(next))
+            .count()
+            .into())
+    }
+
+    pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+        let buf = read_input(input)?;
+        let droplets: HashSet<_> = parse_input(&buf)?.into_iter().collect();
+        Ok(droplets
+            .iter()
+            .flat_map(|droplet| droplet.neighbours())
+            .filter(|next| !droplets.contains(next) && escapes(&droplets, *next))
+            .count()
+            .into())
+    }
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    fn day(&self) -> usize {
+        18
+    }
+
+    fn title(&self) -> &'static str {
+        "Boiling Boulders"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{star_one, star_two};
-    use std::io::Cursor;
+    use crate::{Answer, AocError, CancellationToken};
+    use std::{io::Cursor, time::Duration};
 
     #[test]
     fn test_star_one() {
@@ -181,8 +255,9 @@ mod tests {
 3,2,5
 2,1,5
 2,3,5"
-            )),
-            "64"
+            ))
+            .unwrap(),
+            Answer::Integer(64)
         );
     }
 
@@ -203,8 +278,54 @@ mod tests {
 3,2,5
 2,1,5
 2,3,5"
-            )),
-            "58"
+            ))
+            .unwrap(),
+            Answer::Integer(58)
+        );
+    }
+
+    #[test]
+    fn test_droplets_in_corners() {
+        // Three corners of the droplets' bounds are droplets, so the air has
+        // to be flooded from outside the bounds to reach the rest.
+        assert_eq!(
+            star_two(Cursor::new(b"0,0,0\n2,0,0\n0,2,0\n0,0,2")).unwrap(),
+            Answer::Integer(24)
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        // The air around these two cubes is far too big to flood in time.
+        let token = CancellationToken::with_timeout(Duration::from_millis(100));
+        let result = token.run(|| star_two(Cursor::new(b"0,0,0\n1000000,1000000,1000000")));
+        assert!(matches!(result, Err(AocError::Cancelled)));
+    }
+
+    #[cfg(feature = "reference")]
+    #[test]
+    fn test_against_reference() {
+        use super::reference;
+        use crate::generate::{self, differential};
+
+        differential(
+            300,
+            |rng| {
+                let count = rng.range(1..60) as usize;
+                generate::droplets(rng, count, 5)
+            },
+            |input| {
+                (
+                    star_one(Cursor::new(input)).ok(),
+                    star_two(Cursor::new(input)).ok(),
+                )
+            },
+            |input| {
+                (
+                    reference::star_one(Cursor::new(input)).ok(),
+                    reference::star_two(Cursor::new(input)).ok(),
+                )
+            },
         );
     }
 }
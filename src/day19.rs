@@ -1,26 +1,61 @@
+//! Day 19: Not Enough Minerals
+//!
+//! Robots mine resources, and the resources build more robots. [`parse`]
+//! reads each [`Blueprint`] for building them, and [`part_one`] and
+//! [`part_two`] find how many geodes each blueprint can open in time.
+
+use crate::{
+    current_token,
+    input::{lines, read_input},
+    parse::{parse_line, unsigned},
+    Answer, AocError, CancellationToken, Solution,
+};
+use nom::{
+    bytes::complete::tag,
+    character::complete::{char, digit1},
+    sequence::{delimited, preceded, terminated},
+};
 use rayon::prelude::*;
-use regex::Regex;
 use std::{collections::BinaryHeap, io::BufRead};
 
-lazy_static! {
-    static ref RE: Regex = Regex::new(r"^ Each ore robot costs (\d+) ore. Each clay robot costs (\d+) ore. Each obsidian robot costs (\d+) ore and (\d+) clay. Each geode robot costs (\d+) ore and (\d+) obsidian.$").unwrap();
+/// What it costs to build each kind of robot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blueprint {
+    pub ore_robot_ore: isize,
+    pub clay_robot_ore: isize,
+    pub obsidian_robot_ore: isize,
+    pub obsidian_robot_clay: isize,
+    pub geode_robot_ore: isize,
+    pub geode_robot_obsidian: isize,
 }
 
-fn parse_input(buf: &str) -> impl Iterator<Item = (isize, isize, isize, isize, isize, isize)> + '_ {
-    buf.lines().map(|line| {
-        let (_first, second) = line.split_once(':').unwrap();
-
-        // Each ore robot costs 4 ore. Each clay robot costs 4 ore. Each obsidian robot costs 3 ore and 19 clay. Each geode robot costs 4 ore and 15 obsidian.
-        let captures = RE.captures(second).unwrap();
-        (
-            captures[1].parse::<isize>().unwrap(),
-            captures[2].parse::<isize>().unwrap(),
-            captures[3].parse::<isize>().unwrap(),
-            captures[4].parse::<isize>().unwrap(),
-            captures[5].parse::<isize>().unwrap(),
-            captures[6].parse::<isize>().unwrap(),
+fn parse_input(buf: &str) -> Result<Vec<Blueprint>, AocError> {
+    AocError::collect(lines(buf).map(|line| {
+        // Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 4 ore. Each obsidian robot costs 3 ore and 19 clay. Each geode robot costs 4 ore and 15 obsidian.
+        let cost = |robot| preceded((tag(" Each "), tag(robot), tag(" robot costs ")), unsigned);
+        parse_line(
+            &line,
+            preceded(
+                (tag("Blueprint "), digit1, char(':')),
+                (
+                    terminated(cost("ore"), tag(" ore.")),
+                    terminated(cost("clay"), tag(" ore.")),
+                    cost("obsidian"),
+                    delimited(tag(" ore and "), unsigned, tag(" clay.")),
+                    cost("geode"),
+                    delimited(tag(" ore and "), unsigned, tag(" obsidian.")),
+                ),
+            ),
         )
-    })
+        .map(|costs| Blueprint {
+            ore_robot_ore: costs.0,
+            clay_robot_ore: costs.1,
+            obsidian_robot_ore: costs.2,
+            obsidian_robot_clay: costs.3,
+            geode_robot_ore: costs.4,
+            geode_robot_obsidian: costs.5,
+        })
+    }))
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
@@ -80,167 +115,146 @@ impl Ord for State {
     }
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// The most geodes `blueprint` can open in `minutes`. Only the `beam` most
+/// promising states are kept from one minute to the next.
+fn max_geodes(
+    blueprint: &Blueprint,
+    minutes: isize,
+    beam: usize,
+    token: Option<&CancellationToken>,
+) -> Result<isize, AocError> {
+    let mut stack = BinaryHeap::new();
+    stack.push(State {
+        ore: 0,
+        clay: 0,
+        obsidian: 0,
+        geode: 0,
+        ore_robot: 1,
+        clay_robot: 0,
+        obsidian_robot: 0,
+        geode_robot: 0,
+        time: 0,
+        max_time: minutes,
+        previous_state: None,
+    });
+
+    for _time in 0..minutes {
+        if let Some(token) = token {
+            token.check()?;
+        }
+        let data = stack
+            .into_sorted_vec()
+            .into_iter()
+            .rev()
+            .flat_map(|curr| {
+                (0..5).filter_map(move |v| {
+                    let ore_robots = isize::from(v == 0);
+                    let clay_robots = isize::from(v == 1);
+                    let obsidian_robots = isize::from(v == 2);
+                    let geode_robots = isize::from(v == 3);
+
+                    let mut state = State {
+                        ore: curr.ore
+                            - (ore_robots * blueprint.ore_robot_ore)
+                            - (clay_robots * blueprint.clay_robot_ore)
+                            - (obsidian_robots * blueprint.obsidian_robot_ore)
+                            - (geode_robots * blueprint.geode_robot_ore),
+                        clay: curr.clay - (obsidian_robots * blueprint.obsidian_robot_clay),
+                        obsidian: curr.obsidian - (geode_robots * blueprint.geode_robot_obsidian),
+                        // previous_state: Some(Box::new(curr.clone())),
+                        previous_state: None,
+                        ..curr
+                    };
+                    if state.is_valid() {
+                        state.step();
+                        state.ore_robot += ore_robots;
+                        state.clay_robot += clay_robots;
+                        state.obsidian_robot += obsidian_robots;
+                        state.geode_robot += geode_robots;
+                        Some(state)
+                    } else {
+                        None
+                    }
+                })
+            })
+            .take(beam);
+        stack = data.collect();
+    }
+    Ok(stack.pop().map_or(0, |state| state.geode))
+}
 
-    let receipes: Vec<_> = parse_input(&buf).collect();
+/// Reads every blueprint. They are numbered from 1 in the order given.
+pub fn parse(input: impl BufRead) -> Result<Vec<Blueprint>, AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    receipes
-        .into_par_iter()
-        .enumerate()
-        .map(|(i, receipe)| {
-            let mut stack = BinaryHeap::new();
-            stack.push(State {
-                ore: 0,
-                clay: 0,
-                obsidian: 0,
-                geode: 0,
-                ore_robot: 1,
-                clay_robot: 0,
-                obsidian_robot: 0,
-                geode_robot: 0,
-                time: 0,
-                max_time: 24,
-                previous_state: None,
-            });
+/// The total quality level of the blueprints: each one's number multiplied by
+/// the most geodes it can open in 24 minutes.
+pub fn part_one(blueprints: &[Blueprint]) -> Result<usize, AocError> {
+    // Checked directly as the blueprints are searched on other threads.
+    let token = current_token();
 
-            for _time in 0..24 {
-                let data = stack
-                    .into_sorted_vec()
-                    .into_iter()
-                    .rev()
-                    .flat_map(|curr| {
-                        (0..5).filter_map(move |v| {
-                            let ore_robots = isize::from(v == 0);
-                            let clay_robots = isize::from(v == 1);
-                            let obsidian_robots = isize::from(v == 2);
-                            let geode_robots = isize::from(v == 3);
-
-                            let mut state = State {
-                                ore: curr.ore
-                                    - (ore_robots * receipe.0)
-                                    - (clay_robots * receipe.1)
-                                    - (obsidian_robots * receipe.2)
-                                    - (geode_robots * receipe.4),
-                                clay: curr.clay - (obsidian_robots * receipe.3),
-                                obsidian: curr.obsidian - (geode_robots * receipe.5),
-                                // previous_state: Some(Box::new(curr.clone())),
-                                previous_state: None,
-                                ..curr
-                            };
-                            if state.is_valid() {
-                                state.step();
-                                state.ore_robot += ore_robots;
-                                state.clay_robot += clay_robots;
-                                state.obsidian_robot += obsidian_robots;
-                                state.geode_robot += geode_robots;
-                                Some(state)
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                    .take(4_000);
-                stack = data.collect();
-            }
-            let s = stack.pop().unwrap();
-            // let mut s2 = Some(Box::new(s.clone()));
-
-            // println!("{:?}", s);
-            // while let Some(s) = s2.take() {
-            //     println!(
-            //         "{}: ({}, {}, {}, {}) - ({}, {}, {}, {})",
-            //         s.time,
-            //         s.ore,
-            //         s.clay,
-            //         s.obsidian,
-            //         s.geode,
-            //         s.ore_robot,
-            //         s.clay_robot,
-            //         s.obsidian_robot,
-            //         s.geode_robot
-            //     );
-            //     s2 = s.previous_state;
-            // }
-            (i + 1, s)
+    Ok(blueprints
+        .par_iter()
+        .enumerate()
+        .map(|(i, blueprint)| {
+            Ok((i + 1) * max_geodes(blueprint, 24, 4_000, token.as_ref())? as usize)
         })
-        .map(|(i, x)| i * x.geode as usize)
-        .sum::<usize>()
-        .to_string()
+        .collect::<Result<Vec<_>, AocError>>()?
+        .into_iter()
+        .sum())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let receipes: Vec<_> = parse_input(&buf).collect();
+/// The most geodes each of the first three blueprints can open in 32
+/// minutes, multiplied together.
+pub fn part_two(blueprints: &[Blueprint]) -> Result<usize, AocError> {
+    // Checked directly as the blueprints are searched on other threads.
+    let token = current_token();
 
-    receipes
-        .into_par_iter()
+    Ok(blueprints
+        .par_iter()
         .take(3)
-        .enumerate()
-        .map(|(i, receipe)| {
-            let mut stack = BinaryHeap::new();
-            stack.push(State {
-                ore: 0,
-                clay: 0,
-                obsidian: 0,
-                geode: 0,
-                ore_robot: 1,
-                clay_robot: 0,
-                obsidian_robot: 0,
-                geode_robot: 0,
-                time: 0,
-                max_time: 32,
-                previous_state: None,
-            });
+        .map(|blueprint| Ok(max_geodes(blueprint, 32, 8_000, token.as_ref())? as usize))
+        .collect::<Result<Vec<_>, AocError>>()?
+        .into_iter()
+        .product())
+}
 
-            for _time in 0..32 {
-                let data = stack
-                    .into_sorted_vec()
-                    .into_iter()
-                    .rev()
-                    .flat_map(|curr| {
-                        (0..5).filter_map(move |v| {
-                            let ore_robots = isize::from(v == 0);
-                            let clay_robots = isize::from(v == 1);
-                            let obsidian_robots = isize::from(v == 2);
-                            let geode_robots = isize::from(v == 3);
-
-                            let mut state = State {
-                                ore: curr.ore
-                                    - (ore_robots * receipe.0)
-                                    - (clay_robots * receipe.1)
-                                    - (obsidian_robots * receipe.2)
-                                    - (geode_robots * receipe.4),
-                                clay: curr.clay - (obsidian_robots * receipe.3),
-                                obsidian: curr.obsidian - (geode_robots * receipe.5),
-                                // previous_state: Some(Box::new(curr.clone())),
-                                previous_state: None,
-                                ..curr
-                            };
-                            if state.is_valid() {
-                                state.step();
-                                state.ore_robot += ore_robots;
-                                state.clay_robot += clay_robots;
-                                state.obsidian_robot += obsidian_robots;
-                                state.geode_robot += geode_robots;
-                                Some(state)
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                    .take(8_000);
-                stack = data.collect();
-            }
-            let s = stack.pop().unwrap();
-            (i + 1, s)
-        })
-        .map(|(_i, x)| x.geode as usize)
-        .product::<usize>()
-        .to_string()
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    fn day(&self) -> usize {
+        19
+    }
+
+    fn title(&self) -> &'static str {
+        "Not Enough Minerals"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
@@ -248,6 +262,10 @@ mod tests {
     use itertools::Itertools;
 
     use super::{star_one, star_two, State};
+    use crate::{
+        generate::{self, check},
+        Answer,
+    };
     use std::{collections::BinaryHeap, io::Cursor};
 
     #[test]
@@ -353,8 +371,8 @@ mod tests {
         let res: Vec<_> = vec![
             (0..=(2 / 2) as usize).collect(),
             (0..=(4 / 2) as usize).collect(),
-            (0..=2.min(0) as usize).collect(),
-            (0..=2 / 2.max(0) as usize).collect(),
+            (0..=0_usize).collect(),
+            (0..=2 / 2_usize).collect(),
         ]
         .into_iter()
         .map(|v: Vec<usize>| v.into_iter())
@@ -384,8 +402,8 @@ mod tests {
             star_one(Cursor::new(
                 b"Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
 Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian."
-            )),
-            "33"
+            )).unwrap(),
+            Answer::Integer(33)
         );
     }
 
@@ -394,14 +412,36 @@ Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsid
         assert_eq!(
             star_two(Cursor::new(
                 b"Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian."
-            )),
-            "56"
+            )).unwrap(),
+            Answer::Integer(56)
         );
         assert_eq!(
             star_two(Cursor::new(
 b"Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian."
-            )),
-            "62"
+            )).unwrap(),
+            Answer::Integer(62)
+        );
+    }
+
+    #[test]
+    fn test_quality_levels_add_up() {
+        // Each blueprint is searched on its own, so the total quality is the
+        // same as adding up the blueprints one at a time.
+        check(
+            5,
+            |rng| generate::blueprints(rng, 3),
+            |input| {
+                let quality = |input: &str| match star_one(Cursor::new(input)).unwrap() {
+                    Answer::Integer(n) => n,
+                    x => panic!("Expected a number, got {x:?}"),
+                };
+                let separately = input
+                    .lines()
+                    .enumerate()
+                    .map(|(i, line)| (i as i64 + 1) * quality(line))
+                    .sum::<i64>();
+                quality(input) == separately
+            },
         );
     }
 }
@@ -1,131 +1,118 @@
+//! Day 20: Grove Positioning System
+//!
+//! The grove's coordinates are hidden in an encrypted file of numbers.
+//! [`parse`] reads the numbers, and [`part_one`] and [`part_two`] mix them to
+//! find the coordinates.
+
+use crate::{
+    input::{lines, read_input},
+    Answer, AocError, Solution,
+};
 use std::{collections::VecDeque, io::BufRead};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Coordinate {
-    Moved(isize),
-    UnMoved(isize),
+fn parse_input(buf: &str) -> Result<Vec<isize>, AocError> {
+    let numbers: Vec<_> = AocError::collect(lines(buf).map(|line| line.parse::<isize>(line.text)))?;
+    if numbers.len() < 2 {
+        return Err(AocError::unsolvable("Need at least two numbers to mix"));
+    }
+    Ok(numbers)
 }
 
-impl Coordinate {
-    fn value(&self) -> isize {
-        match self {
-            Coordinate::Moved(x) => *x,
-            Coordinate::UnMoved(x) => *x,
+/// Moves each number round the circle by its own value, in the order they
+/// were first listed, `rounds` times over.
+pub fn mix(numbers: &[isize], rounds: usize) -> Vec<isize> {
+    // Numbers can repeat, so each is tagged with where it started.
+    let mut list = VecDeque::from_iter(numbers.iter().copied().enumerate());
+    for _round in 0..rounds {
+        for i in 0..numbers.len() {
+            let j = list.iter().position(|(j, _)| *j == i).unwrap();
+            list.rotate_left(j);
+            let (i, x) = list.pop_front().unwrap();
+            if x < 0 {
+                list.rotate_right(x.unsigned_abs() % list.len());
+            } else {
+                list.rotate_left(x.unsigned_abs() % list.len());
+            }
+            list.push_front((i, x));
         }
     }
+    list.into_iter().map(|(_, x)| x).collect()
 }
 
-fn parse_input(buf: &str) -> Vec<Coordinate> {
-    buf.lines()
-        .map(|l| Coordinate::UnMoved(l.parse::<isize>().unwrap()))
-        .collect()
+/// The sum of the 1000th, 2000th and 3000th numbers after the 0.
+pub fn grove_coordinates(mixed: &[isize]) -> Result<isize, AocError> {
+    let zero = mixed
+        .iter()
+        .position(|&x| x == 0)
+        .ok_or_else(|| AocError::unsolvable("There is no 0 in the file"))?;
+    Ok([1000, 2000, 3000]
+        .iter()
+        .map(|offset| mixed[(zero + offset) % mixed.len()])
+        .sum())
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// Reads the encrypted file.
+pub fn parse(input: impl BufRead) -> Result<Vec<isize>, AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    let coordinates = parse_input(&buf);
+/// The grove coordinates after mixing once.
+pub fn part_one(numbers: &[isize]) -> Result<isize, AocError> {
+    grove_coordinates(&mix(numbers, 1))
+}
 
-    // println!("{:?}", coordinates);
-    let mut list = VecDeque::from_iter(coordinates.iter().cloned().enumerate());
-    for i in 0..coordinates.len() {
-        let j = list
-            .iter()
-            .enumerate()
-            .find(|(_k, (j, _x))| j == &i)
-            .unwrap()
-            .0;
-        // println!("Moving {:?}", x);
-        // assert_eq!(x.value(), coordinates[i].value());
-        list.rotate_left(j);
-        let (j2, x) = list.pop_front().unwrap();
-        // assert_eq!(&x, d);
-        if x.value() < 0 {
-            list.rotate_right(x.value().unsigned_abs() % list.len());
-        } else {
-            list.rotate_left(x.value().unsigned_abs() % list.len());
-        }
-        list.push_front((j2, Coordinate::Moved(x.value())));
-        // println!("{:?}", list);
-    }
+/// The grove coordinates after applying the decryption key and mixing ten
+/// times.
+pub fn part_two(numbers: &[isize]) -> Result<isize, AocError> {
+    let numbers = numbers.iter().map(|x| x * 811589153).collect::<Vec<_>>();
+    grove_coordinates(&mix(&numbers, 10))
+}
 
-    // find 0
-    let pos_0 = list
-        .iter()
-        .position(|(_i, c)| c == &Coordinate::Moved(0))
-        .unwrap();
-
-    let pos_1 = (pos_0 + 1000) % list.len();
-    let pos_2 = (pos_0 + 2000) % list.len();
-    let pos_3 = (pos_0 + 3000) % list.len();
-
-    // println!(
-    //     "{:?} - {} {} {} {}",
-    //     v.iter().map(|c| c.value()).collect::<Vec<_>>(),
-    //     pos_0,
-    //     v[pos_1].value(),
-    //     v[pos_2].value(),
-    //     v[pos_3].value()
-    // );
-
-    (list[pos_1].1.value() + list[pos_2].1.value() + list[pos_3].1.value()).to_string()
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    let coordinates = parse_input(&buf);
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
+}
 
-    let key = 811589153;
+pub struct Day20;
 
-    // println!("{:?}", coordinates);
-    let mut list = VecDeque::from_iter(
-        coordinates
-            .iter()
-            .map(|x| Coordinate::UnMoved(x.value() * key))
-            .enumerate(),
-    );
-    for _mix in 0..10 {
-        for i in 0..coordinates.len() {
-            let j = list
-                .iter()
-                .enumerate()
-                .find(|(_k, (j, _x))| j == &i)
-                .unwrap()
-                .0;
-            // println!("Moving {:?}", x);
-            // assert_eq!(x.value(), coordinates[i].value());
-            list.rotate_left(j);
-            let (j2, x) = list.pop_front().unwrap();
-            // assert_eq!(&x, d);
-            if x.value() < 0 {
-                list.rotate_right(x.value().unsigned_abs() % list.len());
-            } else {
-                list.rotate_left(x.value().unsigned_abs() % list.len());
-            }
-            list.push_front((j2, Coordinate::Moved(x.value())));
-            // println!("{:?}", list);
-        }
+impl Solution for Day20 {
+    fn day(&self) -> usize {
+        20
     }
-    // find 0
-    let pos_0 = list
-        .iter()
-        .position(|(_i, c)| c == &Coordinate::Moved(0))
-        .unwrap();
 
-    let pos_1 = (pos_0 + 1000) % list.len();
-    let pos_2 = (pos_0 + 2000) % list.len();
-    let pos_3 = (pos_0 + 3000) % list.len();
+    fn title(&self) -> &'static str {
+        "Grove Positioning System"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
 
-    (list[pos_1].1.value() + list[pos_2].1.value() + list[pos_3].1.value()).to_string()
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{star_one, star_two};
+    use super::{mix, parse_input, star_one, star_two};
+    use crate::{
+        generate::{self, check},
+        Answer,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -140,8 +127,9 @@ mod tests {
 0
 4
 "
-            )),
-            "3"
+            ))
+            .unwrap(),
+            Answer::Integer(3)
         );
     }
 
@@ -156,8 +144,65 @@ mod tests {
 -2
 0
 4"
-            )),
-            "1623178306"
+            ))
+            .unwrap(),
+            Answer::Integer(1623178306)
+        );
+    }
+
+    /// The circle read round from the 0.
+    fn from_zero(mixed: Vec<isize>) -> Vec<isize> {
+        let zero = mixed.iter().position(|&x| x == 0).unwrap();
+        mixed[zero..]
+            .iter()
+            .chain(&mixed[..zero])
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_mix() {
+        // The circles the puzzle shows after mixing its example.
+        let numbers = [1, 2, -3, 3, -2, 0, 4];
+        assert_eq!(from_zero(mix(&numbers, 1)), vec![0, 3, -2, 1, 2, -3, 4]);
+        let numbers = numbers.map(|x| x * 811589153);
+        assert_eq!(
+            from_zero(mix(&numbers, 10)),
+            vec![
+                0,
+                -2434767459,
+                1623178306,
+                3246356612,
+                -1623178306,
+                2434767459,
+                811589153
+            ]
+        );
+        // Repeated numbers each move once, in the order they were listed.
+        assert_eq!(
+            from_zero(mix(&[3, 1, 3, 0, -1, 3], 1)),
+            vec![0, 3, 3, -1, 3, 1]
+        );
+    }
+
+    #[test]
+    fn test_mix_keeps_numbers() {
+        check(
+            50,
+            |rng| {
+                let count = rng.range(2..200) as usize;
+                generate::mixing(rng, count)
+            },
+            |input| {
+                let numbers = parse_input(input).unwrap();
+                [1, 10].iter().all(|&rounds| {
+                    let mut mixed = mix(&numbers, rounds);
+                    let mut expected = numbers.clone();
+                    mixed.sort();
+                    expected.sort();
+                    mixed == expected
+                })
+            },
         );
     }
 }
@@ -1,61 +1,82 @@
+//! Day 21: Monkey Math
+//!
+//! Each monkey either yells a number or combines the numbers of two others.
+//! [`parse`] reads the monkeys' jobs as [`Equation`]s, [`part_one`] works out
+//! what `root` yells, and [`part_two`] finds what `humn` must yell instead.
+
+use crate::{
+    input::{lines, read_input, Line},
+    Answer, AocError, Solution,
+};
 use std::{
     collections::{HashMap, HashSet},
+    fmt::Display,
     io::BufRead,
+    iter,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Operation {
+/// How a monkey combines the two numbers it waits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
     Add,
     Multiply,
     Subtract,
     Divide,
-    Equals,
 }
 
 impl Operation {
-    fn parse(input: &str) -> Operation {
+    fn parse(line: &Line, input: &str) -> Result<Operation, AocError> {
         match input {
-            "+" => Operation::Add,
-            "*" => Operation::Multiply,
-            "-" => Operation::Subtract,
-            "/" => Operation::Divide,
-            x => panic!("Found {x}"),
+            "+" => Ok(Operation::Add),
+            "*" => Ok(Operation::Multiply),
+            "-" => Ok(Operation::Subtract),
+            "/" => Ok(Operation::Divide),
+            x => Err(line.error(input, format!("Unknown operation: {x}"))),
         }
     }
 
-    fn calculate(&self, v1: isize, v2: isize) -> isize {
+    /// `a` combined with `b`, unless that divides by zero or overflows.
+    fn apply(self, a: isize, b: isize) -> Option<isize> {
         match self {
-            Operation::Add => v1 + v2,
-            Operation::Multiply => v1 * v2,
-            Operation::Subtract => v1 - v2,
-            Operation::Divide => v1 / v2,
-            Operation::Equals => isize::from(v1 == v2),
+            Operation::Add => a.checked_add(b),
+            Operation::Multiply => a.checked_mul(b),
+            Operation::Subtract => a.checked_sub(b),
+            Operation::Divide => a.checked_div(b),
         }
     }
 
-    fn print(&self) -> String {
-        match self {
-            Operation::Add => "+".to_string(),
-            Operation::Multiply => "*".to_string(),
-            Operation::Subtract => "-".to_string(),
-            Operation::Divide => "/".to_string(),
-            Operation::Equals => "=".to_string(),
+    /// The operand that makes this operation come to `target`, given the
+    /// other operand is `known`. `unknown_left` says which side is missing.
+    /// Dividing rounds down, so of the several numbers divided by `known`
+    /// there, the smallest is taken. A number being divided by cannot be
+    /// solved for.
+    fn solve(self, target: isize, known: isize, unknown_left: bool) -> Option<isize> {
+        match (self, unknown_left) {
+            (Operation::Add, _) => target.checked_sub(known),
+            (Operation::Multiply, _) => (target.checked_rem(known)? == 0).then(|| target / known),
+            (Operation::Subtract, true) => target.checked_add(known),
+            (Operation::Subtract, false) => known.checked_sub(target),
+            (Operation::Divide, true) => (known != 0).then(|| target.checked_mul(known))?,
+            (Operation::Divide, false) => None,
         }
     }
+}
 
-    fn inverse(&self) -> Operation {
-        match self {
-            Operation::Add => Operation::Subtract,
-            Operation::Multiply => Operation::Divide,
-            Operation::Subtract => Operation::Add,
-            Operation::Divide => Operation::Multiply,
-            Operation::Equals => panic!(),
-        }
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Operation::Add => "+",
+            Operation::Multiply => "*",
+            Operation::Subtract => "-",
+            Operation::Divide => "/",
+        };
+        write!(f, "{symbol}")
     }
 }
 
+/// One side of an [`Equation`]: a number, or the name of another monkey.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum Token {
+pub enum Token {
     Value(isize),
     Symbol(String),
 }
@@ -69,322 +90,321 @@ impl Token {
         }
     }
 
-    fn print(&self, equations: &HashMap<String, Equation>) -> String {
+    /// What this is worth, given what the monkeys worked out so far yell.
+    fn value(&self, known: &HashMap<&str, isize>) -> Option<isize> {
         match self {
-            Token::Value(x) => x.to_string(),
-            Token::Symbol(s) => format!("({s}: {})", equations.get(s).unwrap().print(equations)),
+            Token::Value(v) => Some(*v),
+            Token::Symbol(s) => known.get(s.as_str()).copied(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-struct Equation(Token, Option<Operation>, Option<Token>);
+/// A monkey's job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equation {
+    /// Yells a number, or whatever another monkey yells.
+    Yell(Token),
+    /// Yells the result of an operation on two tokens.
+    Combine(Token, Operation, Token),
+}
 
 impl Equation {
-    fn calculate(&self, equations: &HashMap<String, Equation>) -> isize {
-        match (&self.0, &self.1, &self.2) {
-            (Token::Value(v), None, None) => *v,
-            (Token::Value(_), None, Some(_)) => panic!(),
-            (Token::Value(_), Some(_), None) => panic!(),
-            (Token::Value(v1), Some(op), Some(Token::Value(v2))) => op.calculate(*v1, *v2),
-            (Token::Value(v1), Some(op), Some(Token::Symbol(s2))) => {
-                let e2 = equations.get(s2).unwrap();
-                let v2 = e2.calculate(equations);
-                op.calculate(*v1, v2)
+    /// The monkeys this one waits on.
+    pub fn waits_on(&self) -> impl Iterator<Item = &str> {
+        let (a, b) = match self {
+            Equation::Yell(a) => (a, None),
+            Equation::Combine(a, _, b) => (a, Some(b)),
+        };
+        iter::once(a).chain(b).filter_map(|token| match token {
+            Token::Symbol(s) => Some(s.as_str()),
+            Token::Value(_) => None,
+        })
+    }
+}
+
+/// The `roots` and every monkey they wait on, each after all those it waits
+/// on. This is an explicit depth first search, so long chains of monkeys
+/// cannot overflow the stack, and monkeys waiting on each other in a loop
+/// are an error rather than a hang.
+fn waiting_order<'a>(
+    equations: &'a HashMap<String, Equation>,
+    roots: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<&'a str>, AocError> {
+    let mut order = Vec::new();
+    let mut done = HashSet::new();
+    // The monkeys on the way to the one being looked at.
+    let mut visiting = HashSet::new();
+    for root in roots {
+        // Each monkey is pushed again, marked `true`, once everything it
+        // waits on has been pushed.
+        let mut stack = vec![(root, false)];
+        while let Some((name, finished)) = stack.pop() {
+            if finished {
+                visiting.remove(name);
+                done.insert(name);
+                order.push(name);
+                continue;
             }
-            (Token::Symbol(_), None, None) => todo!(),
-            (Token::Symbol(_), None, Some(_)) => todo!(),
-            (Token::Symbol(_), Some(_), None) => todo!(),
-            (Token::Symbol(s1), Some(op), Some(Token::Value(v2))) => {
-                let e1 = equations.get(s1).unwrap();
-                let v1 = e1.calculate(equations);
-                op.calculate(v1, *v2)
+            if done.contains(name) {
+                continue;
             }
-
-            (Token::Symbol(s1), Some(op), Some(Token::Symbol(s2))) => {
-                let e1 = equations.get(s1).unwrap();
-                let v1 = e1.calculate(equations);
-                let e2 = equations.get(s2).unwrap();
-                let v2 = e2.calculate(equations);
-                op.calculate(v1, v2)
+            if !visiting.insert(name) {
+                return Err(AocError::unsolvable(format!("{name} waits on itself")));
             }
+            let equation = equations
+                .get(name)
+                .ok_or_else(|| AocError::unsolvable(format!("Unknown monkey: {name}")))?;
+            stack.push((name, true));
+            stack.extend(
+                equation
+                    .waits_on()
+                    .filter(|s| !done.contains(s))
+                    .map(|s| (s, false)),
+            );
         }
     }
+    Ok(order)
+}
 
-    fn is_constant(&self) -> bool {
-        matches!(
-            (&self.0, &self.2),
-            (Token::Value(_), None) | (Token::Value(_), Some(Token::Value(_)))
-        )
-    }
-
-    fn value(&self) -> isize {
-        match (&self.0, &self.1, &self.2) {
-            (Token::Value(v), None, None) => *v,
-            (Token::Value(_), None, Some(_)) => panic!(),
-            (Token::Value(_), Some(_), None) => panic!(),
-            (Token::Value(v1), Some(op), Some(Token::Value(v2))) => op.calculate(*v1, *v2),
-            (Token::Value(_v1), Some(_op), Some(Token::Symbol(_s2))) => panic!(),
-            (Token::Symbol(_), None, None) => todo!(),
-            (Token::Symbol(_), None, Some(_)) => todo!(),
-            (Token::Symbol(_), Some(_), None) => todo!(),
-            (Token::Symbol(_s1), Some(_op), Some(Token::Value(_v2))) => panic!(),
-
-            (Token::Symbol(_s1), Some(_op), Some(Token::Symbol(_s2))) => panic!(),
-        }
-    }
-
-    #[allow(dead_code)]
-    fn print(&self, equations: &HashMap<String, Equation>) -> String {
-        if let Some(op) = &self.1 {
-            let right = self.2.as_ref().unwrap();
-            format!(
-                "{} {} {}",
-                self.0.print(equations),
-                op.print(),
-                right.print(equations)
-            )
-        } else {
-            self.0.print(equations)
+/// What each monkey in `order` yells, leaving out `unknown` and every monkey
+/// that waits on it.
+fn evaluate<'a>(
+    equations: &HashMap<String, Equation>,
+    order: &[&'a str],
+    unknown: Option<&str>,
+) -> Result<HashMap<&'a str, isize>, AocError> {
+    let mut known = HashMap::new();
+    for &name in order {
+        if Some(name) == unknown {
+            continue;
         }
-    }
-
-    fn try_solve(&self, symbol: &str) -> Option<isize> {
-        if self.0 == Token::Symbol(symbol.to_string())
-            && self
-                .1
-                .as_ref()
-                .map(|op| op == &Operation::Equals)
-                .unwrap_or_default()
-            && self
-                .2
-                .as_ref()
-                .map(|t| matches!(t, Token::Value(_)))
-                .unwrap_or_default()
-        {
-            if let Some(Token::Value(v)) = self.2.as_ref() {
-                Some(*v)
-            } else {
-                panic!()
-            }
-        } else if self.2 == Some(Token::Symbol(symbol.to_string()))
-            && self.1 == Some(Operation::Equals)
-            && matches!(self.0, Token::Value(_))
-        {
-            if let Token::Value(v) = self.0.clone() {
-                Some(v)
-            } else {
-                panic!()
-            }
-        } else {
-            None
+        let value = match &equations[name] {
+            Equation::Yell(token) => token.value(&known),
+            Equation::Combine(a, op, b) => match (a.value(&known), b.value(&known)) {
+                (Some(a), Some(b)) => Some(op.apply(a, b).ok_or_else(|| {
+                    AocError::unsolvable(format!("{name} cannot work out {a} {op} {b}"))
+                })?),
+                _ => None,
+            },
+        };
+        if let Some(value) = value {
+            known.insert(name, value);
         }
     }
+    Ok(known)
 }
 
-fn simplify(equations: &mut HashMap<String, Equation>, ignore: &HashSet<String>) -> bool {
-    let mut constants = HashMap::<String, isize>::new();
-    let mut changed_all = false;
+/// The number the monkey called `name` yells.
+pub fn yells(equations: &HashMap<String, Equation>, name: &str) -> Result<isize, AocError> {
+    let order = waiting_order(equations, [name])?;
+    let known = evaluate(equations, &order, None)?;
+    known
+        .get(name)
+        .copied()
+        .ok_or_else(|| AocError::unsolvable(format!("{name} yells nothing")))
+}
 
-    let mut changed = true;
-    while changed {
-        changed = false;
-        let keys: Vec<_> = { equations.keys().cloned().collect() };
-        for name in keys {
-            if ignore.contains(&name) {
-                continue;
-            }
-            if !constants.contains_key(&name) {
-                if equations.get(&name).unwrap().is_constant() {
-                    changed = true;
-                    changed_all = true;
-                    let v = equations.get(&name).unwrap().value();
-                    constants.insert(name.clone(), v);
-                    let equation = equations.get_mut(&name).unwrap();
-                    equation.0 = Token::Value(v);
-                    equation.1 = None;
-                    equation.2 = None;
-                } else if let Token::Symbol(s) = &equations.get(&name).unwrap().0 {
-                    if equations.get(&name).unwrap().2.is_none() {
-                        let child_equation = equations.get(s).unwrap().clone();
-                        let equation = equations.get_mut(&name).unwrap();
-                        equation.0 = child_equation.0.clone();
-                        equation.1.clone_from(&child_equation.1);
-                        equation.2.clone_from(&child_equation.2);
-                        changed = true;
-                        changed_all = true;
-                    } else if let Some(value) = constants.get(s) {
-                        changed = true;
-                        changed_all = true;
-                        equations.get_mut(&name).unwrap().0 = Token::Value(*value);
-                    }
+/// Checks part two can be solved by working back from `root`: it has to
+/// compare two monkeys, and on the way to `humn` each operation can only
+/// wait on `humn` on one side, and never divide by it.
+fn check_humn(equations: &HashMap<String, Equation>) -> Result<(), AocError> {
+    if !matches!(equations.get("root"), Some(Equation::Combine(..))) {
+        return Err(AocError::unsolvable(
+            "The root monkey must compare two others",
+        ));
+    }
+    if !equations.contains_key("humn") {
+        return Err(AocError::unsolvable("There is no humn to solve for"));
+    }
+    let mut dependents = HashSet::from(["humn"]);
+    for name in waiting_order(equations, ["root"])? {
+        if name == "humn" {
+            continue;
+        }
+        let waits =
+            |token: &Token| matches!(token, Token::Symbol(s) if dependents.contains(s.as_str()));
+        let depends = match &equations[name] {
+            Equation::Yell(token) => waits(token),
+            Equation::Combine(a, op, b) => match (waits(a), waits(b)) {
+                (true, true) => {
+                    return Err(AocError::unsolvable(format!(
+                        "{name} waits on humn more than once"
+                    )))
                 }
-                if let Some(Token::Symbol(s)) = &equations.get(&name).unwrap().2 {
-                    if let Some(value) = constants.get(s) {
-                        changed = true;
-                        changed_all = true;
-                        equations.get_mut(&name).unwrap().2 = Some(Token::Value(*value));
-                    }
+                (_, true) if *op == Operation::Divide => {
+                    return Err(AocError::unsolvable(format!(
+                        "{name} divides by a number that depends on humn"
+                    )))
                 }
-            }
+                (a, b) => a || b,
+            },
+        };
+        if depends {
+            dependents.insert(name);
         }
     }
-    changed_all
+    if !dependents.contains("root") {
+        return Err(AocError::unsolvable("root does not depend on humn"));
+    }
+    Ok(())
 }
 
-fn take_constant_leaf(
-    equations: &mut HashMap<String, Equation>,
-    key: &str,
-) -> Option<(Operation, Token, Token)> {
-    let root_equation = &equations.get(key).unwrap();
-
-    if let Token::Symbol(s) = &root_equation.0 {
-        // Assume other side is value
-        if let Some(Token::Value(v)) = root_equation.2.clone() {
-            let operation = root_equation.1.as_ref().unwrap();
-
-            return Some((
-                operation.inverse(),
-                Token::Value(v),
-                Token::Symbol(s.clone()),
-            ));
-        } else {
-            panic!("Symbols on both sides of equation");
-        }
+/// The monkey on the side of an operation that depends on `humn`, whether
+/// it is on the left, and what the other side is worth.
+fn unknown_side<'a>(
+    name: &str,
+    a: &'a Token,
+    b: &'a Token,
+    known: &HashMap<&str, isize>,
+) -> Result<(&'a str, bool, isize), AocError> {
+    match (a, b, a.value(known), b.value(known)) {
+        (Token::Symbol(a), _, None, Some(b)) => Ok((a, true, b)),
+        (_, Token::Symbol(b), Some(a), None) => Ok((b, false, a)),
+        _ => Err(AocError::unsolvable(format!(
+            "Expected exactly one side of {name} to depend on humn"
+        ))),
+    }
+}
+
+/// Reads the monkeys' jobs, checking that `root` exists, that every monkey
+/// waited on is defined, and that no monkey waits on itself.
+fn parse_input(buf: &str) -> Result<HashMap<String, Equation>, AocError> {
+    let equations: Vec<_> = AocError::collect(lines(buf).map(|line| {
+        let (name, equation_input) = line.split_once(line.text, ": ")?;
+        let parts = equation_input.split(' ').collect::<Vec<_>>();
+        let equation = match parts.as_slice() {
+            [t1] => Equation::Yell(Token::parse(t1)),
+            [t1, op, t2] => {
+                let op = Operation::parse(&line, op)?;
+                if op == Operation::Divide && Token::parse(t2) == Token::Value(0) {
+                    return Err(line.error(t2, "Division by zero"));
+                }
+                Equation::Combine(Token::parse(t1), op, Token::parse(t2))
+            }
+            _ => return Err(line.error(equation_input, "Expected a number or an operation")),
+        };
+        Ok((line, name.to_string(), equation))
+    }))?;
+
+    let names = equations
+        .iter()
+        .map(|(_, name, _)| name.as_str())
+        .collect::<HashSet<_>>();
+    let mut problems = Vec::new();
+    if !names.contains("root") {
+        problems.push(AocError::unsolvable("There is no root monkey"));
     }
-    if let Some(Token::Symbol(s)) = &root_equation.2 {
-        // Assume other side is value
-        if let Token::Value(v) = root_equation.0.clone() {
-            let operation = root_equation.1.as_ref().unwrap();
-            match operation {
-                Operation::Add => Some((
-                    operation.inverse(),
-                    Token::Value(v),
-                    Token::Symbol(s.clone()),
-                )),
-                Operation::Multiply => Some((
-                    operation.inverse(),
-                    Token::Value(v),
-                    Token::Symbol(s.clone()),
-                )),
-                Operation::Subtract => Some((
-                    operation.inverse(),
-                    Token::Symbol(s.clone()),
-                    Token::Value(v),
-                )),
-                Operation::Divide => todo!(),
-                Operation::Equals => todo!(),
+    for (line, _, equation) in &equations {
+        for s in equation.waits_on() {
+            if !names.contains(s) {
+                problems.push(line.error(line.text, format!("Unknown monkey: {s}")));
             }
-        } else {
-            panic!("Symbols on both sides of equation");
         }
-    } else {
-        None
     }
+    AocError::check(problems)?;
+
+    let order = equations
+        .iter()
+        .map(|(_, name, _)| name.clone())
+        .collect::<Vec<_>>();
+    let equations = equations
+        .into_iter()
+        .map(|(_, name, equation)| (name, equation))
+        .collect();
+    waiting_order(&equations, order.iter().map(String::as_str))?;
+    Ok(equations)
 }
 
-fn rotate(equations: &mut HashMap<String, Equation>) -> bool {
-    if let Token::Value(v) = equations.get("root").unwrap().0.clone() {
-        // Left hand side is constant so lets move values from right to left
-        if let Token::Symbol(ref s) = equations.get("root").unwrap().2.clone().unwrap() {
-            if let Some((operation, value, token)) = take_constant_leaf(equations, s) {
-                // println!("Rotating {} {} {:?}", v, operation.print(), token);
-                let new_key = format!("root-{v}");
-                equations.get_mut("root").unwrap().0 = Token::Symbol(new_key.clone());
-                equations.get_mut("root").unwrap().2 = Some(token);
-                equations.insert(
-                    new_key,
-                    Equation(Token::Value(v), Some(operation), Some(value)),
-                );
-                return true;
-            }
+/// Reads the monkeys' jobs, keyed by name.
+pub fn parse(input: impl BufRead) -> Result<HashMap<String, Equation>, AocError> {
+    parse_input(&read_input(input)?)
+}
+
+/// The number `root` yells.
+pub fn part_one(equations: &HashMap<String, Equation>) -> Result<isize, AocError> {
+    yells(equations, "root")
+}
+
+/// The number `humn` must yell for both sides of `root` to be equal. Working
+/// back from `root`, each monkey waiting on `humn` is undone in turn.
+pub fn part_two(equations: &HashMap<String, Equation>) -> Result<isize, AocError> {
+    check_humn(equations)?;
+    let order = waiting_order(equations, ["root"])?;
+    let known = evaluate(equations, &order, Some("humn"))?;
+
+    let (mut name, mut target) = match &equations["root"] {
+        Equation::Combine(a, _, b) => {
+            let (name, _, value) = unknown_side("root", a, b, &known)?;
+            (name, value)
+        }
+        Equation::Yell(_) => {
+            return Err(AocError::unsolvable(
+                "The root monkey must compare two others",
+            ))
         }
-        false
-    } else if let Some(Token::Value(_v)) = equations.get("root").unwrap().2.clone() {
-        let root = equations.get_mut("root").unwrap();
-        root.0 = root.2.replace(root.0.clone()).unwrap();
-        true
-    } else {
-        panic!();
+    };
+    while name != "humn" {
+        (name, target) = match &equations[name] {
+            Equation::Yell(Token::Symbol(next)) => (next.as_str(), target),
+            Equation::Yell(Token::Value(_)) => {
+                return Err(AocError::unsolvable(format!(
+                    "{name} yells a number, not what humn does"
+                )))
+            }
+            Equation::Combine(a, op, b) => {
+                let (next, unknown_left, value) = unknown_side(name, a, b, &known)?;
+                let operand = op.solve(target, value, unknown_left).ok_or_else(|| {
+                    AocError::unsolvable(format!("Nothing humn yells makes {name} yell {target}"))
+                })?;
+                (next, operand)
+            }
+        };
     }
+    Ok(target)
 }
 
-fn parse_input(buf: &str) -> HashMap<String, Equation> {
-    let equations = buf
-        .lines()
-        .map(|line| {
-            let (name, equation_input) = line.split_once(": ").unwrap();
-            let mut parts = equation_input.split(' ');
-            let t1 = Token::parse(parts.next().unwrap());
-            let op = parts.next().map(Operation::parse);
-            let t2 = parts.next().map(Token::parse);
-
-            (name.to_string(), Equation(t1, op, t2))
-        })
-        .collect();
-    equations
+/// Checks the monkeys' jobs, and that part two can be solved.
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    check_humn(&parse(input)?)
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    let equations = parse_input(&buf);
-    equations
-        .get("root")
-        .unwrap()
-        .calculate(&equations)
-        .to_string()
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    // TODO Simplify the equations down
-    let mut equations = parse_input(&buf);
-
-    let root = equations.get_mut("root").unwrap();
-    root.1 = Some(Operation::Equals);
-
-    let mut ignore = HashSet::new();
-    ignore.insert("humn".to_string());
-
-    let human = equations.get_mut("humn").unwrap();
-    human.0 = Token::Value(-1);
-
-    let mut changed = true;
-    while changed {
-        let changed1 = simplify(&mut equations, &ignore);
-        // println!(
-        //     "A: {}: {}",
-        //     equations.get("root").unwrap().print(&equations),
-        //     changed1
-        // );
-        let changed2 = rotate(&mut equations);
-        changed = changed1 || changed2;
-        // println!(
-        //     "B: {}: {}",
-        //     equations.get("root").unwrap().print(&equations),
-        //     changed2
-        // );
-        // println!("{:?}", equations.get("root").unwrap().try_solve("humn"));
-        if equations.get("root").unwrap().try_solve("humn").is_some() {
-            break;
-        }
+pub struct Day21;
+
+impl Solution for Day21 {
+    fn day(&self) -> usize {
+        21
     }
 
-    equations
-        .get("root")
-        .unwrap()
-        .try_solve("humn")
-        .unwrap()
-        .to_string()
+    fn title(&self) -> &'static str {
+        "Monkey Math"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{star_one, star_two};
+    use super::{parse, part_one, part_two, star_one, star_two, validate};
+    use crate::{Answer, AocError};
     use std::io::Cursor;
 
     #[test]
@@ -406,8 +426,9 @@ pppw: cczh / lfqf
 lgvd: ljgn * ptdq
 drzm: hmdt - zczc
 hmdt: 32"
-            )),
-            "152"
+            ))
+            .unwrap(),
+            Answer::Integer(152)
         );
     }
 
@@ -430,8 +451,79 @@ pppw: cczh / lfqf
 lgvd: ljgn * ptdq
 drzm: hmdt - zczc
 hmdt: 32"
-            )),
-            "301"
+            ))
+            .unwrap(),
+            Answer::Integer(301)
         );
     }
+
+    #[test]
+    fn test_unsolvable() {
+        // Each of these used to crash rather than fail.
+        let cases: [(&str, fn(&_) -> _); 4] = [
+            (
+                "root: aaaa + humn\naaaa: 10 / bbbb\nbbbb: 0\nhumn: 1",
+                part_one,
+            ),
+            ("root: aaaa * aaaa\naaaa: 4294967296 * 4294967296", part_one),
+            ("root: 5\nhumn: 1", part_two),
+            (
+                "root: aaaa + bbbb\naaaa: 10 / humn\nbbbb: 2\nhumn: 1",
+                part_two,
+            ),
+        ];
+        for (input, part) in cases {
+            let equations = parse(input.as_bytes()).unwrap();
+            assert!(
+                matches!(part(&equations), Err(AocError::Unsolvable(_))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let mut input = String::from("root: m0 + humn\nhumn: 1\n");
+        for i in 0..100_000 {
+            input.push_str(&format!("m{i}: m{} + 1\n", i + 1));
+        }
+        input.push_str("m100000: 5\n");
+        let equations = parse(input.as_bytes()).unwrap();
+        assert_eq!(part_one(&equations).unwrap(), 100_006);
+        assert_eq!(part_two(&equations).unwrap(), 100_005);
+    }
+
+    #[test]
+    fn test_validate() {
+        let cases = [
+            (
+                "root: aaaa + bbbb\naaaa: bbbb + 1\nbbbb: aaaa + 2\nhumn: 1",
+                "waits on itself",
+            ),
+            (
+                "root: aaaa + humn\naaaa: 10 / 0\nhumn: 1",
+                "line 2, column 12: Division by zero",
+            ),
+            (
+                "root: 5\nhumn: 1",
+                "The root monkey must compare two others",
+            ),
+            (
+                "root: aaaa + bbbb\naaaa: 10 / humn\nbbbb: 2\nhumn: 1",
+                "aaaa divides by a number that depends on humn",
+            ),
+            (
+                "root: aaaa + bbbb\naaaa: humn * humn\nbbbb: 4\nhumn: 1",
+                "aaaa waits on humn more than once",
+            ),
+            (
+                "root: aaaa + bbbb\naaaa: cccc * 2\nbbbb: cccc + 1\ncccc: humn\nhumn: 1",
+                "root waits on humn more than once",
+            ),
+        ];
+        for (input, problem) in cases {
+            let message = validate(input.as_bytes()).unwrap_err().to_string();
+            assert!(message.contains(problem), "{input}: {message}");
+        }
+    }
 }
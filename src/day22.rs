@@ -1,100 +1,82 @@
-use std::{collections::HashMap, io::BufRead, ops::Add};
-
-use itertools::Itertools;
-
-type Coord = (isize, isize);
-
+//! Day 22: Monkey Map
+//!
+//! The monkeys' notes are a [`Map`] of the board and a path of [`Command`]s
+//! to follow across it. [`part_one`] wraps around the flat board, and
+//! [`part_two`] folds it into a cube first.
+
+use crate::{
+    checkpoint,
+    grid::{Bounds, Grid, SparseGrid},
+    input::{read_input, Line},
+    parse::blocks,
+    point::{Direction, Point2},
+    Answer, AocError, Solution,
+};
+use std::{collections::HashMap, io::BufRead};
+use tracing::{debug, trace};
+
+/// One step of the path: turn where we stand, or walk forwards.
 #[derive(Debug)]
-enum Command {
+pub enum Command {
     Left,
     Right,
     Move(isize),
 }
 
-type State = (isize, isize, i8);
+/// Where we are and which way we face, scored as in the puzzle.
+type State = (Point2, i8);
+
+/// The opposite of `i8::from(Direction)`.
+fn facing(dir: i8) -> Result<Direction, AocError> {
+    match dir {
+        0 => Ok(Direction::Right),
+        1 => Ok(Direction::Down),
+        2 => Ok(Direction::Left),
+        3 => Ok(Direction::Up),
+        x => Err(AocError::unsolvable(format!(
+            "Direction is not valid ({x})"
+        ))),
+    }
+}
 
 impl Command {
-    fn move_position(&self, map: &Map, state: State) -> Result<State, String> {
+    fn move_position(&self, map: &Map, state: State) -> Result<State, AocError> {
         match self {
-            Command::Left => Ok((state.0, state.1, (state.2 - 1).rem_euclid(4))),
-            Command::Right => Ok((state.0, state.1, (state.2 + 1).rem_euclid(4))),
+            Command::Left => Ok((state.0, (state.1 - 1).rem_euclid(4))),
+            Command::Right => Ok((state.0, (state.1 + 1).rem_euclid(4))),
             Command::Move(x) => {
-                let mut curr_pos = (state.0, state.1);
-                let mut curr_dir = state.2;
+                let (mut curr_pos, mut curr_dir) = state;
                 for _i in 0..*x {
-                    let next_position = match curr_dir {
-                        // Right
-                        0 => (curr_pos.0, curr_pos.1 + 1),
-                        // Down
-                        1 => (curr_pos.0 + 1, curr_pos.1),
-                        // Left
-                        2 => (curr_pos.0, curr_pos.1 - 1),
-                        // Up
-                        3 => (curr_pos.0 - 1, curr_pos.1),
-                        x => panic!("Direction is not valid ({x})"),
-                    };
-                    (curr_pos, curr_dir) = match map.positions.get(&next_position) {
+                    checkpoint()?;
+                    let next_position = curr_pos + facing(curr_dir)?;
+                    (curr_pos, curr_dir) = match map.positions.get(next_position) {
                         Some(true) => {
                             (next_position, curr_dir) // Valid move
                         }
                         Some(false) => break, // found wall
                         None => {
                             // Wrap around
-                            println!("Wrapping around: {next_position:?}");
+                            trace!("Wrapping around: {next_position:?}");
                             let (wrapped_pos, wrapped_dir) = map.get_most(&curr_pos, curr_dir)?;
                             // Check for wall
-                            match map.positions.get(&wrapped_pos) {
+                            match map.positions.get(wrapped_pos) {
                                 Some(true) => (wrapped_pos, wrapped_dir.into()), // Valid move
                                 Some(false) => break,                            // found wall
-                                None => panic!(
-                                    "Get most returned a position off the map: {wrapped_pos:?}"
-                                ),
+                                None => {
+                                    return Err(AocError::unsolvable(format!(
+                                        "Wrapping around from {curr_pos:?} went off the map at {wrapped_pos:?}"
+                                    )))
+                                }
                             }
                         }
                     };
                 }
-                Ok((curr_pos.0, curr_pos.1, curr_dir))
+                Ok((curr_pos, curr_dir))
             }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
-impl Add<Coord> for Direction {
-    type Output = Coord;
-
-    fn add(self, rhs: Coord) -> Self::Output {
-        let delta = match self {
-            Direction::Up => (-1, 0),
-            Direction::Right => (0, 1),
-            Direction::Down => (1, 0),
-            Direction::Left => (0, -1),
-        };
-        (rhs.0 + delta.0, rhs.1 + delta.1)
-    }
-}
-
-impl Add<Direction> for Coord {
-    type Output = Coord;
-
-    fn add(self, rhs: Direction) -> Self::Output {
-        let delta = match rhs {
-            Direction::Up => (-1, 0),
-            Direction::Right => (0, 1),
-            Direction::Down => (1, 0),
-            Direction::Left => (0, -1),
-        };
-        (self.0 + delta.0, self.1 + delta.1)
-    }
-}
-
 impl From<Direction> for i8 {
     fn from(value: Direction) -> Self {
         match value {
@@ -106,17 +88,6 @@ impl From<Direction> for i8 {
     }
 }
 
-impl Direction {
-    fn inverse(&self) -> Direction {
-        match self {
-            Direction::Up => Self::Down,
-            Direction::Right => Self::Left,
-            Direction::Down => Self::Up,
-            Direction::Left => Self::Right,
-        }
-    }
-}
-
 #[derive(Debug, PartialEq, Eq)]
 enum CornerDirection {
     TopLeft,
@@ -132,123 +103,146 @@ enum CornerType {
     Flat,
 }
 
-struct Map {
-    positions: HashMap<Coord, bool>,
-    bounds: (isize, isize, isize, isize),
+/// The board, along with how walking off one edge brings us back on.
+#[derive(Debug, Clone)]
+pub struct Map {
+    /// Open tiles are `true` and walls `false`.
+    positions: SparseGrid<bool>,
+    bounds: Bounds,
     dimensions: usize,
-    edge_mapping: HashMap<(Coord, Direction), (Coord, Direction)>,
+    edge_mapping: HashMap<(Point2, Direction), (Point2, Direction)>,
 }
 
 impl Map {
-    fn get_left_most(&self, coord: &Coord) -> Result<(Coord, Direction), String> {
+    /// The same board folded into a cube, so walking off a face carries on
+    /// across the face it meets.
+    pub fn fold(&self) -> Result<Map, AocError> {
+        face_size(self)?;
+        let mut map = Map {
+            dimensions: 3,
+            edge_mapping: HashMap::new(),
+            ..self.clone()
+        };
+        map.init_edge_mapping();
+        Ok(map)
+    }
+
+    fn get_left_most(&self, coord: &Point2) -> Result<(Point2, Direction), AocError> {
         if self.dimensions == 3 {
             self.edge_mapping
                 .get(&(*coord, Direction::Right))
                 .cloned()
                 .ok_or_else(|| {
-                    format!(
+                    AocError::unsolvable(format!(
                         "No left most for {:?}\nR:{:?}\nD:{:?}\nL:{:?}\nU:{:?}\n",
                         coord,
                         self.edge_mapping.get(&(*coord, Direction::Right)),
                         self.edge_mapping.get(&(*coord, Direction::Down)),
                         self.edge_mapping.get(&(*coord, Direction::Left)),
                         self.edge_mapping.get(&(*coord, Direction::Up))
-                    )
+                    ))
                 })
         } else {
-            let x = (self.bounds.2..=self.bounds.3)
-                .find(|j| self.positions.contains_key(&(coord.0, *j)))
-                .unwrap();
-            Ok(((coord.0, x), Direction::Right))
+            let x = (self.bounds.min.x..=self.bounds.max.x)
+                .find(|&x| self.positions.contains(Point2::new(x, coord.y)))
+                .ok_or_else(|| AocError::unsolvable(format!("Row {} has no tiles", coord.y)))?;
+            Ok((Point2::new(x, coord.y), Direction::Right))
         }
     }
 
-    fn get_right_most(&self, coord: &Coord) -> Result<(Coord, Direction), String> {
+    fn get_right_most(&self, coord: &Point2) -> Result<(Point2, Direction), AocError> {
         if self.dimensions == 3 {
             self.edge_mapping
                 .get(&(*coord, Direction::Left))
                 .cloned()
                 .ok_or_else(|| {
-                    format!(
+                    AocError::unsolvable(format!(
                         "No right most for {:?}\nR:{:?}\nD:{:?}\nL:{:?}\nU:{:?}\n",
                         coord,
                         self.edge_mapping.get(&(*coord, Direction::Right)),
                         self.edge_mapping.get(&(*coord, Direction::Down)),
                         self.edge_mapping.get(&(*coord, Direction::Left)),
                         self.edge_mapping.get(&(*coord, Direction::Up))
-                    )
+                    ))
                 })
         } else {
-            let x = (self.bounds.2..=self.bounds.3)
+            let x = (self.bounds.min.x..=self.bounds.max.x)
                 .rev()
-                .find(|j| self.positions.contains_key(&(coord.0, *j)))
-                .unwrap();
+                .find(|&x| self.positions.contains(Point2::new(x, coord.y)))
+                .ok_or_else(|| AocError::unsolvable(format!("Row {} has no tiles", coord.y)))?;
             // We don't care about the direction here, so just return the direction
-            Ok(((coord.0, x), Direction::Left))
+            Ok((Point2::new(x, coord.y), Direction::Left))
         }
     }
 
-    fn get_top_most(&self, coord: &Coord) -> Result<(Coord, Direction), String> {
+    fn get_top_most(&self, coord: &Point2) -> Result<(Point2, Direction), AocError> {
         if self.dimensions == 3 {
             self.edge_mapping
                 .get(&(*coord, Direction::Down))
                 .cloned()
                 .ok_or_else(|| {
-                    format!(
+                    AocError::unsolvable(format!(
                         "No top most for {:?}\nR:{:?}\nD:{:?}\nL:{:?}\nU:{:?}\n",
                         coord,
                         self.edge_mapping.get(&(*coord, Direction::Right)),
                         self.edge_mapping.get(&(*coord, Direction::Down)),
                         self.edge_mapping.get(&(*coord, Direction::Left)),
                         self.edge_mapping.get(&(*coord, Direction::Up))
-                    )
+                    ))
                 })
         } else {
-            let y = (self.bounds.0..=self.bounds.1)
-                .find(|i| self.positions.contains_key(&(*i, coord.1)))
-                .unwrap();
-            Ok(((y, coord.1), Direction::Down))
+            let y = (self.bounds.min.y..=self.bounds.max.y)
+                .find(|&y| self.positions.contains(Point2::new(coord.x, y)))
+                .ok_or_else(|| AocError::unsolvable(format!("Column {} has no tiles", coord.x)))?;
+            Ok((Point2::new(coord.x, y), Direction::Down))
         }
     }
 
-    fn get_bottom_most(&self, coord: &Coord) -> Result<(Coord, Direction), String> {
+    fn get_bottom_most(&self, coord: &Point2) -> Result<(Point2, Direction), AocError> {
         if self.dimensions == 3 {
             // self.get_corresponding_point(col)
             self.edge_mapping
                 .get(&(*coord, Direction::Up))
                 .cloned()
                 .ok_or_else(|| {
-                    format!(
+                    AocError::unsolvable(format!(
                         "No bottom most for {:?}\nR:{:?}\nD:{:?}\nL:{:?}\nU:{:?}\n",
                         coord,
                         self.edge_mapping.get(&(*coord, Direction::Right)),
                         self.edge_mapping.get(&(*coord, Direction::Down)),
                         self.edge_mapping.get(&(*coord, Direction::Left)),
                         self.edge_mapping.get(&(*coord, Direction::Up))
-                    )
+                    ))
                 })
         } else {
-            let y = (self.bounds.0..=self.bounds.1)
+            let y = (self.bounds.min.y..=self.bounds.max.y)
                 .rev()
-                .find(|i| self.positions.contains_key(&(*i, coord.1)))
-                .unwrap();
-            Ok(((y, coord.1), Direction::Up))
+                .find(|&y| self.positions.contains(Point2::new(coord.x, y)))
+                .ok_or_else(|| AocError::unsolvable(format!("Column {} has no tiles", coord.x)))?;
+            Ok((Point2::new(coord.x, y), Direction::Up))
         }
     }
 
-    fn categorise2(&self, coord: &Coord) -> Option<CornerType> {
-        let top = self
+    fn categorise2(&self, coord: &Point2) -> Option<CornerType> {
+        let coord = *coord;
+        let top = self.positions.contains(coord + Direction::Up);
+        let bottom = self.positions.contains(coord + Direction::Down);
+        let left = self.positions.contains(coord + Direction::Left);
+        let right = self.positions.contains(coord + Direction::Right);
+
+        let top_left = self
+            .positions
+            .contains(coord + Direction::Up + Direction::Left);
+        let top_right = self
+            .positions
+            .contains(coord + Direction::Up + Direction::Right);
+        let bottom_left = self
             .positions
-            .contains_key(&((coord.0, coord.1) + Direction::Up));
-        let bottom = self.positions.contains_key(&(coord.0 + 1, coord.1));
-        let left = self.positions.contains_key(&(coord.0, coord.1 - 1));
-        let right = self.positions.contains_key(&(coord.0, coord.1 + 1));
-
-        let top_left = self.positions.contains_key(&(coord.0 - 1, coord.1 - 1));
-        let top_right = self.positions.contains_key(&(coord.0 - 1, coord.1 + 1));
-        let bottom_left = self.positions.contains_key(&(coord.0 + 1, coord.1 - 1));
-        let bottom_right = self.positions.contains_key(&(coord.0 + 1, coord.1 + 1));
-        if !self.positions.contains_key(coord) {
+            .contains(coord + Direction::Down + Direction::Left);
+        let bottom_right = self
+            .positions
+            .contains(coord + Direction::Down + Direction::Right);
+        if !self.positions.contains(coord) {
             return None;
         }
 
@@ -285,10 +279,10 @@ impl Map {
 
     fn get_next_point(
         &self,
-        coord: &Coord,
+        coord: &Point2,
         dir: Direction,
-    ) -> ((Coord, Direction), Option<(Coord, Direction)>) {
-        let next = dir + *coord;
+    ) -> ((Point2, Direction), Option<(Point2, Direction)>) {
+        let next = *coord + dir;
         let position_type = self.categorise2(&next);
         match position_type {
             Some(CornerType::Internal(CornerDirection::TopLeft)) => {
@@ -366,17 +360,17 @@ impl Map {
         }
     }
 
-    fn get_direction_of_edge(&self, coord: &Coord, direction: &Direction) -> Direction {
+    fn get_direction_of_edge(&self, coord: &Point2, direction: &Direction) -> Direction {
         match direction {
             Direction::Up | Direction::Down => {
-                if self.positions.contains_key(&(coord.0, coord.1 - 1)) {
+                if self.positions.contains(*coord + Direction::Left) {
                     Direction::Right
                 } else {
                     Direction::Left
                 }
             }
             Direction::Right | Direction::Left => {
-                if self.positions.contains_key(&(coord.0 - 1, coord.1)) {
+                if self.positions.contains(*coord + Direction::Up) {
                     Direction::Down
                 } else {
                     Direction::Up
@@ -388,26 +382,47 @@ impl Map {
     fn init_edge_mapping(&mut self) {
         let mut stack = Vec::new();
 
-        println!("{:?}", self.bounds);
+        debug!("Mapping the edges of {:?}", self.bounds);
 
-        for y in self.bounds.0..=self.bounds.1 {
-            for x in self.bounds.2..=self.bounds.3 {
-                match self.categorise2(&(y, x)) {
+        for y in self.bounds.min.y..=self.bounds.max.y {
+            for x in self.bounds.min.x..=self.bounds.max.x {
+                let p = Point2::new(x, y);
+                match self.categorise2(&p) {
                     Some(CornerType::Internal(CornerDirection::TopLeft)) => {
-                        println!("Top left found at ({y}, {x})");
-                        stack.push(((y - 1, x), Direction::Up, (y, x - 1), Direction::Left))
+                        debug!("Top left corner at ({y}, {x})");
+                        stack.push((
+                            p + Direction::Up,
+                            Direction::Up,
+                            p + Direction::Left,
+                            Direction::Left,
+                        ))
                     }
                     Some(CornerType::Internal(CornerDirection::TopRight)) => {
-                        println!("Top right found at ({y}, {x})");
-                        stack.push(((y - 1, x), Direction::Up, (y, x + 1), Direction::Right))
+                        debug!("Top right corner at ({y}, {x})");
+                        stack.push((
+                            p + Direction::Up,
+                            Direction::Up,
+                            p + Direction::Right,
+                            Direction::Right,
+                        ))
                     }
                     Some(CornerType::Internal(CornerDirection::BottomLeft)) => {
-                        println!("Bottom left found at ({y}, {x})");
-                        stack.push(((y + 1, x), Direction::Down, (y, x - 1), Direction::Left))
+                        debug!("Bottom left corner at ({y}, {x})");
+                        stack.push((
+                            p + Direction::Down,
+                            Direction::Down,
+                            p + Direction::Left,
+                            Direction::Left,
+                        ))
                     }
                     Some(CornerType::Internal(CornerDirection::BottomRight)) => {
-                        println!("Bottom right found at ({y}, {x})");
-                        stack.push(((y + 1, x), Direction::Down, (y, x + 1), Direction::Right))
+                        debug!("Bottom right corner at ({y}, {x})");
+                        stack.push((
+                            p + Direction::Down,
+                            Direction::Down,
+                            p + Direction::Right,
+                            Direction::Right,
+                        ))
                     }
                     _ => {}
                 }
@@ -415,20 +430,22 @@ impl Map {
         }
 
         while let Some((side1, dir1, side2, dir2)) = stack.pop() {
-            println!("{stack:?}");
-            println!("Processing: {side1:?}, {dir1:?} - {side2:?}, {dir2:?}");
+            trace!(
+                remaining = stack.len(),
+                "Zipping {side1:?}, {dir1:?} - {side2:?}, {dir2:?}"
+            );
 
             let corner_type1 = self.categorise2(&side1);
             let corner_type2 = self.categorise2(&side2);
 
-            println!("{corner_type1:?} {corner_type2:?}");
+            trace!("Corners: {corner_type1:?} {corner_type2:?}");
 
             assert_ne!(side1, side2);
 
             if matches!(corner_type1, Some(CornerType::Outside(_)))
                 && matches!(corner_type2, Some(CornerType::Outside(_)))
             {
-                println!("Found two outside corners");
+                trace!("Both sides reached outside corners");
                 continue;
             }
 
@@ -460,9 +477,7 @@ impl Map {
             let (next_side2, maybe_next_side2) = self.get_next_point(&side2, dir2);
 
             if maybe_next_side1.is_some() || maybe_next_side2.is_some() {
-                println!("452: {maybe_next_side1:?} {maybe_next_side2:?}");
-
-                println!("Adding to mapping: {next_side1:?} {next_side2:?}");
+                trace!("Turning a corner: {next_side1:?} {next_side2:?}");
                 self.edge_mapping.insert(
                     (
                         next_side1.0,
@@ -509,138 +524,181 @@ impl Map {
         }
     }
 
-    fn get_most(&self, position: &Coord, dir: i8) -> Result<(Coord, Direction), String> {
+    fn get_most(&self, position: &Point2, dir: i8) -> Result<(Point2, Direction), AocError> {
         match dir {
             0 => self.get_left_most(position),
             1 => self.get_top_most(position),
             2 => self.get_right_most(position),
             3 => self.get_bottom_most(position),
-            x => panic!("Invalid direction: {x}"),
+            x => Err(AocError::unsolvable(format!("Invalid direction: {x}"))),
         }
     }
 }
 
-fn parse_input(buf: &str, dimensions: usize) -> (Map, Vec<Command>) {
-    let (map_input, path_input) = buf.split_once("\n\n").unwrap();
-    let path = {
-        let mut path = vec![];
-        let mut digits = vec![];
+fn parse_path(line: &Line) -> Result<Vec<Command>, AocError> {
+    let mut path = vec![];
+    let mut start = None;
 
-        for c in path_input.chars() {
-            match c {
-                x if x.is_ascii_digit() => {
-                    digits.push(x);
-                }
-                'R' => {
-                    if !digits.is_empty() {
-                        path.push(Command::Move(
-                            digits
-                                .drain(..)
-                                .collect::<String>()
-                                .parse::<isize>()
-                                .unwrap(),
-                        ));
-                    }
-                    path.push(Command::Right);
-                }
-                'L' => {
-                    if !digits.is_empty() {
-                        path.push(Command::Move(
-                            digits
-                                .drain(..)
-                                .collect::<String>()
-                                .parse::<isize>()
-                                .unwrap_or_else(|_e| panic!("{digits:?}")),
-                        ));
-                    }
-                    path.push(Command::Left);
+    for (i, c) in line.text.char_indices() {
+        match c {
+            x if x.is_ascii_digit() => {
+                start.get_or_insert(i);
+            }
+            'R' | 'L' => {
+                if let Some(start) = start.take() {
+                    path.push(Command::Move(line.parse::<isize>(&line.text[start..i])?));
                 }
-                _ => panic!(),
+                path.push(if c == 'R' {
+                    Command::Right
+                } else {
+                    Command::Left
+                });
             }
+            x => return Err(line.error(&line.text[i..], format!("Unknown path step: {x}"))),
         }
+    }
 
-        if !digits.is_empty() {
-            path.push(Command::Move(
-                digits.iter().collect::<String>().parse::<isize>().unwrap(),
-            ));
-        }
-        path
+    if let Some(start) = start {
+        path.push(Command::Move(line.parse::<isize>(&line.text[start..])?));
+    }
+    Ok(path)
+}
+
+fn parse_input(buf: &str) -> Result<(Map, Vec<Command>), AocError> {
+    let blocks = blocks(buf);
+    let [map_input, path_input] = blocks.as_slice() else {
+        return Err(AocError::unsolvable(
+            "Expected a map and a path separated by a blank line",
+        ));
     };
-    let map = {
-        let positions = HashMap::from_iter(map_input.lines().enumerate().flat_map(|(i, line)| {
-            line.chars().enumerate().filter_map(move |(j, c)| match c {
-                '.' => Some(((i as isize, j as isize), true)),
-                '#' => Some(((i as isize, j as isize), false)),
-                ' ' => None,
-                _ => panic!("Bad map"),
-            })
-        }));
-        let minmax_y = positions
-            .keys()
-            .map(|(i, _j)| i)
-            .minmax()
-            .into_option()
-            .unwrap();
-        let minmax_x = positions
-            .keys()
-            .map(|(_i, j)| j)
-            .minmax()
-            .into_option()
-            .unwrap();
-        let bounds = (*minmax_y.0, *minmax_y.1, *minmax_x.0, *minmax_x.1);
-        Map {
-            positions,
-            bounds,
-            dimensions,
-            edge_mapping: HashMap::new(),
-        }
+    let path = match path_input.as_slice() {
+        [line] => parse_path(line)?,
+        _ => return Err(path_input[1].error(path_input[1].text, "Expected a single path")),
+    };
+    let positions = SparseGrid::from_lines(map_input.iter().copied(), ' ', |c| match c {
+        '.' => Some(true),
+        '#' => Some(false),
+        _ => None,
+    })?;
+    let bounds = positions
+        .bounds()
+        .ok_or_else(|| AocError::unsolvable("The map is empty"))?;
+    let map = Map {
+        positions,
+        bounds,
+        dimensions: 2,
+        edge_mapping: HashMap::new(),
     };
-    (map, path)
+    Ok((map, path))
+}
+
+/// The length of each face of the cube the map folds into. Each face has to
+/// be a whole square, lined up with the others on a grid of that size.
+fn face_size(map: &Map) -> Result<isize, AocError> {
+    let tiles = map.positions.len();
+    let size = (tiles / 6).isqrt();
+    if size == 0 || 6 * size * size != tiles {
+        return Err(AocError::unsolvable(format!(
+            "{tiles} tiles cannot fold into a cube"
+        )));
+    }
+    let size = size as isize;
+    let mut problems = Vec::new();
+    for top in (0..=map.bounds.max.y).step_by(size as usize) {
+        for left in (0..=map.bounds.max.x).step_by(size as usize) {
+            let filled = (top..top + size)
+                .flat_map(|y| (left..left + size).map(move |x| Point2::new(x, y)))
+                .filter(|&pos| map.positions.contains(pos))
+                .count() as isize;
+            if filled != 0 && filled != size * size {
+                problems.push(AocError::parse(
+                    top as usize + 1,
+                    left as usize + 1,
+                    format!("Expected a full {size}x{size} face here"),
+                ));
+            }
+        }
+    }
+    AocError::check(problems)?;
+    Ok(size)
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// Reads the flat board and the path to follow across it.
+pub fn parse(input: impl BufRead) -> Result<(Map, Vec<Command>), AocError> {
+    parse_input(&read_input(input)?)
+}
 
-    let (map, commands) = parse_input(&buf, 2);
+/// Follows the path from the leftmost open tile of the top row, and scores
+/// where it ends up.
+pub fn password(map: &Map, path: &[Command]) -> Result<isize, AocError> {
+    let start_x = (0..=map.bounds.max.x)
+        .find(|&x| map.positions.contains(Point2::new(x, 0)))
+        .ok_or_else(|| AocError::unsolvable("There is no open tile on the top row"))?;
+    let mut position = (Point2::new(start_x, 0), 0);
+
+    for command in path {
+        trace!("At {position:?}");
+        position = command.move_position(map, position)?;
+    }
+    Ok(1000 * (position.0.y + 1) + 4 * (position.0.x + 1) + position.1 as isize)
+}
 
-    let start_x = (0..=map.bounds.3)
-        .find(|j| map.positions.contains_key(&(0, *j)))
-        .unwrap();
+/// The password when the board wraps around flat.
+pub fn part_one(map: &Map, path: &[Command]) -> Result<isize, AocError> {
+    password(map, path)
+}
 
-    let mut position = (0, map.get_left_most(&(0, start_x)).unwrap().0 .1, 0);
+/// The password when the board is folded into a cube.
+pub fn part_two(map: &Map, path: &[Command]) -> Result<isize, AocError> {
+    password(&map.fold()?, path)
+}
 
-    for command in commands {
-        println!("{position:?}");
-        position = command
-            .move_position(&map, position)
-            .unwrap_or_else(|e| panic!("Error: {e:?} at {position:?}"));
-    }
-    (1000 * (position.0 + 1) + 4 * (position.1 + 1) + position.2 as isize).to_string()
+/// Checks the map folds into a cube, as part two needs.
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    let (map, _) = parse(input)?;
+    face_size(&map)?;
+    Ok(())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    let (map, path) = parse(input)?;
+    Ok(part_one(&map, &path)?.into())
+}
+
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    let (map, path) = parse(input)?;
+    Ok(part_two(&map, &path)?.into())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let (mut map, commands) = parse_input(&buf, 3);
-    let start_x = (0..=map.bounds.3)
-        .find(|j| map.positions.contains_key(&(0, *j)))
-        .unwrap();
-    let mut position = (0, start_x, 0);
-    map.init_edge_mapping();
-
-    for command in commands {
-        position = command
-            .move_position(&map, position)
-            .unwrap_or_else(|e| panic!("Error: {e:?} at {position:?}"));
+pub struct Day22;
+
+impl Solution for Day22 {
+    fn day(&self) -> usize {
+        22
+    }
+
+    fn title(&self) -> &'static str {
+        "Monkey Map"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
     }
-    (1000 * (position.0 + 1) + 4 * (position.1 + 1) + position.2 as isize).to_string()
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{star_one, star_two};
+    use super::{facing, parse, star_one, star_two, validate};
+    use crate::{point::Point2, Answer, AocError};
     use std::io::Cursor;
 
     #[test]
@@ -661,8 +719,9 @@ mod tests {
         ......#.
 
 10R5L5R10L4R5L5"
-            )),
-            "6032"
+            ))
+            .unwrap(),
+            Answer::Integer(6032)
         );
     }
 
@@ -684,8 +743,70 @@ mod tests {
         ......#.
 
 10R5L5R10L4R5L5"
-            )),
-            "5031"
+            ))
+            .unwrap(),
+            Answer::Integer(5031)
         );
     }
+
+    #[test]
+    fn test_validate() {
+        // The example with the last row of the bottom right face cut off.
+        let problems = match validate(Cursor::new(
+            b"        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......
+
+10R5L5R10L4R5L5",
+        )) {
+            Err(AocError::Unsolvable(message)) => message,
+            x => panic!("Expected the map not to fold, got {x:?}"),
+        };
+        assert_eq!(problems, "94 tiles cannot fold into a cube");
+    }
+
+    #[test]
+    fn test_bad_directions() {
+        let (map, _) = parse(Cursor::new(b"...\n.#.\n\n1R")).unwrap();
+        let message = |result| match result {
+            Err(AocError::Unsolvable(message)) => message,
+            x => panic!("Expected the direction to be refused, got {x:?}"),
+        };
+        assert_eq!(message(facing(4)), "Direction is not valid (4)");
+        assert_eq!(
+            message(map.get_most(&Point2::new(1, 1), 4).map(|(_, dir)| dir)),
+            "Invalid direction: 4"
+        );
+    }
+
+    #[test]
+    fn test_crlf() {
+        // Only the ends of the lines are trimmed. The spaces before the first
+        // faces of the map say where on the board each row starts.
+        let input = "        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5 \n\n"
+            .replace('\n', "\r\n");
+        assert_eq!(star_two(Cursor::new(input)).unwrap(), Answer::Integer(5031));
+    }
 }
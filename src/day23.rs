@@ -1,50 +1,52 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Display,
-    io::BufRead,
+//! Day 23: Unstable Diffusion
+//!
+//! The elves spread out over rounds. [`parse`] reads where they start as a
+//! [`Map`], [`part_one`] counts the empty ground after ten rounds, and
+//! [`part_two`] finds the first round in which no elf moves.
+
+use crate::{
+    checkpoint,
+    grid::{Bounds, Grid, SparseGrid},
+    input::read_input,
+    point::{Direction, Point2},
+    Answer, AocError, Solution,
 };
-
-use itertools::Itertools;
-
-type Coord = (isize, isize);
-
-enum Direction {
-    North,
-    South,
-    West,
-    East,
-}
-
-struct Map {
-    elves: HashSet<Coord>,
+use std::{collections::HashMap, fmt::Display, io::BufRead};
+
+/// The order elves consider moving in, before it rotates each round.
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// Where the elves are.
+#[derive(Debug, Clone)]
+pub struct Map {
+    elves: SparseGrid<()>,
 }
 
 impl Map {
-    fn bounds(&self) -> (isize, isize, isize, isize) {
-        let minmax_y = self
-            .elves
-            .iter()
-            .map(|(i, _j)| i)
-            .minmax()
-            .into_option()
-            .unwrap();
-        let minmax_x = self
-            .elves
-            .iter()
-            .map(|(_i, j)| j)
-            .minmax()
-            .into_option()
-            .unwrap();
-        (*minmax_y.0, *minmax_y.1, *minmax_x.0, *minmax_x.1)
+    /// There is always at least one elf.
+    fn bounds(&self) -> Bounds {
+        self.elves.bounds().unwrap()
+    }
+
+    /// The ground tiles without an elf in the smallest rectangle holding
+    /// every elf.
+    pub fn empty_ground(&self) -> usize {
+        let bounds = self.bounds();
+        bounds.height() * bounds.width() - self.elves.len()
     }
 }
 
 impl Display for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let bounds = self.bounds();
-        for i in bounds.0..=bounds.1 {
-            for j in bounds.2..=bounds.3 {
-                let c = if self.elves.contains(&(i, j)) {
+        for y in bounds.min.y..=bounds.max.y {
+            for x in bounds.min.x..=bounds.max.x {
+                let c = if self.elves.contains(Point2::new(x, y)) {
                     '#'
                 } else {
                     '.'
@@ -57,179 +59,133 @@ impl Display for Map {
     }
 }
 
-fn step(map: &mut Map, directions: &[Direction], start_idx: usize) -> bool {
+/// Plays one round, numbered from zero, and says whether any elf moved.
+pub fn step(map: &mut Map, start_idx: usize) -> Result<bool, AocError> {
     let mut new_positions = HashMap::new();
-    let mut new_elves = HashSet::new();
-
-    for elf in &map.elves {
-        if [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ]
-        .iter()
-        .all(|(i, j)| !map.elves.contains(&(elf.0 + i, elf.1 + j)))
-        {
-            assert!(new_elves.insert(*elf));
-        } else {
-            let mut possible_move = false;
-            for i in 0..directions.len() {
-                let direction = &directions[(start_idx + i) % directions.len()];
-                match direction {
-                    Direction::North => {
-                        if !map.elves.contains(&(elf.0 - 1, elf.1 - 1))
-                            && !map.elves.contains(&(elf.0 - 1, elf.1))
-                            && !map.elves.contains(&(elf.0 - 1, elf.1 + 1))
-                        {
-                            new_positions
-                                .entry((elf.0 - 1, elf.1))
-                                .or_insert_with(Vec::new)
-                                .push(elf);
-                            possible_move = true;
-                            break;
-                        }
-                    }
-                    Direction::South => {
-                        if !map.elves.contains(&(elf.0 + 1, elf.1 - 1))
-                            && !map.elves.contains(&(elf.0 + 1, elf.1))
-                            && !map.elves.contains(&(elf.0 + 1, elf.1 + 1))
-                        {
-                            new_positions
-                                .entry((elf.0 + 1, elf.1))
-                                .or_insert_with(Vec::new)
-                                .push(elf);
-                            possible_move = true;
-
-                            break;
-                        }
-                    }
-                    Direction::West => {
-                        if !map.elves.contains(&(elf.0 - 1, elf.1 - 1))
-                            && !map.elves.contains(&(elf.0, elf.1 - 1))
-                            && !map.elves.contains(&(elf.0 + 1, elf.1 - 1))
-                        {
-                            new_positions
-                                .entry((elf.0, elf.1 - 1))
-                                .or_insert_with(Vec::new)
-                                .push(elf);
-                            possible_move = true;
-
-                            break;
-                        }
-                    }
-                    Direction::East => {
-                        if !map.elves.contains(&(elf.0 - 1, elf.1 + 1))
-                            && !map.elves.contains(&(elf.0, elf.1 + 1))
-                            && !map.elves.contains(&(elf.0 + 1, elf.1 + 1))
-                        {
-                            new_positions
-                                .entry((elf.0, elf.1 + 1))
-                                .or_insert_with(Vec::new)
-                                .push(elf);
-                            possible_move = true;
+    let mut new_elves = SparseGrid::new();
 
-                            break;
-                        }
-                    }
-                }
-            }
-            if !possible_move {
-                assert!(new_elves.insert(*elf));
-            }
+    for elf in map.elves.positions() {
+        checkpoint()?;
+        if map.elves.neighbours8(elf).next().is_none() {
+            assert!(new_elves.insert(elf, ()).is_none());
+            continue;
+        }
+        // An elf moves the first way where it and the two diagonals either
+        // side of it are free.
+        let direction = (0..DIRECTIONS.len())
+            .map(|i| DIRECTIONS[(start_idx + i) % DIRECTIONS.len()])
+            .find(|&direction| {
+                let ahead = elf + direction;
+                [
+                    ahead,
+                    ahead + direction.turn_left(),
+                    ahead + direction.turn_right(),
+                ]
+                .iter()
+                .all(|&pos| !map.elves.contains(pos))
+            });
+        match direction {
+            Some(direction) => new_positions
+                .entry(elf + direction)
+                .or_insert_with(Vec::new)
+                .push(elf),
+            None => assert!(new_elves.insert(elf, ()).is_none()),
         }
     }
 
     if new_positions.is_empty() {
-        return false;
+        return Ok(false);
     }
 
     for (new_pos, old_postions) in new_positions {
         if old_postions.len() == 1 {
-            assert!(new_elves.insert(new_pos));
+            assert!(new_elves.insert(new_pos, ()).is_none());
         } else {
             for pos in old_postions {
-                assert!(new_elves.insert(*pos));
+                assert!(new_elves.insert(pos, ()).is_none());
             }
         }
     }
 
     *map = Map { elves: new_elves };
 
-    true
+    Ok(true)
 }
 
-fn parse_input(buf: &str) -> Map {
-    let elves = HashSet::from_iter(buf.lines().enumerate().flat_map(|(i, line)| {
-        line.chars().enumerate().filter_map(move |(j, c)| {
-            if c == '#' {
-                Some((i as isize, j as isize))
-            } else {
-                None
-            }
-        })
-    }));
-    Map { elves }
+fn parse_input(buf: &str) -> Result<Map, AocError> {
+    let elves = SparseGrid::parse(buf, '.', |c| (c == '#').then_some(()))?;
+    if elves.is_empty() {
+        return Err(AocError::unsolvable("There are no elves"));
+    }
+    Ok(Map { elves })
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-
-    let mut map = parse_input(&buf);
-
-    let directions = [
-        Direction::North,
-        Direction::South,
-        Direction::West,
-        Direction::East,
-    ];
+/// Reads where the elves start.
+pub fn parse(input: impl BufRead) -> Result<Map, AocError> {
+    parse_input(&read_input(input)?)
+}
 
+/// The empty ground after ten rounds.
+pub fn part_one(map: &Map) -> Result<usize, AocError> {
+    let mut map = map.clone();
     for i in 0..10 {
-        step(&mut map, &directions, i);
+        step(&mut map, i)?;
     }
+    Ok(map.empty_ground())
+}
+
+/// The first round in which no elf moves, counting from one.
+pub fn part_two(map: &Map) -> Result<usize, AocError> {
+    let mut map = map.clone();
+    let mut i = 0;
+    while step(&mut map, i)? {
+        i += 1
+    }
+    Ok(i + 1)
+}
+
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    parse(input)?;
+    Ok(())
+}
 
-    let bounds = map.bounds();
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_one(&parse(input)?)?.into())
+}
 
-    (bounds.0..=bounds.1)
-        .map(|i| {
-            (bounds.2..=bounds.3)
-                .filter(|j| !map.elves.contains(&(i, *j)))
-                .count()
-        })
-        .sum::<usize>()
-        .to_string()
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(part_two(&parse(input)?)?.into())
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
-    let mut map = parse_input(&buf);
+pub struct Day23;
 
-    let directions = [
-        Direction::North,
-        Direction::South,
-        Direction::West,
-        Direction::East,
-    ];
+impl Solution for Day23 {
+    fn day(&self) -> usize {
+        23
+    }
 
-    let mut i = 0;
+    fn title(&self) -> &'static str {
+        "Unstable Diffusion"
+    }
 
-    while step(&mut map, &directions, i) {
-        i += 1
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
     }
 
-    (i + 1).to_string()
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::{star_one, star_two};
+    use crate::Answer;
     use std::io::Cursor;
 
     #[test]
@@ -243,8 +199,9 @@ mod tests {
 #.###..
 ##.#.##
 .#..#.."
-            )),
-            "110"
+            ))
+            .unwrap(),
+            Answer::Integer(110)
         );
     }
 
@@ -259,8 +216,9 @@ mod tests {
 #.###..
 ##.#.##
 .#..#.."
-            )),
-            "20"
+            ))
+            .unwrap(),
+            Answer::Integer(20)
         );
     }
 }
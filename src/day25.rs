@@ -1,66 +1,128 @@
+//! Day 25: Full of Hot Air
+//!
+//! The fuel requirements are written in SNAFU, a balanced base five.
+//! [`parse`] reads them, and [`part_one`] writes their total back in SNAFU.
+//! The numbers can also be streamed from [`numbers`].
+
+use crate::{
+    input::{parse_lines, try_for_each_line, Line},
+    Answer, AocError, Solution,
+};
+use itertools::process_results;
 use std::io::BufRead;
 
-fn parse_input(buf: &str) -> Vec<isize> {
-    buf.lines()
-        .map(|line| {
-            line.chars()
-                .rev()
-                .enumerate()
-                .map(|(i, c)| {
-                    let x = match c {
-                        '2' => 2,
-                        '1' => 1,
-                        '0' => 0,
-                        '-' => -1,
-                        '=' => -2,
-                        _ => panic!(),
-                    };
-                    x * 5u64.pow(i as u32) as isize
-                })
-                .sum()
+/// Reads a SNAFU number, where each digit is worth five times the one after
+/// it and runs from `=` (-2) to `2`. Numbers too large for an `isize` are an
+/// error.
+fn parse_snafu(line: &Line) -> Result<isize, AocError> {
+    let digits: Vec<isize> = AocError::collect(line.text.char_indices().map(|(i, c)| match c {
+        '2' => Ok(2),
+        '1' => Ok(1),
+        '0' => Ok(0),
+        '-' => Ok(-1),
+        '=' => Ok(-2),
+        x => Err(line.error(&line.text[i..], format!("Bad SNAFU digit: {x}"))),
+    }))?;
+    // A running total can pass the limit and come back under it, when later
+    // digits are negative, so it is kept wider.
+    digits
+        .iter()
+        .try_fold(0i128, |total, &digit| {
+            total.checked_mul(5)?.checked_add(digit as i128)
         })
-        .collect()
+        .and_then(|total| isize::try_from(total).ok())
+        .ok_or_else(|| line.error(line.text, "SNAFU number is too large"))
 }
 
-pub fn star_one(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+/// Writes a number in SNAFU.
+pub fn to_snafu(n: isize) -> String {
+    if n == 0 {
+        return "0".to_string();
+    }
+    // Wide enough that taking off the last digit cannot overflow.
+    let mut n = n as i128;
+    let mut digits = Vec::new();
+    while n != 0 {
+        let (c, digit) = match n.rem_euclid(5) {
+            0 => ('0', 0),
+            1 => ('1', 1),
+            2 => ('2', 2),
+            3 => ('=', -2),
+            _ => ('-', -1),
+        };
+        digits.push(c);
+        n = (n - digit) / 5;
+    }
+    digits.iter().rev().collect()
+}
 
-    let numbers = parse_input(&buf);
-    let mut total: isize = numbers.iter().sum();
-    let mut v = Vec::new();
+/// Each fuel requirement, as it is read.
+pub fn numbers(input: impl BufRead) -> impl Iterator<Item = Result<isize, AocError>> {
+    parse_lines(input, |line| parse_snafu(&line))
+}
 
-    // println!("{total}");
+/// Reads every fuel requirement.
+pub fn parse(input: impl BufRead) -> Result<Vec<isize>, AocError> {
+    AocError::collect(numbers(input))
+}
 
-    while total > 0 {
-        let c = match total % 5 {
-            0 => '0',
-            1 => '1',
-            2 => '2',
-            3 => '=',
-            4 => '-',
-            _ => panic!(),
-        };
-        v.push(c);
-        total /= 5;
-        if c == '-' || c == '=' {
-            total += 1;
-        }
-    }
-    v.iter().rev().map(|x| x.to_string()).collect()
+/// The total fuel needed, in SNAFU.
+pub fn part_one(numbers: impl IntoIterator<Item = isize>) -> Result<String, AocError> {
+    numbers
+        .into_iter()
+        .try_fold(0isize, isize::checked_add)
+        .map(to_snafu)
+        .ok_or_else(|| AocError::unsolvable("The total fuel is too large"))
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut buf = String::new();
-    let _res = input.read_to_string(&mut buf);
+pub fn validate(input: impl BufRead) -> Result<(), AocError> {
+    AocError::check(numbers(input).filter_map(Result::err).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<Answer, AocError> {
+    Ok(Answer::Text(process_results(numbers(input), |numbers| {
+        part_one(numbers)
+    })??))
+}
 
-    "Merry Xmas".to_string()
+pub fn star_two(input: impl BufRead) -> Result<Answer, AocError> {
+    try_for_each_line(input, |_| Ok(()))?;
+    Ok(Answer::from("Merry Xmas"))
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    fn day(&self) -> usize {
+        25
+    }
+
+    fn title(&self) -> &'static str {
+        "Full of Hot Air"
+    }
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_one(input)
+    }
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        star_two(input)
+    }
+
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError> {
+        validate(input)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{star_one, star_two};
+    use super::{parse, parse_snafu, part_one, star_one, star_two, to_snafu, validate};
+    use crate::{
+        generate::{self, check},
+        input::lines,
+        Answer,
+    };
     use std::io::Cursor;
 
     #[test]
@@ -80,13 +142,55 @@ mod tests {
 12
 1=
 122"
-            )),
-            "2=-1=0"
+            ))
+            .unwrap(),
+            Answer::from("2=-1=0")
         );
     }
 
     #[test]
     fn test_star_two() {
-        assert_eq!(star_two(Cursor::new(b"")), "Merry Xmas");
+        assert_eq!(
+            star_two(Cursor::new(b"")).unwrap(),
+            Answer::from("Merry Xmas")
+        );
+    }
+
+    #[test]
+    fn test_snafu_round_trip() {
+        let parse = |s: &str| parse_snafu(&lines(s).next().unwrap()).unwrap();
+        check(
+            500,
+            |rng| generate::snafu(rng, 20),
+            |snafu| to_snafu(parse(snafu)) == *snafu,
+        );
+        check(
+            500,
+            |rng| rng.next_u64() as isize,
+            |&n| parse(&to_snafu(n)) == n,
+        );
+    }
+
+    #[test]
+    fn test_small_totals() {
+        assert_eq!(to_snafu(0), "0");
+        assert_eq!(to_snafu(-1), "-");
+        assert_eq!(to_snafu(-3), "-2");
+        assert_eq!(star_one(Cursor::new(b"=")).unwrap(), Answer::from("="));
+        assert_eq!(star_one(Cursor::new(b"1\n-")).unwrap(), Answer::from("0"));
+        for n in [isize::MIN, isize::MAX] {
+            let snafu = to_snafu(n);
+            assert_eq!(parse_snafu(&lines(&snafu).next().unwrap()).unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let long = "2".repeat(29);
+        assert!(validate(Cursor::new(long.as_bytes())).is_err());
+        assert!(star_one(Cursor::new(long.as_bytes())).is_err());
+        let big = to_snafu(isize::MAX);
+        let numbers = parse(Cursor::new(format!("{big}\n{big}"))).unwrap();
+        assert!(part_one(numbers).is_err());
     }
 }
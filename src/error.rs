@@ -0,0 +1,161 @@
+use std::{
+    any::Any,
+    error::Error,
+    fmt::Display,
+    io,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+/// Everything that can go wrong between reading a puzzle input and
+/// producing an answer.
+#[derive(Debug)]
+pub enum AocError {
+    Io(io::Error),
+    /// The file was not in any of the places it could be.
+    MissingFile {
+        searched: Vec<PathBuf>,
+    },
+    /// The input did not match the puzzle format. `line` and `column` are
+    /// 1-indexed.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The input parsed but has no answer.
+    Unsolvable(String),
+    /// More than one thing is wrong with the input.
+    Invalid(Vec<AocError>),
+    /// The solver was told to stop, usually because it ran out of time.
+    Cancelled,
+    /// The solver panicked, which is a bug in it rather than the input.
+    Panicked(String),
+}
+
+impl AocError {
+    pub fn parse(line: usize, column: usize, message: impl Into<String>) -> AocError {
+        AocError::Parse {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn unsolvable(message: impl Into<String>) -> AocError {
+        AocError::Unsolvable(message.into())
+    }
+
+    /// Fails with every one of `problems`, if there are any.
+    pub fn check(problems: Vec<AocError>) -> Result<(), AocError> {
+        let mut problems = problems
+            .into_iter()
+            .flat_map(|e| match e {
+                AocError::Invalid(inner) => inner,
+                e => vec![e],
+            })
+            .collect::<Vec<_>>();
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(problems.remove(0)),
+            _ => Err(AocError::Invalid(problems)),
+        }
+    }
+
+    /// Like collecting into a `Result`, but carries on past the first error
+    /// so that one bad line does not hide the rest.
+    pub fn collect<T, C: FromIterator<T>>(
+        results: impl IntoIterator<Item = Result<T, AocError>>,
+    ) -> Result<C, AocError> {
+        let mut problems = Vec::new();
+        let values = results
+            .into_iter()
+            .filter_map(|result| result.map_err(|e| problems.push(e)).ok())
+            .collect();
+        AocError::check(problems)?;
+        Ok(values)
+    }
+
+    /// Both values, or every problem with either of them.
+    pub fn both<A, B>(a: Result<A, AocError>, b: Result<B, AocError>) -> Result<(A, B), AocError> {
+        match (a, b) {
+            (Ok(a), Ok(b)) => Ok((a, b)),
+            (Err(a), Err(b)) => Err(AocError::check(vec![a, b]).unwrap_err()),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        }
+    }
+
+    /// Runs `f`, turning a panic into [`AocError::Panicked`] so that one
+    /// broken solver cannot take a whole run down with it.
+    pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, AocError>) -> Result<T, AocError> {
+        panic::catch_unwind(AssertUnwindSafe(f))
+            .unwrap_or_else(|payload| Err(AocError::Panicked(panic_message(payload.as_ref()))))
+    }
+
+    /// Each separate problem, looking inside [`AocError::Invalid`].
+    pub fn problems(&self) -> Vec<&AocError> {
+        match self {
+            AocError::Invalid(problems) => problems.iter().collect(),
+            e => vec![e],
+        }
+    }
+}
+
+impl Display for AocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AocError::Io(e) => write!(f, "could not read input: {e}"),
+            AocError::MissingFile { searched } => {
+                let name = searched
+                    .first()
+                    .and_then(|path| path.file_name())
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default();
+                write!(f, "could not find {name}, looked in:")?;
+                for path in searched {
+                    write!(f, "\n  {}", path.display())?;
+                }
+                Ok(())
+            }
+            AocError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {line}, column {column}: {message}"),
+            AocError::Unsolvable(message) => write!(f, "no solution: {message}"),
+            AocError::Invalid(problems) => {
+                write!(f, "{} problems in the input:", problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  {problem}")?;
+                }
+                Ok(())
+            }
+            AocError::Cancelled => write!(f, "cancelled before finishing"),
+            AocError::Panicked(message) => write!(f, "the solver crashed: {message}"),
+        }
+    }
+}
+
+/// What a panic said, when it said it with a string.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+impl Error for AocError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AocError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AocError {
+    fn from(value: io::Error) -> Self {
+        AocError::Io(value)
+    }
+}
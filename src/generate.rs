@@ -0,0 +1,287 @@
+//! Random puzzle inputs for property tests. Each generator writes text in the
+//! same format as the real input, so it goes through the same parsing as
+//! everything else.
+
+use std::{fmt::Write, ops::Range};
+
+/// A small seedable random number generator (SplitMix64). It is nowhere near
+/// good enough for anything but making test inputs, but the same seed always
+/// gives the same input, so a failure can be replayed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `range`, which must not be empty.
+    pub fn range(&mut self, range: Range<i64>) -> i64 {
+        let width = range.end.abs_diff(range.start);
+        range.start + (self.next_u64() % width) as i64
+    }
+
+    /// A number from `0` up to but not including `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// True one time in `n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Runs `property` against `cases` inputs, each made by `generate` from its
+/// own seed. Panics on the first input the property does not hold for,
+/// showing the input and the seed that made it.
+pub fn check<T: std::fmt::Debug>(
+    cases: u64,
+    mut generate: impl FnMut(&mut Rng) -> T,
+    mut property: impl FnMut(&T) -> bool,
+) {
+    for seed in 0..cases {
+        let input = generate(&mut Rng::new(seed));
+        if !property(&input) {
+            panic!("property does not hold for seed {seed}:\n{input:#?}");
+        }
+    }
+}
+
+/// Runs `fast` and `reference` on `cases` inputs made by `generate`, and
+/// panics on the first input they give different answers for, showing the
+/// input and the seed that made it.
+pub fn differential<T: std::fmt::Debug, A: PartialEq + std::fmt::Debug>(
+    cases: u64,
+    mut generate: impl FnMut(&mut Rng) -> T,
+    mut fast: impl FnMut(&T) -> A,
+    mut reference: impl FnMut(&T) -> A,
+) {
+    for seed in 0..cases {
+        let input = generate(&mut Rng::new(seed));
+        let (fast, reference) = (fast(&input), reference(&input));
+        if fast != reference {
+            panic!(
+                "answers differ for seed {seed}: {fast:?}, but the reference gives {reference:?}\n{input:#?}"
+            );
+        }
+    }
+}
+
+/// Shuffles the lines of `input`, for inputs where their order should not
+/// matter. `skip` lines at the start are left where they are.
+pub fn shuffle_lines(rng: &mut Rng, input: &str, skip: usize) -> String {
+    let mut lines = input.lines().collect::<Vec<_>>();
+    rng.shuffle(&mut lines[skip..]);
+    lines.join("\n")
+}
+
+/// Day 9: `moves` moves of the head of the rope, such as `R 4`.
+pub fn rope_moves(rng: &mut Rng, moves: usize) -> String {
+    (0..moves)
+        .map(|_| format!("{} {}", rng.choose(&["U", "D", "L", "R"]), rng.range(1..10)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Day 11: `count` monkeys, each testing divisibility by a different prime
+/// and throwing to two other monkeys.
+pub fn monkeys(rng: &mut Rng, count: usize) -> String {
+    const PRIMES: [i64; 9] = [2, 3, 5, 7, 11, 13, 17, 19, 23];
+    assert!((2..=PRIMES.len()).contains(&count));
+    let mut divisors = PRIMES.to_vec();
+    rng.shuffle(&mut divisors);
+
+    let mut input = String::new();
+    for (i, divisor) in divisors.iter().take(count).enumerate() {
+        let items = (0..rng.below(5))
+            .map(|_| rng.range(50..100).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let operation = match rng.below(3) {
+            0 => "old * old".to_string(),
+            1 => format!("old * {}", rng.range(2..20)),
+            _ => format!("old + {}", rng.range(1..10)),
+        };
+        let mut other = || (i + 1 + rng.below(count - 1)) % count;
+        let (if_true, if_false) = (other(), other());
+        if i > 0 {
+            input.push_str("\n\n");
+        }
+        write!(
+            input,
+            "Monkey {i}:
+  Starting items: {items}
+  Operation: new = {operation}
+  Test: divisible by {divisor}
+    If true: throw to monkey {if_true}
+    If false: throw to monkey {if_false}"
+        )
+        .unwrap();
+    }
+    input
+}
+
+/// Day 15: a line with the row to check and the size of the search area,
+/// then `count` sensors and their beacons, all within `0..size`.
+pub fn sensors(rng: &mut Rng, count: usize, size: i64) -> String {
+    let mut input = format!("{} {size}", rng.range(0..size));
+    for _ in 0..count {
+        let (sx, sy) = (rng.range(0..size), rng.range(0..size));
+        let (bx, by) = (rng.range(0..size), rng.range(0..size));
+        write!(
+            input,
+            "\nSensor at x={sx}, y={sy}: closest beacon is at x={bx}, y={by}"
+        )
+        .unwrap();
+    }
+    input
+}
+
+/// Day 16: `count` valves, starting with AA, all connected by tunnels that
+/// lead both ways. Around half of them are stuck shut.
+pub fn valves(rng: &mut Rng, count: usize) -> String {
+    assert!((2..=26).contains(&count));
+    let names = (0..count)
+        .map(|i| {
+            let letter = (b'A' + i as u8) as char;
+            format!("{letter}{letter}")
+        })
+        .collect::<Vec<_>>();
+    let mut tunnels = vec![Vec::new(); count];
+    let mut connect = |a: usize, b: usize| {
+        if a != b && !tunnels[a].contains(&b) {
+            tunnels[a].push(b);
+            tunnels[b].push(a);
+        }
+    };
+    // A tree keeps everything reachable, and a few more make loops.
+    for i in 1..count {
+        connect(i, rng.below(i));
+    }
+    for _ in 0..count / 2 {
+        connect(rng.below(count), rng.below(count));
+    }
+
+    (0..count)
+        .map(|i| {
+            let flow_rate = if i == 0 || rng.one_in(2) {
+                0
+            } else {
+                rng.range(1..25)
+            };
+            let leads = tunnels[i]
+                .iter()
+                .map(|&j| names[j].as_str())
+                .collect::<Vec<_>>();
+            let (tunnel, valve) = if leads.len() == 1 {
+                ("tunnel leads", "valve")
+            } else {
+                ("tunnels lead", "valves")
+            };
+            format!(
+                "Valve {} has flow rate={flow_rate}; {tunnel} to {valve} {}",
+                names[i],
+                leads.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Day 17: a jet pattern `len` long.
+pub fn jets(rng: &mut Rng, len: usize) -> String {
+    (0..len).map(|_| *rng.choose(&['<', '>'])).collect()
+}
+
+/// Day 18: `count` cubes, all within a `size` wide cube. Small sizes pack
+/// them in, leaving pockets of air.
+pub fn droplets(rng: &mut Rng, count: usize, size: i64) -> String {
+    (0..count)
+        .map(|_| {
+            format!(
+                "{},{},{}",
+                rng.range(0..size),
+                rng.range(0..size),
+                rng.range(0..size)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Day 19: `count` blueprints with costs around those in the puzzle.
+pub fn blueprints(rng: &mut Rng, count: usize) -> String {
+    (1..=count)
+        .map(|id| {
+            format!(
+                "Blueprint {id}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.",
+                rng.range(2..5),
+                rng.range(2..5),
+                rng.range(2..5),
+                rng.range(5..20),
+                rng.range(2..5),
+                rng.range(5..20),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Day 20: `count` numbers to mix, exactly one of which is 0. The others may
+/// repeat.
+pub fn mixing(rng: &mut Rng, count: usize) -> String {
+    let mut numbers = (1..count)
+        .map(|_| match rng.range(-1000..1000) {
+            0 => 1,
+            x => x,
+        })
+        .collect::<Vec<_>>();
+    numbers.push(0);
+    rng.shuffle(&mut numbers);
+    numbers
+        .iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Day 25: a SNAFU number of up to `digits` digits, with no leading zeros.
+pub fn snafu(rng: &mut Rng, digits: usize) -> String {
+    let mut number = rng.choose(&['1', '2']).to_string();
+    for _ in 1..=rng.below(digits) {
+        number.push(*rng.choose(&['=', '-', '0', '1', '2']));
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Rng};
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        assert_eq!(a.next_u64(), b.next_u64());
+        check(100, |rng| rng.range(-3..4), |x| (-3..4).contains(x));
+    }
+}
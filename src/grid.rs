@@ -0,0 +1,443 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+};
+
+use crate::{
+    input::{lines, Line},
+    parse::char_grid,
+    point::{Direction, Point2},
+    AocError,
+};
+
+/// A position on a grid, with `x` the column and `y` the row. Signed so that
+/// the neighbours of an edge can be looked up and found missing.
+pub type Pos = Point2;
+
+/// Up, right, down and left.
+pub const NEIGHBOURS_4: [Pos; 4] = [
+    Direction::Up.delta(),
+    Direction::Right.delta(),
+    Direction::Down.delta(),
+    Direction::Left.delta(),
+];
+
+/// The orthogonal and diagonal neighbours, in reading order.
+pub const NEIGHBOURS_8: [Pos; 8] = [
+    Point2::new(-1, -1),
+    Point2::new(0, -1),
+    Point2::new(1, -1),
+    Point2::new(-1, 0),
+    Point2::new(1, 0),
+    Point2::new(-1, 1),
+    Point2::new(0, 1),
+    Point2::new(1, 1),
+];
+
+/// The smallest rectangle holding a set of positions. Both corners are
+/// inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Bounds {
+    /// The bounds of `positions`, or `None` if there are none.
+    pub fn enclosing(positions: impl IntoIterator<Item = Pos>) -> Option<Bounds> {
+        positions.into_iter().fold(None, |bounds, pos| {
+            Some(match bounds {
+                None => Bounds { min: pos, max: pos },
+                Some(Bounds { min, max }) => Bounds {
+                    min: Point2::new(min.x.min(pos.x), min.y.min(pos.y)),
+                    max: Point2::new(max.x.max(pos.x), max.y.max(pos.y)),
+                },
+            })
+        })
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x) && (self.min.y..=self.max.y).contains(&pos.y)
+    }
+
+    /// Moves `pos` back inside the bounds as if the edges were joined, so
+    /// that leaving the right edge comes back in on the left.
+    pub fn wrap(&self, pos: Pos) -> Pos {
+        Point2::new(
+            self.min.x + (pos.x - self.min.x).rem_euclid(self.width() as isize),
+            self.min.y + (pos.y - self.min.y).rem_euclid(self.height() as isize),
+        )
+    }
+
+    /// These bounds with `n` rows and columns taken off every side, or `None`
+    /// if nothing would be left.
+    pub fn shrink(&self, n: isize) -> Option<Bounds> {
+        let bounds = Bounds {
+            min: self.min + Point2::new(n, n),
+            max: self.max - Point2::new(n, n),
+        };
+        (bounds.min.x <= bounds.max.x && bounds.min.y <= bounds.max.y).then_some(bounds)
+    }
+
+    /// Every position inside the bounds, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let Bounds { min, max } = *self;
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point2::new(x, y)))
+    }
+}
+
+/// Lookups shared by the dense and sparse grids.
+pub trait Grid<T> {
+    fn get(&self, pos: Pos) -> Option<&T>;
+
+    /// The bounds of the grid, or `None` if it is empty.
+    fn bounds(&self) -> Option<Bounds>;
+
+    fn contains(&self, pos: Pos) -> bool {
+        self.get(pos).is_some()
+    }
+
+    /// The positions `deltas` away from `pos` that are on the grid.
+    fn neighbours<'a>(
+        &'a self,
+        pos: Pos,
+        deltas: &'a [Pos],
+    ) -> impl Iterator<Item = (Pos, &'a T)> + 'a
+    where
+        T: 'a,
+    {
+        deltas.iter().filter_map(move |&delta| {
+            let next = pos + delta;
+            self.get(next).map(|value| (next, value))
+        })
+    }
+
+    fn neighbours4<'a>(&'a self, pos: Pos) -> impl Iterator<Item = (Pos, &'a T)>
+    where
+        T: 'a,
+    {
+        self.neighbours(pos, &NEIGHBOURS_4)
+    }
+
+    fn neighbours8<'a>(&'a self, pos: Pos) -> impl Iterator<Item = (Pos, &'a T)>
+    where
+        T: 'a,
+    {
+        self.neighbours(pos, &NEIGHBOURS_8)
+    }
+
+    /// Looks up `pos` as if the edges of the grid were joined.
+    fn get_wrapped(&self, pos: Pos) -> Option<&T> {
+        self.get(self.bounds()?.wrap(pos))
+    }
+
+    /// Walks from `pos` in steps of `delta`, not including `pos` itself, until
+    /// stepping off the grid.
+    fn ray<'a>(&'a self, pos: Pos, delta: Pos) -> impl Iterator<Item = (Pos, &'a T)>
+    where
+        T: 'a,
+    {
+        let mut pos = pos;
+        std::iter::from_fn(move || {
+            pos += delta;
+            self.get(pos).map(|value| (pos, value))
+        })
+    }
+}
+
+/// A rectangular grid with a value in every cell, with the top left at
+/// `(0, 0)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGrid<T> {
+    height: usize,
+    width: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> DenseGrid<T> {
+    pub fn new(height: usize, width: usize, value: T) -> DenseGrid<T> {
+        DenseGrid {
+            height,
+            width,
+            cells: vec![value; height * width],
+        }
+    }
+}
+
+impl<T> DenseGrid<T> {
+    /// Reads a character map, which must be a rectangle.
+    pub fn parse(buf: &str, tile: impl FnMut(char) -> Option<T>) -> Result<DenseGrid<T>, AocError> {
+        let rows = char_grid(lines(buf), tile)?;
+        let width = rows.first().map(|(_, row)| row.len()).unwrap_or(0);
+        AocError::check(
+            rows.iter()
+                .filter(|(_, row)| row.len() != width)
+                .map(|(line, _)| {
+                    line.error(line.text, format!("Expected {width} tiles on every row"))
+                })
+                .collect(),
+        )?;
+        Ok(DenseGrid {
+            height: rows.len(),
+            width,
+            cells: rows.into_iter().flat_map(|(_, row)| row).collect(),
+        })
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn index_of(&self, pos: Pos) -> Option<usize> {
+        let x = usize::try_from(pos.x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(pos.y).ok().filter(|&y| y < self.height)?;
+        Some(y * self.width + x)
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        let index = self.index_of(pos)?;
+        self.cells.get_mut(index)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width as isize;
+        (0..self.cells.len() as isize).map(move |i| Point2::new(i % width, i / width))
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+}
+
+impl<T> Grid<T> for DenseGrid<T> {
+    fn get(&self, pos: Pos) -> Option<&T> {
+        self.cells.get(self.index_of(pos)?)
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        (self.height > 0 && self.width > 0).then(|| Bounds {
+            min: Point2::ZERO,
+            max: Point2::new(self.width as isize - 1, self.height as isize - 1),
+        })
+    }
+}
+
+impl<T> Index<Pos> for DenseGrid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is off the grid"))
+    }
+}
+
+impl<T> IndexMut<Pos> for DenseGrid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is off the grid"))
+    }
+}
+
+/// A grid that only stores the cells that have something in them, for maps
+/// that are mostly empty or have no fixed size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Pos, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> SparseGrid<T> {
+        SparseGrid::default()
+    }
+
+    /// Reads a character map, leaving out every `blank` character. Rows
+    /// don't have to be the same length.
+    pub fn parse(
+        buf: &str,
+        blank: char,
+        tile: impl FnMut(char) -> Option<T>,
+    ) -> Result<SparseGrid<T>, AocError> {
+        SparseGrid::from_lines(lines(buf), blank, tile)
+    }
+
+    /// [`SparseGrid::parse`] for a map that is only part of the input. The
+    /// first line is row 0.
+    pub(crate) fn from_lines<'a>(
+        lines: impl IntoIterator<Item = Line<'a>>,
+        blank: char,
+        mut tile: impl FnMut(char) -> Option<T>,
+    ) -> Result<SparseGrid<T>, AocError> {
+        let rows = char_grid(lines, |c| {
+            if c == blank {
+                Some(None)
+            } else {
+                tile(c).map(Some)
+            }
+        })?;
+        Ok(rows
+            .into_iter()
+            .enumerate()
+            .flat_map(|(y, (_, row))| {
+                row.into_iter().enumerate().filter_map(move |(x, value)| {
+                    Some((Point2::new(x as isize, y as isize), value?))
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the value that was there before, if any.
+    pub fn insert(&mut self, pos: Pos, value: T) -> Option<T> {
+        self.cells.insert(pos, value)
+    }
+
+    pub fn remove(&mut self, pos: Pos) -> Option<T> {
+        self.cells.remove(&pos)
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.cells.keys().copied()
+    }
+
+    /// Every filled cell with its position, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.cells.iter().map(|(&pos, value)| (pos, value))
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(Pos, &mut T) -> bool) {
+        self.cells.retain(|&pos, value| f(pos, value));
+    }
+}
+
+impl<T> Grid<T> for SparseGrid<T> {
+    fn get(&self, pos: Pos) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    /// Worked out from every cell, so this is not free.
+    fn bounds(&self) -> Option<Bounds> {
+        Bounds::enclosing(self.positions())
+    }
+}
+
+impl<T> FromIterator<(Pos, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Pos, T)>>(iter: I) -> Self {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Extend<(Pos, T)> for SparseGrid<T> {
+    fn extend<I: IntoIterator<Item = (Pos, T)>>(&mut self, iter: I) {
+        self.cells.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bounds, DenseGrid, Grid, SparseGrid};
+    use crate::{point::Point2, AocError};
+
+    #[test]
+    fn test_dense() {
+        let grid = DenseGrid::parse("123\n456\n", |c| c.to_digit(10)).unwrap();
+        assert_eq!((grid.height(), grid.width()), (2, 3));
+        assert_eq!(grid[Point2::new(2, 1)], 6);
+        assert_eq!(grid.get(Point2::new(0, 2)), None);
+        assert_eq!(grid.get(Point2::new(-1, 0)), None);
+        let mut neighbours: Vec<_> = grid
+            .neighbours4(Point2::new(0, 0))
+            .map(|(_, &v)| v)
+            .collect();
+        neighbours.sort();
+        assert_eq!(neighbours, [2, 4]);
+        assert_eq!(grid.neighbours8(Point2::new(1, 1)).count(), 5);
+        assert_eq!(grid.get_wrapped(Point2::new(3, -1)), Some(&4));
+        let ray: Vec<_> = grid
+            .ray(Point2::new(0, 0), Point2::new(1, 0))
+            .map(|(_, &v)| v)
+            .collect();
+        assert_eq!(ray, [2, 3]);
+    }
+
+    #[test]
+    fn test_dense_errors() {
+        match DenseGrid::parse("12\n3x\n", |c| c.to_digit(10)) {
+            Err(AocError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 2)),
+            x => panic!("Expected a parse error, got {x:?}"),
+        }
+        match DenseGrid::parse("12\n345\n", |c| c.to_digit(10)) {
+            Err(AocError::Parse { line, .. }) => assert_eq!(line, 2),
+            x => panic!("Expected a parse error, got {x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_sparse() {
+        let grid = SparseGrid::parse("  #\n#.#\n", ' ', |c| (c == '#').then_some(()));
+        assert!(grid.is_err());
+        let grid = SparseGrid::parse("..#\n#.#\n", '.', |c| (c == '#').then_some(())).unwrap();
+        assert_eq!(grid.len(), 3);
+        assert_eq!(
+            grid.bounds(),
+            Some(Bounds {
+                min: Point2::new(0, 0),
+                max: Point2::new(2, 1)
+            })
+        );
+        assert_eq!(grid.neighbours8(Point2::new(1, 0)).count(), 3);
+        assert_eq!(SparseGrid::<()>::new().bounds(), None);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let bounds = Bounds {
+            min: Point2::new(1, 1),
+            max: Point2::new(4, 3),
+        };
+        assert_eq!(bounds.wrap(Point2::new(5, 0)), Point2::new(1, 3));
+        assert_eq!(bounds.wrap(Point2::new(0, 4)), Point2::new(4, 1));
+        assert_eq!(
+            bounds.shrink(1),
+            Some(Bounds {
+                min: Point2::new(2, 2),
+                max: Point2::new(3, 2)
+            })
+        );
+        assert_eq!(bounds.shrink(2), None);
+        assert_eq!(bounds.positions().count(), 12);
+    }
+}
@@ -0,0 +1,193 @@
+use std::{fmt::Display, io::BufRead, ops::Range, str::FromStr};
+
+use crate::AocError;
+
+/// Reads the whole of `input`, rather than silently stopping at the first
+/// I/O error.
+pub(crate) fn read_input(mut input: impl BufRead) -> Result<String, AocError> {
+    let mut buf = String::new();
+    input.read_to_string(&mut buf)?;
+    Ok(buf)
+}
+
+/// Calls `f` with each line of `input` as it is read, so the whole input
+/// never has to be held in memory. Lines are cleaned up as in [`lines`].
+pub(crate) fn try_for_each_line(
+    input: impl BufRead,
+    f: impl FnMut(Line<'_>) -> Result<(), AocError>,
+) -> Result<(), AocError> {
+    parse_lines(input, f).collect()
+}
+
+/// Parses each line of `input` with `parse` as it is read, so the whole
+/// input never has to be held in memory. Lines are cleaned up as in
+/// [`lines`].
+pub(crate) fn parse_lines<R: BufRead, T, F: FnMut(Line<'_>) -> Result<T, AocError>>(
+    input: R,
+    parse: F,
+) -> ParseLines<R, F> {
+    ParseLines {
+        input,
+        parse,
+        buf: String::new(),
+        number: 0,
+        blank: 0..0,
+        held: false,
+        failed: false,
+    }
+}
+
+pub(crate) struct ParseLines<R, F> {
+    input: R,
+    parse: F,
+    /// The same buffer is reused for every line.
+    buf: String,
+    number: usize,
+    /// Blank lines are held back until a line with something on it turns up,
+    /// so that any at the end are dropped.
+    blank: Range<usize>,
+    /// Whether `buf` holds a line waiting for the blank lines before it.
+    held: bool,
+    /// Set once reading fails, as trying again is unlikely to help.
+    failed: bool,
+}
+
+impl<R: BufRead, T, F: FnMut(Line<'_>) -> Result<T, AocError>> Iterator for ParseLines<R, F> {
+    type Item = Result<T, AocError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.held {
+                if let Some(number) = self.blank.next() {
+                    return Some((self.parse)(Line { number, text: "" }));
+                }
+                self.held = false;
+                return Some((self.parse)(Line {
+                    number: self.number,
+                    text: self.buf.trim_end(),
+                }));
+            }
+            if self.failed {
+                return None;
+            }
+            self.buf.clear();
+            match self.input.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.number += 1,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e.into()));
+                }
+            }
+            if !self.buf.trim_end().is_empty() {
+                self.held = true;
+            } else if self.blank.is_empty() {
+                self.blank = self.number..self.number + 1;
+            } else {
+                self.blank.end += 1;
+            }
+        }
+    }
+}
+
+/// A single line of puzzle input, remembered so that parse errors can say
+/// where they happened.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Line<'a> {
+    /// 1-indexed line number.
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// 1-indexed column of `part`, which should be a slice of this line.
+    fn column(&self, part: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let offset = (part.as_ptr() as usize).wrapping_sub(start);
+        if offset <= self.text.len() {
+            offset + 1
+        } else {
+            1
+        }
+    }
+
+    /// A parse error pointing at `part` of this line.
+    pub fn error(&self, part: &str, message: impl Into<String>) -> AocError {
+        AocError::parse(self.number, self.column(part), message)
+    }
+
+    pub fn parse<T>(&self, part: &str) -> Result<T, AocError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let trimmed = part.trim();
+        trimmed
+            .parse::<T>()
+            .map_err(|e| self.error(trimmed, format!("could not parse '{trimmed}': {e}")))
+    }
+
+    pub fn split_once(
+        &self,
+        part: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), AocError> {
+        part.split_once(delimiter)
+            .ok_or_else(|| self.error(part, format!("expected '{delimiter}' in '{part}'")))
+    }
+
+    /// Removes `prefix` from the start of `part`.
+    pub fn strip_prefix(&self, part: &'a str, prefix: &str) -> Result<&'a str, AocError> {
+        part.strip_prefix(prefix)
+            .ok_or_else(|| self.error(part, format!("expected '{prefix}'")))
+    }
+}
+
+/// The lines of `buf`, which may end in `\r\n`. Trailing whitespace is
+/// left off each line, and blank lines at the end are dropped.
+pub(crate) fn lines(buf: &str) -> impl Iterator<Item = Line<'_>> {
+    buf.trim_end().lines().enumerate().map(|(i, text)| Line {
+        number: i + 1,
+        text: text.trim_end(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lines, try_for_each_line};
+    use crate::AocError;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_error_position() {
+        let buf = "1,2\n3,x";
+        let line = lines(buf).nth(1).unwrap();
+        let (_, y) = line.split_once(line.text, ",").unwrap();
+        match line.parse::<usize>(y) {
+            Err(AocError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            x => panic!("Expected a parse error, got {x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_untidy_lines() {
+        let buf = "a \r\n\r\nb\t\r\n\r\n\r\n";
+        let expected = vec![(1, "a"), (2, ""), (3, "b")];
+        let read = lines(buf)
+            .map(|line| (line.number, line.text))
+            .collect::<Vec<_>>();
+        assert_eq!(read, expected);
+
+        let mut streamed = Vec::new();
+        try_for_each_line(Cursor::new(buf), |line| {
+            streamed.push((line.number, line.text.to_string()));
+            Ok(())
+        })
+        .unwrap();
+        let streamed = streamed
+            .iter()
+            .map(|(number, text)| (*number, text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(streamed, expected);
+    }
+}
@@ -1,484 +1,346 @@
-use std::{
-    fs,
-    io::{BufRead, BufReader},
-    path::{Path, PathBuf},
-};
-
-#[macro_use]
-extern crate lazy_static;
-
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
-
-type DayFn = fn(Box<dyn BufRead>) -> String;
-
-pub fn get_day(day: usize) -> (DayFn, DayFn, PathBuf) {
-    match day {
-        1 => {
-            use day01::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day01.txt"),
-            )
-        }
-
-        2 => {
-            use day02::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day02.txt"),
-            )
-        }
-
-        3 => {
-            use day03::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day03.txt"),
-            )
-        }
-
-        4 => {
-            use day04::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day04.txt"),
-            )
-        }
-
-        5 => {
-            use day05::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day05.txt"),
-            )
-        }
-
-        6 => {
-            use day06::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day06.txt"),
-            )
-        }
-
-        7 => {
-            use day07::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day07.txt"),
-            )
-        }
-
-        8 => {
-            use day08::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day08.txt"),
-            )
-        }
-
-        9 => {
-            use day09::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day09.txt"),
-            )
-        }
-
-        10 => {
-            use day10::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day10.txt"),
-            )
-        }
-
-        11 => {
-            use day11::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day11.txt"),
-            )
-        }
-
-        12 => {
-            use day12::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day12.txt"),
-            )
-        }
-
-        13 => {
-            use day13::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day13.txt"),
-            )
-        }
-
-        14 => {
-            use day14::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day14.txt"),
-            )
-        }
-
-        15 => {
-            use day15::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day15.txt"),
-            )
-        }
-
-        16 => {
-            use day16::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day16.txt"),
-            )
-        }
-
-        17 => {
-            use day17::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day17.txt"),
-            )
-        }
-
-        18 => {
-            use day18::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day18.txt"),
-            )
-        }
-
-        19 => {
-            use day19::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day19.txt"),
-            )
-        }
-
-        20 => {
-            use day20::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day20.txt"),
-            )
-        }
-
-        21 => {
-            use day21::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day21.txt"),
-            )
-        }
-
-        22 => {
-            use day22::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day22.txt"),
-            )
-        }
-
-        23 => {
-            use day23::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day23.txt"),
-            )
-        }
-
-        24 => {
-            use day24::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day24.txt"),
-            )
-        }
-
-        25 => {
-            use day25::{star_one, star_two};
-            (
-                star_one as DayFn,
-                star_two as DayFn,
-                Path::new("data").join("day25.txt"),
-            )
-        }
+//! Solutions to Advent of Code 2022.
+//!
+//! Every day has a module of its own, such as [`day21`], with a `parse`
+//! function that reads the puzzle input into that day's model, and
+//! `part_one` and `part_two` functions that solve it:
+//!
+//! ```
+//! use advent_of_code_2022::day21;
+//!
+//! let input = "root: left + right\nleft: 2\nright: 3\n";
+//! let equations = day21::parse(input.as_bytes()).unwrap();
+//! assert_eq!(day21::part_one(&equations).unwrap(), 5);
+//! ```
+//!
+//! [`get_day`] looks a day up by number instead, and gives its answers as
+//! an [`Answer`].
+
+use std::io::BufRead;
+
+mod answer;
+mod cancel;
+pub mod cycle;
+mod data;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+mod error;
+#[cfg(any(test, feature = "reference"))]
+pub mod generate;
+pub mod grid;
+mod input;
+mod manifest;
+mod measure;
+mod parse;
+pub mod point;
+pub mod search;
+
+/// Slow, obviously correct solutions to check the fast ones against.
+#[cfg(feature = "reference")]
+pub mod reference {
+    pub use crate::day15::reference as day15;
+    pub use crate::day17::reference as day17;
+    pub use crate::day18::reference as day18;
+}
 
-        x => {
-            unimplemented!("Have not implemented day {}", x);
-        }
+pub use answer::Answer;
+pub use cancel::{checkpoint, current_token, CancellationToken};
+pub use data::{
+    find_data_file, find_input, get_data, input_search_paths, Normalized, DATA_DIR_VAR,
+};
+pub use error::AocError;
+pub use manifest::{Manifest, MANIFEST_FILE};
+pub use measure::{measure, Measurement, TrackingAllocator};
+
+/// A single day's puzzle, solved one star at a time.
+pub trait Solution: Sync {
+    /// The day of December the puzzle was released on.
+    fn day(&self) -> usize;
+
+    /// The puzzle title, as shown on the Advent of Code site.
+    fn title(&self) -> &'static str;
+
+    fn part_one(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError>;
+
+    fn part_two(&self, input: Box<dyn BufRead>) -> Result<Answer, AocError>;
+
+    /// Reads the input without solving anything, checking everything the
+    /// solver relies on. Every problem found is reported, not just the first.
+    fn validate(&self, input: Box<dyn BufRead>) -> Result<(), AocError>;
+
+    /// Solves part `1` or `2`. A panic in the solver is returned as
+    /// [`AocError::Panicked`].
+    fn solve(&self, part: u8, input: Box<dyn BufRead>) -> Result<Answer, AocError> {
+        AocError::catch_panic(|| match part {
+            1 => self.part_one(input),
+            2 => self.part_two(input),
+            _ => Err(AocError::unsolvable(format!("There is no part {part}"))),
+        })
     }
 }
 
-pub fn get_days() -> impl Iterator<Item = usize> {
-    1..=24
+static SOLUTIONS: [&dyn Solution; 25] = [
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+    &day06::Day06,
+    &day07::Day07,
+    &day08::Day08,
+    &day09::Day09,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day16::Day16,
+    &day17::Day17,
+    &day18::Day18,
+    &day19::Day19,
+    &day20::Day20,
+    &day21::Day21,
+    &day22::Day22,
+    &day23::Day23,
+    &day24::Day24,
+    &day25::Day25,
+];
+
+/// Every implemented day, in calendar order.
+pub fn solutions() -> impl Iterator<Item = &'static dyn Solution> {
+    SOLUTIONS.iter().copied()
 }
 
-pub fn get_data(filepath: &Path) -> Box<dyn BufRead> {
-    let f = fs::File::open(filepath).unwrap();
-    let input = BufReader::new(f);
-    Box::new(input)
+pub fn get_day(day: usize) -> Option<&'static dyn Solution> {
+    solutions().find(|solution| solution.day() == day)
+}
+
+pub fn get_days() -> impl Iterator<Item = usize> {
+    solutions().map(|solution| solution.day())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Checks a part against the answer in `answers.toml`.
+    fn check(day: usize, part: u8) {
+        let manifest = Manifest::load(None).unwrap();
+        let expected = manifest
+            .expected(day, part)
+            .unwrap_or_else(|| panic!("No answer for day {day} part {part}"));
+        let solution = get_day(day).unwrap();
+        let input = get_data(&find_input(day).unwrap()).unwrap();
+        assert_eq!(&solution.solve(part, input).unwrap(), expected);
+    }
+
     #[test]
-    fn day01_complete() {
-        let (star_one, star_two, filepath) = get_day(1);
-        assert_eq!(star_one(get_data(&filepath)), "69528");
+    fn solve_catches_panics() {
+        struct Broken;
+        impl Solution for Broken {
+            fn day(&self) -> usize {
+                0
+            }
+            fn title(&self) -> &'static str {
+                "Broken"
+            }
+            fn part_one(&self, _: Box<dyn BufRead>) -> Result<Answer, AocError> {
+                panic!("part one is broken")
+            }
+            fn part_two(&self, _: Box<dyn BufRead>) -> Result<Answer, AocError> {
+                Ok(Answer::from(2_usize))
+            }
+            fn validate(&self, _: Box<dyn BufRead>) -> Result<(), AocError> {
+                Ok(())
+            }
+        }
+        match Broken.solve(1, Box::new(std::io::empty())) {
+            Err(AocError::Panicked(message)) => assert_eq!(message, "part one is broken"),
+            x => panic!("Expected the panic to be caught, got {x:?}"),
+        }
+        assert_eq!(
+            Broken.solve(2, Box::new(std::io::empty())).unwrap(),
+            Answer::Integer(2)
+        );
+    }
 
-        assert_eq!(star_two(get_data(&filepath)), "206152");
+    #[test]
+    fn registry_covers_every_day() {
+        assert_eq!(get_days().collect::<Vec<_>>(), (1..=25).collect::<Vec<_>>());
+        assert!(get_day(0).is_none());
+        assert!(get_day(26).is_none());
     }
 
     #[test]
-    fn day02_complete() {
-        let (star_one, star_two, filepath) = get_day(2);
-        assert_eq!(star_one(get_data(&filepath)), "12645");
+    fn day01_complete() {
+        check(1, 1);
+        check(1, 2);
+    }
 
-        assert_eq!(star_two(get_data(&filepath)), "11756");
+    #[test]
+    fn day02_complete() {
+        check(2, 1);
+        check(2, 2);
     }
 
     #[test]
     fn day03_complete() {
-        let (star_one, star_two, filepath) = get_day(3);
-        assert_eq!(star_one(get_data(&filepath)), "8298");
-
-        assert_eq!(star_two(get_data(&filepath)), "2708");
+        check(3, 1);
+        check(3, 2);
     }
 
     #[test]
     fn day04_complete() {
-        let (star_one, star_two, filepath) = get_day(4);
-        assert_eq!(star_one(get_data(&filepath)), "466");
-
-        assert_eq!(star_two(get_data(&filepath)), "865");
+        check(4, 1);
+        check(4, 2);
     }
 
     #[test]
     fn day05_complete() {
-        let (star_one, star_two, filepath) = get_day(5);
-        assert_eq!(star_one(get_data(&filepath)), "GFTNRBZPF");
-
-        assert_eq!(star_two(get_data(&filepath)), "VRQWPDSGP");
+        check(5, 1);
+        check(5, 2);
     }
 
     #[test]
     fn day06_complete() {
-        let (star_one, star_two, filepath) = get_day(6);
-        assert_eq!(star_one(get_data(&filepath)), "1140");
-
-        assert_eq!(star_two(get_data(&filepath)), "3495");
+        check(6, 1);
+        check(6, 2);
     }
 
     #[test]
     fn day07_complete() {
-        let (star_one, star_two, filepath) = get_day(7);
-        assert_eq!(star_one(get_data(&filepath)), "1084134");
-
-        assert_eq!(star_two(get_data(&filepath)), "6183184");
+        check(7, 1);
+        check(7, 2);
     }
 
     #[test]
     fn day08_complete() {
-        let (star_one, star_two, filepath) = get_day(8);
-        assert_eq!(star_one(get_data(&filepath)), "1827");
-
-        assert_eq!(star_two(get_data(&filepath)), "335580");
+        check(8, 1);
+        check(8, 2);
     }
 
     #[test]
     fn day09_complete() {
-        let (star_one, star_two, filepath) = get_day(9);
-        assert_eq!(star_one(get_data(&filepath)), "6486");
-
-        assert_eq!(star_two(get_data(&filepath)), "2678");
+        check(9, 1);
+        check(9, 2);
     }
 
     #[test]
     fn day10_complete() {
-        let (star_one, star_two, filepath) = get_day(10);
-        assert_eq!(star_one(get_data(&filepath)), "14620");
-
-        assert_eq!(star_two(get_data(&filepath)), "###....##.####.###..#..#.###..####.#..#.\n#..#....#.#....#..#.#..#.#..#.#....#..#.\n###.....#.###..#..#.####.#..#.###..#..#.\n#..#....#.#....###..#..#.###..#....#..#.\n#..#.#..#.#....#.#..#..#.#.#..#....#..#.\n###...##..#....#..#.#..#.#..#.#.....##..");
+        check(10, 1);
+        check(10, 2);
     }
 
     #[test]
     fn day11_complete() {
-        let (star_one, star_two, filepath) = get_day(11);
-        assert_eq!(star_one(get_data(&filepath)), "100345");
-
-        assert_eq!(star_two(get_data(&filepath)), "28537348205");
+        check(11, 1);
+        check(11, 2);
     }
 
     #[test]
     fn day12_complete() {
-        let (star_one, star_two, filepath) = get_day(12);
-        assert_eq!(star_one(get_data(&filepath)), "339");
-
-        assert_eq!(star_two(get_data(&filepath)), "332");
+        check(12, 1);
+        check(12, 2);
     }
 
     #[test]
     fn day13_complete() {
-        let (star_one, star_two, filepath) = get_day(13);
-        assert_eq!(star_one(get_data(&filepath)), "6046");
-
-        assert_eq!(star_two(get_data(&filepath)), "21423");
+        check(13, 1);
+        check(13, 2);
     }
 
     #[test]
     fn day14_complete() {
-        let (star_one, star_two, filepath) = get_day(14);
-        assert_eq!(star_one(get_data(&filepath)), "755");
-
-        assert_eq!(star_two(get_data(&filepath)), "29805");
+        check(14, 1);
+        check(14, 2);
     }
 
     #[test]
     fn day15_complete() {
-        let (star_one, star_two, filepath) = get_day(15);
-        assert_eq!(star_one(get_data(&filepath)), "5335787");
-
-        assert_eq!(star_two(get_data(&filepath)), "13673971349056");
+        check(15, 1);
+        check(15, 2);
     }
 
     #[test]
     fn day16_complete() {
-        let (star_one, _star_two, filepath) = get_day(16);
-        assert_eq!(star_one(get_data(&filepath)), "2080");
-        // FIXME: takes too long.
-        // assert_eq!(star_two(get_data(&filepath)), "2752");
+        check(16, 1);
+        check(16, 2);
     }
 
     #[test]
     fn day17_complete() {
-        let (star_one, star_two, filepath) = get_day(17);
-        assert_eq!(star_one(get_data(&filepath)), "3092");
-
-        assert_eq!(star_two(get_data(&filepath)), "1528323699442");
+        check(17, 1);
+        check(17, 2);
     }
 
     #[test]
     fn day18_complete() {
-        let (star_one, star_two, filepath) = get_day(18);
-        assert_eq!(star_one(get_data(&filepath)), "4364");
-
-        assert_eq!(star_two(get_data(&filepath)), "2508");
+        check(18, 1);
+        check(18, 2);
     }
 
     #[test]
     fn day19_complete() {
-        let (star_one, star_two, filepath) = get_day(19);
-        assert_eq!(star_one(get_data(&filepath)), "1081");
-
-        assert_eq!(star_two(get_data(&filepath)), "2415");
+        check(19, 1);
+        check(19, 2);
     }
 
     #[test]
     fn day20_complete() {
-        let (star_one, star_two, filepath) = get_day(20);
-        assert_eq!(star_one(get_data(&filepath)), "2827");
-
-        assert_eq!(star_two(get_data(&filepath)), "7834270093909");
+        check(20, 1);
+        check(20, 2);
     }
 
     #[test]
     fn day21_complete() {
-        let (star_one, star_two, filepath) = get_day(21);
-        assert_eq!(star_one(get_data(&filepath)), "194058098264286");
-
-        assert_eq!(star_two(get_data(&filepath)), "3592056845086");
+        check(21, 1);
+        check(21, 2);
     }
 
     #[test]
     fn day22_complete() {
-        let (star_one, star_two, filepath) = get_day(22);
-        assert_eq!(star_one(get_data(&filepath)), "27492");
-
-        assert_eq!(star_two(get_data(&filepath)), "78291");
+        check(22, 1);
+        check(22, 2);
     }
 
     #[test]
     fn day23_complete() {
-        let (star_one, star_two, filepath) = get_day(23);
-        assert_eq!(star_one(get_data(&filepath)), "4091");
-
-        assert_eq!(star_two(get_data(&filepath)), "1036");
+        check(23, 1);
+        check(23, 2);
     }
 
     #[test]
     fn day24_complete() {
-        let (star_one, star_two, filepath) = get_day(24);
-        assert_eq!(star_one(get_data(&filepath)), "264");
-
-        assert_eq!(star_two(get_data(&filepath)), "789");
+        check(24, 1);
+        check(24, 2);
     }
 
     #[test]
     fn day25_complete() {
-        let (star_one, star_two, filepath) = get_day(25);
-        assert_eq!(star_one(get_data(&filepath)), "2-212-2---=00-1--102");
-
-        assert_eq!(star_two(get_data(&filepath)), "Merry Xmas");
+        check(25, 1);
+        check(25, 2);
     }
 }
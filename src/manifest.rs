@@ -0,0 +1,163 @@
+use std::{collections::BTreeMap, fs, io, path::Path, str::FromStr};
+
+use toml::{Spanned, Value};
+
+use crate::{data::find_data_file, Answer, AocError};
+
+/// The manifest looked for alongside the puzzle inputs.
+pub const MANIFEST_FILE: &str = "answers.toml";
+
+/// Known answers, keyed by day and part. On disk this is TOML with a table
+/// per day:
+///
+/// ```toml
+/// [day01]
+/// part1 = 69528
+/// part2 = 206152
+/// ```
+///
+/// Text and grid answers are written as strings, and stay text even when
+/// they look like numbers, as a SNAFU answer can. Integers too large for
+/// TOML are written as strings too.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    answers: BTreeMap<(usize, u8), Answer>,
+}
+
+impl Manifest {
+    /// Reads the manifest at `path`, or finds `answers.toml` in the usual
+    /// data directories if there is no path.
+    pub fn load(path: Option<&Path>) -> Result<Manifest, AocError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => find_data_file(MANIFEST_FILE)?,
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => text.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(AocError::MissingFile {
+                searched: vec![path],
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn expected(&self, day: usize, part: u8) -> Option<&Answer> {
+        self.answers.get(&(day, part))
+    }
+}
+
+/// 1-indexed line and column of the byte at `offset`.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+impl FromStr for Manifest {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |offset: usize, message: String| {
+            let (line, column) = position(s, offset);
+            AocError::parse(line, column, message)
+        };
+
+        let tables: BTreeMap<Spanned<String>, BTreeMap<Spanned<String>, Spanned<Value>>> =
+            toml::from_str(s).map_err(|e| {
+                let offset = e.span().map(|span| span.start).unwrap_or(0);
+                error(offset, e.message().to_string())
+            })?;
+
+        let mut answers = BTreeMap::new();
+        for (day_key, parts) in tables {
+            let day = day_key
+                .get_ref()
+                .strip_prefix("day")
+                .and_then(|day| day.parse::<usize>().ok())
+                .ok_or_else(|| {
+                    error(
+                        day_key.span().start,
+                        format!("expected a table such as [day01], found [{day_key}]"),
+                    )
+                })?;
+            for (part_key, value) in parts {
+                let part = match part_key.get_ref().as_str() {
+                    "part1" => 1,
+                    "part2" => 2,
+                    x => {
+                        return Err(error(
+                            part_key.span().start,
+                            format!("expected part1 or part2, found {x}"),
+                        ))
+                    }
+                };
+                let answer = match value.get_ref() {
+                    Value::Integer(x) => Answer::Integer(*x),
+                    Value::String(s) => match s.parse::<i128>() {
+                        Ok(x) if i64::try_from(x).is_err() => Answer::BigInteger(x),
+                        _ => Answer::from(s.as_str()),
+                    },
+                    _ => {
+                        return Err(error(
+                            value.span().start,
+                            "answers must be integers or strings".to_string(),
+                        ))
+                    }
+                };
+                answers.insert((day, part), answer);
+            }
+        }
+        Ok(Manifest { answers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Manifest;
+    use crate::{Answer, AocError};
+
+    #[test]
+    fn test_parse() {
+        let manifest =
+            "[day10]\npart1 = 14620\npart2 = '''\n#..#\n.##.\n'''\n[day25]\npart1 = \"2=-1=0\"\n"
+                .parse::<Manifest>()
+                .unwrap();
+        assert_eq!(manifest.expected(10, 1), Some(&Answer::Integer(14620)));
+        assert_eq!(manifest.expected(10, 2), Some(&Answer::from("#..#\n.##.")));
+        assert_eq!(manifest.expected(25, 1), Some(&Answer::from("2=-1=0")));
+        assert_eq!(manifest.expected(25, 2), None);
+    }
+
+    #[test]
+    fn test_numeric_strings() {
+        let manifest = "[day25]\npart1 = \"1\"\n[day26]\npart1 = \"170141183460469231731687303715884105727\"\n"
+            .parse::<Manifest>()
+            .unwrap();
+        // A SNAFU number that happens to be all digits is still text.
+        assert_eq!(
+            manifest.expected(25, 1),
+            Some(&Answer::Text("1".to_string()))
+        );
+        assert_eq!(
+            manifest.expected(26, 1),
+            Some(&Answer::BigInteger(i128::MAX))
+        );
+    }
+
+    #[test]
+    fn test_bad_value() {
+        match "[day01]\npart1 = 1.5\n".parse::<Manifest>() {
+            Err(AocError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 9)),
+            x => panic!("Expected a parse error, got {x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_bad_part() {
+        match "[day01]\npart3 = 1\n".parse::<Manifest>() {
+            Err(AocError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 1)),
+            x => panic!("Expected a parse error, got {x:?}"),
+        }
+    }
+}
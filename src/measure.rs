@@ -0,0 +1,80 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator to keep track of how much heap is in use. It
+/// only has an effect once installed with `#[global_allocator]`, which is left
+/// to the binary.
+pub struct TrackingAllocator;
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn record_alloc(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+/// How long something took and how much extra heap it needed at most.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Measurement {
+    pub duration: Duration,
+    /// Zero unless [`TrackingAllocator`] is installed. The count is for the
+    /// whole heap, so it is only the measured work's own when nothing else
+    /// allocates meanwhile.
+    pub peak_heap: usize,
+}
+
+/// Runs `f`, timing it and recording the peak heap use above what was
+/// already allocated beforehand. Work on other threads, including other calls
+/// to `measure`, must wait until it returns, or its allocations are counted in
+/// the peak and it resets the peak part way through.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Measurement) {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let duration = start.elapsed();
+    let peak_heap = PEAK.load(Ordering::Relaxed).saturating_sub(baseline);
+    (
+        result,
+        Measurement {
+            duration,
+            peak_heap,
+        },
+    )
+}
@@ -0,0 +1,188 @@
+//! Building blocks for reading puzzle input. Line-level structure (blocks
+//! and character grids) is split out here, and the text of each line is read
+//! with small [`nom`] parsers so that every day reports mistakes the same
+//! way, with the line and column they were found at.
+
+use std::str::FromStr;
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{char, digit1, one_of, space0},
+    combinator::{all_consuming, map_res, opt, recognize},
+    error::ErrorKind,
+    multi::separated_list1,
+    sequence::preceded,
+    IResult, Parser,
+};
+
+use crate::{
+    input::{lines, Line},
+    AocError,
+};
+
+/// The error every parser here fails with.
+pub(crate) type Error<'a> = nom::error::Error<&'a str>;
+
+/// Runs `parser` over the whole of `line`.
+pub(crate) fn parse_line<'a, O>(
+    line: &Line<'a>,
+    parser: impl Parser<&'a str, Output = O, Error = Error<'a>>,
+) -> Result<O, AocError> {
+    parse_part(line, line.text, parser)
+}
+
+/// Runs `parser` over the whole of `part`, which should be a slice of
+/// `line`. A failure becomes a parse error pointing at where the parser got
+/// stuck.
+pub(crate) fn parse_part<'a, O>(
+    line: &Line<'a>,
+    part: &'a str,
+    parser: impl Parser<&'a str, Output = O, Error = Error<'a>>,
+) -> Result<O, AocError> {
+    match all_consuming(parser).parse(part) {
+        Ok((_, output)) => Ok(output),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            let message = match e.code {
+                ErrorKind::Digit => "Expected a number",
+                ErrorKind::MapRes => "Number out of range",
+                ErrorKind::Eof => "Unexpected input after the end",
+                _ => "Unexpected input",
+            };
+            Err(line.error(e.input, format!("{message}: '{}'", e.input)))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(line.error(part, "Incomplete input")),
+    }
+}
+
+/// A whole number, such as `12`.
+pub(crate) fn unsigned<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(digit1, str::parse).parse(input)
+}
+
+/// A number with an optional sign, such as `-12` or `+3`.
+pub(crate) fn signed<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(recognize((opt(one_of("+-")), digit1)), str::parse).parse(input)
+}
+
+/// One or more items separated by commas, with or without spaces after them.
+pub(crate) fn comma_list<'a, O>(
+    item: impl Parser<&'a str, Output = O, Error = Error<'a>>,
+) -> impl Parser<&'a str, Output = Vec<O>, Error = Error<'a>> {
+    separated_list1((char(','), space0), item)
+}
+
+/// A `key: value` line, which may be indented, with the value read by
+/// `value`.
+pub(crate) fn field<'a, O>(
+    key: &'a str,
+    value: impl Parser<&'a str, Output = O, Error = Error<'a>>,
+) -> impl Parser<&'a str, Output = O, Error = Error<'a>> {
+    preceded((space0, tag(key), char(':'), space0), value)
+}
+
+/// Groups the lines of `buf` into blocks separated by blank lines.
+pub(crate) fn blocks(buf: &str) -> Vec<Vec<Line<'_>>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+    for line in lines(buf) {
+        if line.text.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+/// Calls `tile` on every character of a character map. Anything it returns
+/// `None` for is reported as a parse error, all of them at once.
+pub(crate) fn char_grid<'a, T>(
+    lines: impl IntoIterator<Item = Line<'a>>,
+    mut tile: impl FnMut(char) -> Option<T>,
+) -> Result<Vec<(Line<'a>, Vec<T>)>, AocError> {
+    AocError::collect(lines.into_iter().map(|line| {
+        let row = AocError::collect(line.text.char_indices().map(|(i, c)| {
+            tile(c).ok_or_else(|| line.error(&line.text[i..], format!("Unexpected tile: {c}")))
+        }))?;
+        Ok((line, row))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blocks, char_grid, comma_list, field, parse_line, signed, unsigned};
+    use crate::{input::lines, AocError};
+    use nom::{bytes::complete::tag, sequence::preceded};
+
+    fn position<T: std::fmt::Debug>(result: Result<T, AocError>) -> (usize, usize) {
+        match result {
+            Err(AocError::Parse { line, column, .. }) => (line, column),
+            x => panic!("Expected a parse error, got {x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_numbers() {
+        let buf = "-12,+3, 4\nx=7\nx=-";
+        let mut lines = lines(buf);
+        let line = lines.next().unwrap();
+        assert_eq!(
+            parse_line(&line, comma_list(signed::<i32>)).unwrap(),
+            vec![-12, 3, 4]
+        );
+        assert_eq!(
+            position(parse_line(&line, comma_list(unsigned::<u32>))),
+            (1, 1)
+        );
+
+        let line = lines.next().unwrap();
+        assert_eq!(
+            parse_line(&line, preceded(tag("x="), unsigned::<u8>)).unwrap(),
+            7
+        );
+        let line = lines.next().unwrap();
+        assert_eq!(
+            position(parse_line(&line, preceded(tag("x="), signed::<i8>))),
+            (3, 4)
+        );
+    }
+
+    #[test]
+    fn test_field() {
+        let buf = "  Starting items: 79, 98\n  Test: divisible by 23 or so";
+        let mut lines = lines(buf);
+        let line = lines.next().unwrap();
+        assert_eq!(
+            parse_line(&line, field("Starting items", comma_list(unsigned::<u32>))).unwrap(),
+            vec![79, 98]
+        );
+        let line = lines.next().unwrap();
+        assert_eq!(
+            position(parse_line(
+                &line,
+                field("Test", preceded(tag("divisible by "), unsigned::<u32>))
+            )),
+            (2, 24)
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks = blocks("a\nb\n\nc\n");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1][0].number, 4);
+    }
+
+    #[test]
+    fn test_char_grid() {
+        let tile = |c: char| c.to_digit(10);
+        let rows = char_grid(lines("12\n34"), tile).unwrap();
+        assert_eq!(rows[1].1, vec![3, 4]);
+        assert_eq!(position(char_grid(lines("12\n3x"), tile)), (2, 2));
+    }
+}
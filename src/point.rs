@@ -0,0 +1,349 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+/// A point or offset on a plane. On grids `y` is the row and grows downwards,
+/// so [`Direction::Up`] takes one off `y`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point2 {
+    pub const ZERO: Point2 = Point2::new(0, 0);
+
+    pub const fn new(x: isize, y: isize) -> Point2 {
+        Point2 { x, y }
+    }
+
+    /// The taxicab distance, moving only along the axes.
+    pub fn manhattan(self, other: Point2) -> isize {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// The distance when diagonal moves are allowed too.
+    pub fn chebyshev(self, other: Point2) -> isize {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    /// Each coordinate clamped to -1, 0 or 1, so a step towards `self` along
+    /// both axes at once.
+    pub fn signum(self) -> Point2 {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+
+    /// A quarter turn anticlockwise about the origin, as seen on a grid.
+    pub fn rotate_left(self) -> Point2 {
+        Point2::new(self.y, -self.x)
+    }
+
+    /// A quarter turn clockwise about the origin, as seen on a grid.
+    pub fn rotate_right(self) -> Point2 {
+        Point2::new(-self.y, self.x)
+    }
+}
+
+impl Display for Point2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl Add for Point2 {
+    type Output = Point2;
+
+    fn add(self, rhs: Point2) -> Point2 {
+        Point2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Point2;
+
+    fn sub(self, rhs: Point2) -> Point2 {
+        Point2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl AddAssign for Point2 {
+    fn add_assign(&mut self, rhs: Point2) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point2 {
+    fn sub_assign(&mut self, rhs: Point2) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<isize> for Point2 {
+    type Output = Point2;
+
+    fn mul(self, rhs: isize) -> Point2 {
+        Point2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point2 {
+    type Output = Point2;
+
+    fn neg(self) -> Point2 {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+impl Add<Direction> for Point2 {
+    type Output = Point2;
+
+    fn add(self, rhs: Direction) -> Point2 {
+        self + rhs.delta()
+    }
+}
+
+impl AddAssign<Direction> for Point2 {
+    fn add_assign(&mut self, rhs: Direction) {
+        *self = *self + rhs;
+    }
+}
+
+/// A point or offset in space.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Point3 {
+    pub const ZERO: Point3 = Point3::new(0, 0, 0);
+
+    /// One step along each axis in each direction.
+    pub const NEIGHBOURS: [Point3; 6] = [
+        Point3::new(1, 0, 0),
+        Point3::new(-1, 0, 0),
+        Point3::new(0, 1, 0),
+        Point3::new(0, -1, 0),
+        Point3::new(0, 0, 1),
+        Point3::new(0, 0, -1),
+    ];
+
+    pub const fn new(x: isize, y: isize, z: isize) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    pub fn manhattan(self, other: Point3) -> isize {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+
+    pub fn chebyshev(self, other: Point3) -> isize {
+        (self.x - other.x)
+            .abs()
+            .max((self.y - other.y).abs())
+            .max((self.z - other.z).abs())
+    }
+
+    /// The six points sharing a face with this one.
+    pub fn neighbours(self) -> impl Iterator<Item = Point3> {
+        Point3::NEIGHBOURS
+            .into_iter()
+            .map(move |delta| self + delta)
+    }
+
+    /// The smaller of each coordinate.
+    pub fn min(self, other: Point3) -> Point3 {
+        Point3::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    /// The larger of each coordinate.
+    pub fn max(self, other: Point3) -> Point3 {
+        Point3::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+
+    /// A quarter turn about the x axis, taking y towards z.
+    pub fn rotate_x(self) -> Point3 {
+        Point3::new(self.x, -self.z, self.y)
+    }
+
+    /// A quarter turn about the y axis, taking z towards x.
+    pub fn rotate_y(self) -> Point3 {
+        Point3::new(self.z, self.y, -self.x)
+    }
+
+    /// A quarter turn about the z axis, taking x towards y.
+    pub fn rotate_z(self) -> Point3 {
+        Point3::new(-self.y, self.x, self.z)
+    }
+}
+
+impl Display for Point3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, rhs: Point3) -> Point3 {
+        Point3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, rhs: Point3) -> Point3 {
+        Point3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl AddAssign for Point3 {
+    fn add_assign(&mut self, rhs: Point3) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point3 {
+    fn sub_assign(&mut self, rhs: Point3) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<isize> for Point3 {
+    type Output = Point3;
+
+    fn mul(self, rhs: isize) -> Point3 {
+        Point3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+
+    fn neg(self) -> Point3 {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// One of the four ways to face on a grid, in clockwise order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// One step this way.
+    pub const fn delta(self) -> Point2 {
+        match self {
+            Direction::Up => Point2::new(0, -1),
+            Direction::Right => Point2::new(1, 0),
+            Direction::Down => Point2::new(0, 1),
+            Direction::Left => Point2::new(-1, 0),
+        }
+    }
+
+    pub fn turn_left(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Right => Direction::Up,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// The opposite direction.
+    pub fn inverse(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+}
+
+/// Drawn as an arrow, as the puzzles do.
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arrow = match self {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        };
+        write!(f, "{arrow}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Point2, Point3};
+
+    #[test]
+    fn test_point2() {
+        let a = Point2::new(1, -2);
+        let b = Point2::new(-3, 4);
+        assert_eq!(a + b, Point2::new(-2, 2));
+        assert_eq!(a - b, Point2::new(4, -6));
+        assert_eq!(-a * 2, Point2::new(-2, 4));
+        assert_eq!(a.manhattan(b), 10);
+        assert_eq!(a.chebyshev(b), 6);
+        assert_eq!((b - a).signum(), Point2::new(-1, 1));
+        assert_eq!(a.rotate_left().rotate_right(), a);
+        assert_eq!(a.rotate_left().rotate_left(), -a);
+    }
+
+    #[test]
+    fn test_direction() {
+        for dir in Direction::ALL {
+            assert_eq!(dir.turn_left().turn_right(), dir);
+            assert_eq!(dir.turn_right().turn_right(), dir.inverse());
+            assert_eq!(dir.delta() + dir.inverse().delta(), Point2::ZERO);
+            assert_eq!(dir.delta().rotate_right(), dir.turn_right().delta());
+            assert_eq!(dir.delta().rotate_left(), dir.turn_left().delta());
+        }
+        assert_eq!(Point2::ZERO + Direction::Up, Point2::new(0, -1));
+    }
+
+    #[test]
+    fn test_point3() {
+        let a = Point3::new(1, 2, 3);
+        assert_eq!(a.manhattan(Point3::ZERO), 6);
+        assert_eq!(a.chebyshev(-a), 6);
+        assert_eq!(a.neighbours().count(), 6);
+        assert!(a.neighbours().all(|n| n.manhattan(a) == 1));
+        assert_eq!(a.rotate_x().rotate_x(), Point3::new(1, -2, -3));
+        assert_eq!(a.rotate_y().rotate_y().rotate_y().rotate_y(), a);
+        assert_eq!(a.rotate_z(), Point3::new(-2, 1, 3));
+    }
+}
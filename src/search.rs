@@ -0,0 +1,401 @@
+use crate::{checkpoint, AocError};
+use std::{
+    collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+/// Each node reached so far, with its cost and the node it was reached from.
+/// Starts have no predecessor.
+type Visited<N, C> = HashMap<N, (C, Option<N>)>;
+
+/// A search over a graph that is only known through a successor function.
+/// It sets off from one or more starts, and can be told to give up after
+/// visiting a number of states.
+#[derive(Debug, Clone)]
+pub struct Search<N> {
+    starts: Vec<N>,
+    budget: Option<usize>,
+}
+
+/// The cheapest route found to a goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N, C> {
+    pub cost: C,
+    /// Every node along the way, from the start it set off from to the goal.
+    pub nodes: Vec<N>,
+}
+
+impl<N, C> Path<N, C> {
+    pub fn start(&self) -> &N {
+        &self.nodes[0]
+    }
+
+    pub fn goal(&self) -> &N {
+        &self.nodes[self.nodes.len() - 1]
+    }
+}
+
+/// Every node a breadth-first search reached, and how far it was from the
+/// nearest start.
+#[derive(Debug, Clone)]
+pub struct Reached<N> {
+    visited: Visited<N, usize>,
+}
+
+impl<N: Clone + Eq + Hash> Reached<N> {
+    /// The fewest steps to `node`, if it could be reached at all.
+    pub fn distance(&self, node: &N) -> Option<usize> {
+        self.visited.get(node).map(|(steps, _)| *steps)
+    }
+
+    /// One of the shortest routes to `node`, starting with the start it came
+    /// from.
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        self.visited
+            .contains_key(node)
+            .then(|| reconstruct(&self.visited, node.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.visited.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visited.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&N, usize)> {
+        self.visited.iter().map(|(node, (steps, _))| (node, *steps))
+    }
+}
+
+/// Walks the predecessors back from `goal` to the start it came from.
+fn reconstruct<N: Clone + Eq + Hash, C>(visited: &Visited<N, C>, goal: N) -> Vec<N> {
+    let mut nodes = vec![goal];
+    while let Some((_, Some(previous))) = visited.get(&nodes[nodes.len() - 1]) {
+        nodes.push(previous.clone());
+    }
+    nodes.reverse();
+    nodes
+}
+
+/// A node waiting in the A* queue. The heap is a max-heap, so the ordering is
+/// reversed to pop the lowest estimate first, preferring nodes that are
+/// further along when estimates tie.
+struct Queued<N, C> {
+    estimate: C,
+    cost: C,
+    node: N,
+}
+
+impl<N, C: Ord> PartialEq for Queued<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<N, C: Ord> Eq for Queued<N, C> {}
+
+impl<N, C: Ord> PartialOrd for Queued<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N, C: Ord> Ord for Queued<N, C> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl<N: Clone + Eq + Hash> Search<N> {
+    pub fn new(start: N) -> Search<N> {
+        Search::from_starts([start])
+    }
+
+    /// A search that sets off from all of `starts` at once, so it finds the
+    /// nearest goal to any of them.
+    pub fn from_starts(starts: impl IntoIterator<Item = N>) -> Search<N> {
+        Search {
+            starts: starts.into_iter().collect(),
+            budget: None,
+        }
+    }
+
+    /// Gives up with an error after expanding more than `states` nodes.
+    pub fn budget(mut self, states: usize) -> Search<N> {
+        self.budget = Some(states);
+        self
+    }
+
+    /// Counts one more expanded node against the budget, and lets the search
+    /// be cancelled.
+    fn expand(&self, expanded: &mut usize) -> Result<(), AocError> {
+        checkpoint()?;
+        *expanded += 1;
+        match self.budget {
+            Some(budget) if *expanded > budget => Err(AocError::unsolvable(format!(
+                "Gave up after visiting {budget} states"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn breadth_first<I>(
+        &self,
+        mut successors: impl FnMut(&N) -> I,
+        mut is_goal: impl FnMut(&N) -> bool,
+    ) -> Result<(Visited<N, usize>, Option<N>), AocError>
+    where
+        I: IntoIterator<Item = N>,
+    {
+        let mut visited = HashMap::new();
+        let mut queue = VecDeque::new();
+        for start in &self.starts {
+            if visited.insert(start.clone(), (0, None)).is_none() {
+                queue.push_back((start.clone(), 0));
+            }
+        }
+
+        let mut expanded = 0;
+        while let Some((node, steps)) = queue.pop_front() {
+            if is_goal(&node) {
+                return Ok((visited, Some(node)));
+            }
+            self.expand(&mut expanded)?;
+            for next in successors(&node) {
+                if let Entry::Vacant(entry) = visited.entry(next.clone()) {
+                    entry.insert((steps + 1, Some(node.clone())));
+                    queue.push_back((next, steps + 1));
+                }
+            }
+        }
+        Ok((visited, None))
+    }
+
+    /// The fewest steps to a goal, where every step costs the same.
+    pub fn bfs<I>(
+        &self,
+        successors: impl FnMut(&N) -> I,
+        is_goal: impl FnMut(&N) -> bool,
+    ) -> Result<Option<Path<N, usize>>, AocError>
+    where
+        I: IntoIterator<Item = N>,
+    {
+        let (visited, goal) = self.breadth_first(successors, is_goal)?;
+        Ok(goal.map(|goal| Path {
+            cost: visited[&goal].0,
+            nodes: reconstruct(&visited, goal),
+        }))
+    }
+
+    /// The fewest steps to every node that can be reached.
+    pub fn bfs_all<I>(&self, successors: impl FnMut(&N) -> I) -> Result<Reached<N>, AocError>
+    where
+        I: IntoIterator<Item = N>,
+    {
+        let (visited, _) = self.breadth_first(successors, |_| false)?;
+        Ok(Reached { visited })
+    }
+
+    /// The cheapest route to a goal, where `successors` gives each neighbour
+    /// along with the cost of stepping to it.
+    pub fn dijkstra<C, I>(
+        &self,
+        successors: impl FnMut(&N) -> I,
+        is_goal: impl FnMut(&N) -> bool,
+    ) -> Result<Option<Path<N, C>>, AocError>
+    where
+        C: Copy + Ord + Default + Add<Output = C>,
+        I: IntoIterator<Item = (N, C)>,
+    {
+        self.astar(successors, |_| C::default(), is_goal)
+    }
+
+    /// Like [`Search::dijkstra`], but looks first where `heuristic` thinks a
+    /// goal is closest. The heuristic must never overestimate the cost left,
+    /// or the route found may not be the cheapest.
+    pub fn astar<C, I>(
+        &self,
+        mut successors: impl FnMut(&N) -> I,
+        mut heuristic: impl FnMut(&N) -> C,
+        mut is_goal: impl FnMut(&N) -> bool,
+    ) -> Result<Option<Path<N, C>>, AocError>
+    where
+        C: Copy + Ord + Default + Add<Output = C>,
+        I: IntoIterator<Item = (N, C)>,
+    {
+        let mut visited: Visited<N, C> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for start in &self.starts {
+            if visited
+                .insert(start.clone(), (C::default(), None))
+                .is_none()
+            {
+                queue.push(Queued {
+                    estimate: heuristic(start),
+                    cost: C::default(),
+                    node: start.clone(),
+                });
+            }
+        }
+
+        let mut expanded = 0;
+        while let Some(Queued { cost, node, .. }) = queue.pop() {
+            // A cheaper way here was found after this one was queued.
+            if visited[&node].0 < cost {
+                continue;
+            }
+            if is_goal(&node) {
+                return Ok(Some(Path {
+                    cost,
+                    nodes: reconstruct(&visited, node),
+                }));
+            }
+            self.expand(&mut expanded)?;
+            for (next, step) in successors(&node) {
+                let next_cost = cost + step;
+                if visited.get(&next).is_none_or(|(best, _)| next_cost < *best) {
+                    visited.insert(next.clone(), (next_cost, Some(node.clone())));
+                    queue.push(Queued {
+                        estimate: next_cost + heuristic(&next),
+                        cost: next_cost,
+                        node: next,
+                    });
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Search;
+    use crate::{
+        grid::{DenseGrid, Grid, Pos},
+        point::Point2,
+        AocError,
+    };
+
+    const MAZE: &str = "\
+.....#...
+.###.#.#.
+.#...#.#.
+.#.###.#.
+.......#.";
+
+    fn maze() -> DenseGrid<bool> {
+        DenseGrid::parse(MAZE, |c| match c {
+            '.' => Some(true),
+            '#' => Some(false),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let maze = maze();
+        let successors = |&pos: &Pos| {
+            maze.neighbours4(pos)
+                .filter(|(_, open)| **open)
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>()
+        };
+        let goal = Point2::new(8, 4);
+        let path = Search::new(Point2::ZERO)
+            .bfs(successors, |&pos| pos == goal)
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.cost, 20);
+        assert_eq!(path.nodes.len(), 21);
+        assert_eq!((*path.start(), *path.goal()), (Point2::ZERO, goal));
+        assert!(path
+            .nodes
+            .windows(2)
+            .all(|step| step[0].manhattan(step[1]) == 1 && maze[step[1]]));
+
+        // Starting nearer takes fewer steps.
+        let path = Search::from_starts([Point2::ZERO, Point2::new(6, 4)])
+            .bfs(successors, |&pos| pos == goal)
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.cost, 10);
+        assert_eq!(*path.start(), Point2::new(6, 4));
+
+        let reached = Search::new(Point2::ZERO).bfs_all(successors).unwrap();
+        assert_eq!(
+            reached.len(),
+            maze.iter().filter(|(_, open)| **open).count()
+        );
+        assert_eq!(reached.distance(&goal), Some(20));
+        assert_eq!(reached.path_to(&goal).unwrap(), {
+            let path = Search::new(Point2::ZERO)
+                .bfs(successors, |&pos| pos == goal)
+                .unwrap()
+                .unwrap();
+            path.nodes
+        });
+        assert_eq!(reached.distance(&Point2::new(5, 0)), None);
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        // Going the long way round is cheaper than the direct edge.
+        let edges = |&node: &u8| match node {
+            0 => vec![(1, 10), (2, 1)],
+            1 => vec![(3, 1)],
+            2 => vec![(1, 2), (3, 20)],
+            _ => vec![],
+        };
+        let path = Search::new(0)
+            .dijkstra(edges, |&n| n == 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.nodes, vec![0, 2, 1, 3]);
+
+        assert_eq!(Search::new(3).dijkstra(edges, |&n| n == 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_astar() {
+        let maze = maze();
+        let goal = Point2::new(8, 4);
+        let successors = |&pos: &Pos| {
+            maze.neighbours4(pos)
+                .filter(|(_, open)| **open)
+                .map(|(pos, _)| (pos, 1))
+                .collect::<Vec<_>>()
+        };
+        let astar = Search::new(Point2::ZERO)
+            .astar(successors, |pos| pos.manhattan(goal), |&pos| pos == goal)
+            .unwrap()
+            .unwrap();
+        let dijkstra = Search::new(Point2::ZERO)
+            .dijkstra(successors, |&pos| pos == goal)
+            .unwrap()
+            .unwrap();
+        assert_eq!(astar.cost, dijkstra.cost);
+        assert_eq!(astar.cost, 20);
+    }
+
+    #[test]
+    fn test_budget() {
+        let successors = |&n: &u32| [n + 1];
+        let path = Search::new(0)
+            .budget(100)
+            .bfs(successors, |&n| n == 50)
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.cost, 50);
+
+        let result = Search::new(0).budget(100).bfs(successors, |&n| n == 500);
+        assert!(matches!(result, Err(AocError::Unsolvable(_))));
+    }
+}
@@ -0,0 +1,141 @@
+//! Days whose puzzles only need one line at a time should read their input
+//! as it comes, so that even very large inputs fit in a little memory. This
+//! is its own test binary so the allocator can be swapped out for one that
+//! counts.
+
+use advent_of_code_2022::{get_day, measure, Normalized, TrackingAllocator};
+use std::{
+    env, fs,
+    io::{self, BufReader, Read},
+    process::{self, Command},
+};
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Far more than any of the days should need, but far less than the input.
+const LIMIT: usize = 16 * 1024;
+
+/// An input made of `pattern` over and over, made up as it is read rather
+/// than held in memory.
+struct Repeated {
+    pattern: &'static [u8],
+    pos: usize,
+    left: usize,
+}
+
+impl Repeated {
+    /// At least `size` bytes of `pattern`, which should end in a newline.
+    fn new(pattern: &'static str, size: usize) -> Repeated {
+        Repeated {
+            pattern: pattern.as_bytes(),
+            pos: 0,
+            left: size.div_ceil(pattern.len()),
+        }
+    }
+}
+
+impl Read for Repeated {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if self.left == 0 {
+            return Ok(0);
+        }
+        let rest = &self.pattern[self.pos..];
+        let n = rest.len().min(out.len());
+        out[..n].copy_from_slice(&rest[..n]);
+        self.pos += n;
+        if self.pos == self.pattern.len() {
+            self.pos = 0;
+            self.left -= 1;
+        }
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_line_local_days_use_bounded_memory() {
+    let size = 2 * 1024 * 1024;
+    let cases: [(usize, &[u8], &str); 7] = [
+        (1, &[1, 2], "1000\n2000\n3000\n\n4000\n\n"),
+        (2, &[1, 2], "A Y\nB X\nC Z\n"),
+        (
+            3,
+            &[1, 2],
+            "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\nPmmdzqPrVvPwwTWBwg\n",
+        ),
+        (
+            4,
+            &[1, 2],
+            "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n",
+        ),
+        (
+            9,
+            &[1, 2],
+            // Back where it started, so the tail only ever visits a few places.
+            "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\nU 3\nL 1\nD 5\nL 1\n",
+        ),
+        (10, &[1, 2], "addx 15\naddx -11\nnoop\naddx 6\n"),
+        (
+            25,
+            &[1, 2],
+            "1=-0-2\n12111\n2=0=\n21\n2=01\n111\n20012\n112\n1=-1=\n1-12\n12\n1=\n122\n",
+        ),
+    ];
+    for (day, parts, pattern) in cases {
+        let solution = get_day(day).unwrap();
+        for &part in parts {
+            let input = Box::new(Normalized::new(BufReader::new(Repeated::new(
+                pattern, size,
+            ))));
+            let (answer, measurement) = measure(|| solution.solve(part, input));
+            answer.unwrap();
+            assert!(
+                measurement.peak_heap < LIMIT,
+                "day {day} part {part} needed {} bytes for a {size} byte input",
+                measurement.peak_heap
+            );
+        }
+    }
+}
+
+/// The command line reads input files as it goes too, rather than loading
+/// them before solving, as the peak heap it reports shows.
+#[test]
+fn test_cli_streams_input_files() {
+    let size = 2 * 1024 * 1024;
+    let path = env::temp_dir().join(format!("aoc-memory-{}.txt", process::id()));
+    let mut file = fs::File::create(&path).unwrap();
+    io::copy(
+        &mut Repeated::new("1000\n2000\n3000\n\n4000\n\n", size),
+        &mut file,
+    )
+    .unwrap();
+    drop(file);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_main"))
+        .args([
+            "run",
+            "1",
+            "--stats",
+            "--format",
+            "json",
+            "--threads",
+            "1",
+            "--input",
+        ])
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let reports: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for part in reports[0]["parts"].as_array().unwrap() {
+        let peak = part["stats"]["peak_heap_bytes"].as_u64().unwrap() as usize;
+        assert!(
+            peak < LIMIT,
+            "part {} needed {peak} bytes for a {size} byte input",
+            part["part"]
+        );
+    }
+}